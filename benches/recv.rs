@@ -0,0 +1,63 @@
+use async_std::task::block_on;
+use async_zmq::{pull, push, Pull, Push, SinkExt, StreamExt};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Messages received per iteration.
+const MESSAGES: usize = 10_000;
+/// Messages queued before receiving, kept below the default high water mark of 1000 so the
+/// sender never blocks.
+const ROUND: usize = 500;
+
+fn sockets(uri: &str) -> (Push, Pull) {
+    let push = push(uri).unwrap().bind().unwrap();
+    let pull = pull(uri).unwrap().connect().unwrap();
+    (push, pull)
+}
+
+async fn fill(push: &mut Push) {
+    for _ in 0..ROUND {
+        push.send(vec!["market", "data"]).await.unwrap();
+    }
+}
+
+fn recv(c: &mut Criterion) {
+    let mut group = c.benchmark_group("recv");
+    group.throughput(Throughput::Elements(MESSAGES as u64));
+
+    let (mut push, mut pull) = sockets("inproc://bench_single");
+    group.bench_function("single", |b| {
+        b.iter(|| {
+            block_on(async {
+                for _ in 0..MESSAGES / ROUND {
+                    fill(&mut push).await;
+                    for _ in 0..ROUND {
+                        pull.next().await.unwrap().unwrap();
+                    }
+                }
+            })
+        })
+    });
+
+    for max in [16, 256] {
+        let (mut push, mut pull) = sockets(&format!("inproc://bench_batch_{}", max));
+        group.bench_with_input(BenchmarkId::new("batch", max), &max, |b, &max| {
+            b.iter(|| {
+                block_on(async {
+                    for _ in 0..MESSAGES / ROUND {
+                        fill(&mut push).await;
+                        let mut batches = pull.recv_batch(max);
+                        let mut received = 0;
+                        while received < ROUND {
+                            received += batches.next().await.unwrap().unwrap().len();
+                        }
+                    }
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, recv);
+criterion_main!(benches);
@@ -0,0 +1,224 @@
+//! Sockets whose type is only known at runtime
+//!
+//! Build an [`AnySocket`] with [`SocketBuilder::new`], passing the socket type as a value. It
+//! offers `send` and `recv` for every socket type and reports the ones a type does not support
+//! as [`AnySocketError::Unsupported`]. Everything specific to a socket type is available by
+//! matching on the variant, what all types share through [`ZmqSocketExt`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{AnySocket, Result, SocketBuilder, SocketType};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let socket_type = SocketType::PULL; // e.g. read from a config file
+//!     let mut zmq = SocketBuilder::new(socket_type, "tcp://127.0.0.1:5555").bind()?;
+//!
+//!     if let AnySocket::Subscribe(zmq) = &zmq {
+//!         zmq.set_subscribe("")?;
+//!     }
+//!     let msg = zmq.recv().await?;
+//!     println!("{:?}", msg.iter());
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`AnySocket`]: enum.AnySocket.html
+//! [`SocketBuilder::new`]: ../struct.SocketBuilder.html#method.new
+//! [`AnySocketError::Unsupported`]: ../errors/enum.AnySocketError.html#variant.Unsupported
+//! [`ZmqSocketExt`]: ../traits/trait.ZmqSocketExt.html
+
+use std::time::Duration;
+
+use zmq::SocketType;
+
+use crate::{
+    reactor::{AsRawSocket, ZmqSocket},
+    socket::socket_methods,
+    traits::{sealed::Sealed, ZmqSocketExt},
+    AnySocketError, CloseError, Dealer, IntoMultipart, Multipart, Operation, Pair, Publish, Pull,
+    Push, RecvError, RecvErrorKind, Reply, Request, Router, SinkExt, StreamExt, Subscribe,
+    XPublish, XSubscribe, ZmqStream,
+};
+
+/// A socket of any type.
+pub enum AnySocket {
+    /// A DEALER socket.
+    Dealer(Dealer),
+    /// A PAIR socket.
+    Pair(Pair),
+    /// A PUB socket.
+    Publish(Publish),
+    /// A PULL socket.
+    Pull(Pull),
+    /// A PUSH socket.
+    Push(Push),
+    /// A REP socket.
+    Reply(Reply),
+    /// A REQ socket.
+    Request(Request),
+    /// A ROUTER socket.
+    Router(Router),
+    /// A STREAM socket.
+    Stream(ZmqStream),
+    /// A SUB socket.
+    Subscribe(Subscribe),
+    /// An XPUB socket.
+    XPublish(XPublish),
+    /// An XSUB socket.
+    XSubscribe(XSubscribe),
+}
+
+impl AnySocket {
+    /// The type of the socket.
+    pub fn socket_type(&self) -> SocketType {
+        match self {
+            AnySocket::Dealer(_) => SocketType::DEALER,
+            AnySocket::Pair(_) => SocketType::PAIR,
+            AnySocket::Publish(_) => SocketType::PUB,
+            AnySocket::Pull(_) => SocketType::PULL,
+            AnySocket::Push(_) => SocketType::PUSH,
+            AnySocket::Reply(_) => SocketType::REP,
+            AnySocket::Request(_) => SocketType::REQ,
+            AnySocket::Router(_) => SocketType::ROUTER,
+            AnySocket::Stream(_) => SocketType::STREAM,
+            AnySocket::Subscribe(_) => SocketType::SUB,
+            AnySocket::XPublish(_) => SocketType::XPUB,
+            AnySocket::XSubscribe(_) => SocketType::XSUB,
+        }
+    }
+
+    socket_methods!();
+
+    /// Send a message, if the socket type supports sending.
+    pub async fn send<M: IntoMultipart>(&mut self, msg: M) -> Result<(), AnySocketError> {
+        match self {
+            AnySocket::Dealer(socket) => socket.send(msg).await?,
+            AnySocket::Pair(socket) => socket.send(msg).await?,
+            AnySocket::Publish(socket) => socket.send(msg).await?,
+            AnySocket::Push(socket) => socket.send(msg).await?,
+            AnySocket::Reply(socket) => socket.send(msg).await?,
+            AnySocket::Request(socket) => socket.send(msg).await?,
+            AnySocket::Router(socket) => socket.send(msg).await?,
+            AnySocket::Stream(socket) => socket.send(msg).await?,
+            AnySocket::XPublish(socket) => socket.send(msg).await?,
+            _ => return Err(self.unsupported(Operation::Send)),
+        }
+        Ok(())
+    }
+
+    /// Receive a message, if the socket type supports receiving.
+    pub async fn recv(&mut self) -> Result<Multipart, AnySocketError> {
+        Ok(match self {
+            AnySocket::Dealer(socket) => next(socket.next().await)?,
+            AnySocket::Pair(socket) => next(socket.next().await)?,
+            AnySocket::Pull(socket) => next(socket.next().await)?,
+            AnySocket::Reply(socket) => socket.recv().await?,
+            AnySocket::Request(socket) => socket.recv().await?,
+            AnySocket::Router(socket) => next(socket.next().await)?,
+            AnySocket::Stream(socket) => next(socket.next().await)?,
+            AnySocket::Subscribe(socket) => next(socket.next().await)?,
+            AnySocket::XPublish(socket) => next(socket.next().await)?,
+            AnySocket::XSubscribe(socket) => next(socket.next().await)?,
+            _ => return Err(self.unsupported(Operation::Recv)),
+        })
+    }
+
+    /// Close the socket, see the `close` method of the socket type.
+    ///
+    /// Returns how many queued messages had to be discarded, always zero for the socket types
+    /// that only receive.
+    pub async fn close(self, linger: Duration) -> Result<usize, CloseError> {
+        match self {
+            AnySocket::Dealer(socket) => socket.close(linger).await,
+            AnySocket::Pair(socket) => socket.close(linger).await,
+            AnySocket::Publish(socket) => socket.close(linger).await,
+            AnySocket::Pull(socket) => socket.close(linger).await.map(|()| 0),
+            AnySocket::Push(socket) => socket.close(linger).await,
+            AnySocket::Reply(socket) => socket.close(linger).await,
+            AnySocket::Request(socket) => socket.close(linger).await,
+            AnySocket::Router(socket) => socket.close(linger).await,
+            AnySocket::Stream(socket) => socket.close(linger).await,
+            AnySocket::Subscribe(socket) => socket.close(linger).await.map(|()| 0),
+            AnySocket::XPublish(socket) => socket.close(linger).await,
+            AnySocket::XSubscribe(socket) => socket.close(linger).await.map(|()| 0),
+        }
+    }
+
+    fn unsupported(&self, operation: Operation) -> AnySocketError {
+        AnySocketError::Unsupported {
+            socket_type: self.socket_type(),
+            operation,
+        }
+    }
+}
+
+/// Unwrap the next item of a socket stream, which only ends once the context was terminated.
+fn next<E>(item: Option<Result<Multipart, E>>) -> Result<Multipart, E>
+where
+    E: From<RecvError>,
+{
+    item.unwrap_or(Err(RecvError::from(RecvErrorKind::ContextTerminated).into()))
+}
+
+impl ZmqSocketExt for AnySocket {
+    fn socket_type(&self) -> SocketType {
+        AnySocket::socket_type(self)
+    }
+}
+
+impl Sealed for AnySocket {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        match self {
+            AnySocket::Dealer(socket) => socket.zmq_socket(),
+            AnySocket::Pair(socket) => socket.zmq_socket(),
+            AnySocket::Publish(socket) => socket.zmq_socket(),
+            AnySocket::Pull(socket) => socket.zmq_socket(),
+            AnySocket::Push(socket) => socket.zmq_socket(),
+            AnySocket::Reply(socket) => socket.zmq_socket(),
+            AnySocket::Request(socket) => socket.zmq_socket(),
+            AnySocket::Router(socket) => socket.zmq_socket(),
+            AnySocket::Stream(socket) => socket.zmq_socket(),
+            AnySocket::Subscribe(socket) => socket.zmq_socket(),
+            AnySocket::XPublish(socket) => socket.zmq_socket(),
+            AnySocket::XSubscribe(socket) => socket.zmq_socket(),
+        }
+    }
+}
+
+impl From<ZmqSocket> for AnySocket {
+    fn from(socket: ZmqSocket) -> Self {
+        let socket_type = socket
+            .as_socket()
+            .get_socket_type()
+            .expect("ØMQ always knows the type of a live socket");
+        match socket_type {
+            SocketType::DEALER => AnySocket::Dealer(socket.into()),
+            SocketType::PAIR => AnySocket::Pair(socket.into()),
+            SocketType::PUB => AnySocket::Publish(socket.into()),
+            SocketType::PULL => AnySocket::Pull(socket.into()),
+            SocketType::PUSH => AnySocket::Push(socket.into()),
+            SocketType::REP => AnySocket::Reply(socket.into()),
+            SocketType::REQ => AnySocket::Request(socket.into()),
+            SocketType::ROUTER => AnySocket::Router(socket.into()),
+            SocketType::STREAM => AnySocket::Stream(socket.into()),
+            SocketType::SUB => AnySocket::Subscribe(socket.into()),
+            SocketType::XPUB => AnySocket::XPublish(socket.into()),
+            SocketType::XSUB => AnySocket::XSubscribe(socket.into()),
+        }
+    }
+}
+
+impl From<zmq::Socket> for AnySocket {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
+    fn from(socket: zmq::Socket) -> Self {
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
+    }
+}
@@ -0,0 +1,192 @@
+//! Reusable receive buffers
+//!
+//! Receiving through [`Stream`] allocates a new [`Multipart`] for every message. Hot receive
+//! loops can avoid that by handing a buffer to the `recv_into` method of a socket, which reuses
+//! the frames the buffer already holds.
+//!
+//! A [`MultipartPool`] keeps such buffers around once they are dropped, so they can be passed
+//! on to other tasks and still be recycled afterwards. Only the `Vec` holding the frames is
+//! pooled: the frames themselves are dropped when a buffer goes back to its pool, so the pool
+//! saves the allocation of the `Vec` but not the one of each frame.
+//!
+//! With the `bytes` feature enabled, received frames can be turned into [`Bytes`] without
+//! copying their payload, see [`IntoBytes`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{buf::MultipartPool, Result};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let mut zmq = async_zmq::subscribe("tcp://127.0.0.1:5555")?.connect()?;
+//!     zmq.set_subscribe("")?;
+//!
+//!     let pool = MultipartPool::new(64);
+//!     loop {
+//!         let mut msg = pool.get();
+//!         zmq.recv_into(&mut msg).await?;
+//!         println!("{:?}", msg.iter());
+//!     }
+//! }
+//! ```
+//!
+//! [`Stream`]: ../trait.Stream.html
+//! [`Multipart`]: ../type.Multipart.html
+//! [`MultipartPool`]: struct.MultipartPool.html
+//! [`Bytes`]: https://docs.rs/bytes/1/bytes/struct.Bytes.html
+//! [`IntoBytes`]: trait.IntoBytes.html
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use crate::socket::Multipart;
+
+/// A pool of [`MultipartBuf`]s.
+///
+/// The pool recycles the `Vec` of a buffer, not its frames, see the
+/// [module documentation](index.html).
+///
+/// Pools are cheap to clone and every clone refers to the same set of buffers.
+///
+/// [`MultipartBuf`]: struct.MultipartBuf.html
+#[derive(Clone, Debug)]
+pub struct MultipartPool(Arc<Shared>);
+
+#[derive(Debug)]
+struct Shared {
+    /// Buffers waiting to be handed out again.
+    idle: Mutex<Vec<Multipart>>,
+    /// How many idle buffers the pool holds on to at most.
+    capacity: usize,
+}
+
+impl MultipartPool {
+    /// Create a pool that keeps at most `capacity` idle buffers around.
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Shared {
+            idle: Mutex::new(Vec::with_capacity(capacity)),
+            capacity,
+        }))
+    }
+
+    /// Take an empty buffer out of the pool, allocating a new one if none is idle.
+    ///
+    /// A recycled buffer holds no frames but keeps the capacity of its `Vec`.
+    pub fn get(&self) -> MultipartBuf {
+        let frames = self.0.idle.lock().unwrap().pop().unwrap_or_default();
+        MultipartBuf {
+            frames,
+            pool: Some(self.clone()),
+        }
+    }
+
+    /// The number of idle buffers currently held by the pool.
+    pub fn idle(&self) -> usize {
+        self.0.idle.lock().unwrap().len()
+    }
+
+    fn put(&self, mut frames: Multipart) {
+        // Only the `Vec` is pooled. Drop the frames right away, holding on to their payload
+        // would pin memory owned by ØMQ for as long as the buffer sits idle.
+        frames.clear();
+        let mut idle = self.0.idle.lock().unwrap();
+        if idle.len() < self.0.capacity {
+            idle.push(frames);
+        }
+    }
+}
+
+impl Default for MultipartPool {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+/// A [`Multipart`] that goes back to its [`MultipartPool`] when dropped.
+///
+/// It dereferences to a [`Multipart`], so it can be passed to `recv_into` and read like any
+/// received message.
+///
+/// [`Multipart`]: ../type.Multipart.html
+/// [`MultipartPool`]: struct.MultipartPool.html
+#[derive(Debug, Default)]
+pub struct MultipartBuf {
+    frames: Multipart,
+    pool: Option<MultipartPool>,
+}
+
+impl MultipartBuf {
+    /// Create a buffer that does not belong to any pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the frames out of the buffer. The buffer's allocation is not returned to its pool.
+    pub fn into_inner(mut self) -> Multipart {
+        self.pool = None;
+        std::mem::take(&mut self.frames)
+    }
+
+    /// Move every frame out of the buffer as [`Bytes`], without copying their payload.
+    ///
+    /// The buffer is left empty but keeps its allocation for the next message.
+    ///
+    /// [`Bytes`]: https://docs.rs/bytes/1/bytes/struct.Bytes.html
+    #[cfg(feature = "bytes")]
+    pub fn drain_bytes(&mut self) -> impl Iterator<Item = bytes::Bytes> + '_ {
+        self.frames.drain(..).map(IntoBytes::into_bytes)
+    }
+}
+
+impl Deref for MultipartBuf {
+    type Target = Multipart;
+
+    fn deref(&self) -> &Multipart {
+        &self.frames
+    }
+}
+
+impl DerefMut for MultipartBuf {
+    fn deref_mut(&mut self) -> &mut Multipart {
+        &mut self.frames
+    }
+}
+
+impl Drop for MultipartBuf {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool.take() {
+            pool.put(std::mem::take(&mut self.frames));
+        }
+    }
+}
+
+/// Conversion of a received frame into [`Bytes`] that keeps using the memory ØMQ received it
+/// into.
+///
+/// The frame is released once the last `Bytes` referring to it is dropped.
+///
+/// [`Bytes`]: https://docs.rs/bytes/1/bytes/struct.Bytes.html
+#[cfg(feature = "bytes")]
+pub trait IntoBytes {
+    /// Turn the frame into `Bytes` without copying its payload.
+    fn into_bytes(self) -> bytes::Bytes;
+}
+
+#[cfg(feature = "bytes")]
+impl IntoBytes for zmq::Message {
+    fn into_bytes(self) -> bytes::Bytes {
+        bytes::Bytes::from_owner(Frame(self))
+    }
+}
+
+/// Owner handed to `Bytes`, exposing the payload of a frame.
+#[cfg(feature = "bytes")]
+struct Frame(zmq::Message);
+
+#[cfg(feature = "bytes")]
+impl AsRef<[u8]> for Frame {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
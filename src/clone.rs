@@ -0,0 +1,321 @@
+//! Clone pattern: a key-value store shared through snapshots and updates
+//!
+//! This is the reliable state distribution of the ØMQ guide's "Clone" pattern. A
+//! [`CloneServer`] owns the store and assigns every change a sequence number. A [`CloneClient`]
+//! subscribes to the changes, fetches a snapshot of the store and then applies the changes in
+//! order, noticing when one was lost on the way.
+//!
+//! The server listens on three sockets:
+//!
+//! - a [`Router`] answering snapshot requests, one entry per message followed by the sequence
+//!   number the snapshot was taken at,
+//! - a [`Publish`] socket broadcasting every change with its sequence number,
+//! - a [`Pull`] socket collecting changes sent by the clients.
+//!
+//! Every entry travels as a key frame, an 8 byte big-endian sequence number and a body. An
+//! empty body deletes the key.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{clone::{CloneClient, CloneServer}, Result};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let server = CloneServer::new(
+//!         async_zmq::router("tcp://127.0.0.1:5556")?.bind()?,
+//!         async_zmq::publish("tcp://127.0.0.1:5557")?.bind()?,
+//!         async_zmq::pull("tcp://127.0.0.1:5558")?.bind()?,
+//!     );
+//!     async_std::task::spawn(server.run());
+//!
+//!     let mut client = CloneClient::connect(
+//!         async_zmq::dealer("tcp://127.0.0.1:5556")?.connect()?,
+//!         async_zmq::subscribe("tcp://127.0.0.1:5557")?.connect()?,
+//!         async_zmq::push("tcp://127.0.0.1:5558")?.connect()?,
+//!     )
+//!     .await?;
+//!
+//!     client.set("config/timeout", "30").await?;
+//!     loop {
+//!         let update = client.recv_update().await?;
+//!         println!("{} = {:?}", update.key, client.get(&update.key));
+//!     }
+//! }
+//! ```
+//!
+//! [`CloneServer`]: struct.CloneServer.html
+//! [`CloneClient`]: struct.CloneClient.html
+//! [`Router`]: ../router/struct.Router.html
+//! [`Publish`]: ../publish/struct.Publish.html
+//! [`Pull`]: ../pull/struct.Pull.html
+
+use std::collections::BTreeMap;
+
+use futures::future::{select, Either};
+use zmq::Message;
+
+use crate::{
+    CloneError, Dealer, Multipart, Publish, Pull, Push, RecvError, RecvErrorKind, Router, SinkExt,
+    StreamExt, Subscribe,
+};
+
+/// Request for a snapshot, followed by the key prefix to fetch, empty for the whole store.
+const SNAPSHOT_REQUEST: &str = "ICANHAZ?";
+/// Last message of a snapshot, followed by its sequence number and the key prefix.
+const SNAPSHOT_END: &str = "KTHXBAI";
+
+/// An entry of the store, as it is sent over the wire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KvMsg {
+    /// The key of the entry.
+    pub key: String,
+    /// The sequence number the server assigned to the change, zero for changes sent by a
+    /// client.
+    pub sequence: u64,
+    /// The value of the entry, empty if the key was deleted.
+    pub body: Vec<u8>,
+}
+
+impl KvMsg {
+    fn to_multipart(&self) -> Multipart {
+        vec![
+            self.key.as_str().into(),
+            self.sequence.to_be_bytes()[..].into(),
+            self.body[..].into(),
+        ]
+    }
+
+    fn parse(frames: &[Message]) -> Option<Self> {
+        match frames {
+            [key, sequence, body] => Some(Self {
+                key: key.as_str()?.to_owned(),
+                sequence: parse_sequence(sequence)?,
+                body: body.to_vec(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+fn parse_sequence(frame: &[u8]) -> Option<u64> {
+    Some(u64::from_be_bytes(frame.try_into().ok()?))
+}
+
+/// The server side of the clone pattern, see the [module documentation].
+///
+/// [module documentation]: index.html
+pub struct CloneServer {
+    snapshot: Router,
+    publisher: Publish,
+    collector: Pull,
+    store: BTreeMap<String, KvMsg>,
+    sequence: u64,
+}
+
+impl CloneServer {
+    /// Create a server with an empty store, answering snapshot requests on `snapshot`,
+    /// publishing changes on `publisher` and collecting them on `collector`.
+    pub fn new(snapshot: Router, publisher: Publish, collector: Pull) -> Self {
+        Self {
+            snapshot,
+            publisher,
+            collector,
+            store: BTreeMap::new(),
+            sequence: 0,
+        }
+    }
+
+    /// Serve snapshots and publish collected changes until one of the sockets fails.
+    ///
+    /// Malformed requests and changes are ignored.
+    pub async fn run(mut self) -> Result<(), CloneError> {
+        loop {
+            let next = match select(self.snapshot.next(), self.collector.next()).await {
+                Either::Left((msg, _)) => Either::Left(msg),
+                Either::Right((msg, _)) => Either::Right(msg),
+            };
+            match next {
+                Either::Left(Some(msg)) => self.send_snapshot(msg?).await?,
+                Either::Right(Some(msg)) => self.publish(msg?).await?,
+                // The context was terminated.
+                Either::Left(None) | Either::Right(None) => return Ok(()),
+            }
+        }
+    }
+
+    async fn send_snapshot(&mut self, request: Multipart) -> Result<(), CloneError> {
+        let (identity, subtree) = match &request[..] {
+            [identity, command, subtree] if command.as_str() == Some(SNAPSHOT_REQUEST) => {
+                (identity, subtree)
+            }
+            _ => return Ok(()),
+        };
+
+        let entries = self
+            .store
+            .values()
+            .filter(|kv| kv.key.as_bytes().starts_with(subtree));
+        for kv in entries {
+            let mut msg = vec![Message::from(&identity[..])];
+            msg.extend(kv.to_multipart());
+            self.snapshot.feed(msg).await?;
+        }
+        let end = vec![
+            Message::from(&identity[..]),
+            SNAPSHOT_END.into(),
+            self.sequence.to_be_bytes()[..].into(),
+            Message::from(&subtree[..]),
+        ];
+        self.snapshot.feed(end).await?;
+        Ok(self.snapshot.flush().await?)
+    }
+
+    async fn publish(&mut self, msg: Multipart) -> Result<(), CloneError> {
+        let mut kv = match KvMsg::parse(&msg) {
+            Some(kv) => kv,
+            None => return Ok(()),
+        };
+        self.sequence += 1;
+        kv.sequence = self.sequence;
+
+        self.publisher.send(kv.to_multipart()).await?;
+        if kv.body.is_empty() {
+            self.store.remove(&kv.key);
+        } else {
+            self.store.insert(kv.key.clone(), kv);
+        }
+        Ok(())
+    }
+}
+
+/// The client side of the clone pattern, see the [module documentation].
+///
+/// [module documentation]: index.html
+pub struct CloneClient {
+    snapshot: Dealer,
+    updates: Subscribe,
+    collector: Push,
+    store: BTreeMap<String, KvMsg>,
+    sequence: u64,
+}
+
+impl CloneClient {
+    /// Fetch the store through `snapshot` and subscribe to its changes on `updates`. Changes
+    /// made through [`set`] are sent to `collector`.
+    ///
+    /// [`set`]: #method.set
+    pub async fn connect(
+        snapshot: Dealer,
+        updates: Subscribe,
+        collector: Push,
+    ) -> Result<Self, CloneError> {
+        // Subscribe first, so no change made after the snapshot was taken is missed. Every
+        // change is needed to notice gaps in the sequence numbers.
+        updates.set_subscribe("")?;
+        let mut client = Self {
+            snapshot,
+            updates,
+            collector,
+            store: BTreeMap::new(),
+            sequence: 0,
+        };
+        client.resync().await?;
+        Ok(client)
+    }
+
+    /// Fetch a new snapshot, replacing the store.
+    ///
+    /// Needed after [`recv_update`] reported a gap.
+    ///
+    /// [`recv_update`]: #method.recv_update
+    pub async fn resync(&mut self) -> Result<(), CloneError> {
+        let request = vec![SNAPSHOT_REQUEST, ""];
+        self.snapshot.send(request).await?;
+
+        self.store.clear();
+        loop {
+            let msg = self
+                .snapshot
+                .next()
+                .await
+                .ok_or(RecvError::from(RecvErrorKind::ContextTerminated))??;
+            match &msg[..] {
+                [command, sequence, _] if command.as_str() == Some(SNAPSHOT_END) => {
+                    self.sequence = parse_sequence(sequence).ok_or(CloneError::Malformed)?;
+                    return Ok(());
+                }
+                _ => {
+                    let kv = KvMsg::parse(&msg).ok_or(CloneError::Malformed)?;
+                    self.store.insert(kv.key.clone(), kv);
+                }
+            }
+        }
+    }
+
+    /// The value of `key`, if it is in the store.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.store.get(key).map(|kv| &kv.body[..])
+    }
+
+    /// Every entry of the store, ordered by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.store
+            .values()
+            .map(|kv| (kv.key.as_str(), &kv.body[..]))
+    }
+
+    /// The sequence number of the last change applied to the store.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Ask the server to set `key` to `body`, or to delete it if `body` is empty.
+    ///
+    /// The store only changes once the server published the change, see [`recv_update`].
+    ///
+    /// [`recv_update`]: #method.recv_update
+    pub async fn set(&mut self, key: &str, body: impl Into<Vec<u8>>) -> Result<(), CloneError> {
+        let kv = KvMsg {
+            key: key.to_owned(),
+            sequence: 0,
+            body: body.into(),
+        };
+        Ok(self.collector.send(kv.to_multipart()).await?)
+    }
+
+    /// Wait for the next change and apply it to the store.
+    ///
+    /// Changes the snapshot already contained are skipped. If a change went missing, this
+    /// returns [`CloneError::Gap`] without touching the store, call [`resync`] to recover.
+    ///
+    /// [`CloneError::Gap`]: ../errors/enum.CloneError.html#variant.Gap
+    /// [`resync`]: #method.resync
+    pub async fn recv_update(&mut self) -> Result<KvMsg, CloneError> {
+        loop {
+            let msg = self
+                .updates
+                .next()
+                .await
+                .ok_or(RecvError::from(RecvErrorKind::ContextTerminated))??;
+            let kv = KvMsg::parse(&msg).ok_or(CloneError::Malformed)?;
+            if kv.sequence <= self.sequence {
+                continue;
+            }
+            if kv.sequence != self.sequence + 1 {
+                return Err(CloneError::Gap {
+                    expected: self.sequence + 1,
+                    received: kv.sequence,
+                });
+            }
+
+            self.sequence = kv.sequence;
+            if kv.body.is_empty() {
+                self.store.remove(&kv.key);
+            } else {
+                self.store.insert(kv.key.clone(), kv.clone());
+            }
+            return Ok(kv);
+        }
+    }
+}
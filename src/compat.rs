@@ -0,0 +1,223 @@
+//! Generic socket types of earlier releases
+//!
+//! Sending socket types used to be generic over the iterator of frames they accept, e.g.
+//! `Dealer<I, T>`, and their `Sink` only took a [`MultipartIter`]. They are non-generic now and
+//! accept anything implementing [`IntoMultipart`].
+//!
+//! This module keeps the old names and builder functions around, so existing code compiles by
+//! importing them from here instead of the crate root. Each type wraps its non-generic
+//! counterpart, which it dereferences to, and only takes a [`MultipartIter`] through its
+//! `Sink`, so `send(vec![...].into())` still infers the item type.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{compat, Result, SinkExt};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let mut zmq = compat::push("tcp://127.0.0.1:5555")?.bind()?;
+//!
+//!     zmq.send(vec!["topic", "broadcast message"].into()).await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`MultipartIter`]: ../struct.MultipartIter.html
+//! [`IntoMultipart`]: ../trait.IntoMultipart.html
+
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::stream::FusedStream;
+use zmq::{Message, SocketType};
+
+use crate::{
+    reactor::ZmqSocket, MultipartIter, RequestReplyError, Sink, SocketBuilder, SocketError, Stream,
+};
+
+/// A socket that only sends [`MultipartIter<I, T>`], like the generic socket types of earlier
+/// releases did.
+///
+/// [`MultipartIter<I, T>`]: ../struct.MultipartIter.html
+pub struct Compat<S, I, T> {
+    socket: S,
+    _phantom: PhantomData<fn(I) -> T>,
+}
+
+impl<S, I, T> Compat<S, I, T> {
+    /// Unwrap the non-generic socket.
+    pub fn into_inner(self) -> S {
+        self.socket
+    }
+}
+
+impl<S, I, T> Deref for Compat<S, I, T> {
+    type Target = S;
+
+    fn deref(&self) -> &S {
+        &self.socket
+    }
+}
+
+impl<S, I, T> DerefMut for Compat<S, I, T> {
+    fn deref_mut(&mut self) -> &mut S {
+        &mut self.socket
+    }
+}
+
+impl<S, I, T> Sink<MultipartIter<I, T>> for Compat<S, I, T>
+where
+    S: Sink<MultipartIter<I, T>> + Unpin,
+    I: Iterator<Item = T>,
+    T: Into<Message>,
+{
+    type Error = S::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().socket).poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().socket).start_send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().socket).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.get_mut().socket).poll_close(cx)
+    }
+}
+
+impl<S: Stream + Unpin, I, T> Stream for Compat<S, I, T> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.get_mut().socket).poll_next(cx)
+    }
+}
+
+impl<S: FusedStream + Unpin, I, T> FusedStream for Compat<S, I, T> {
+    fn is_terminated(&self) -> bool {
+        self.socket.is_terminated()
+    }
+}
+
+impl<S: From<ZmqSocket>, I, T> From<ZmqSocket> for Compat<S, I, T> {
+    fn from(socket: ZmqSocket) -> Self {
+        Self {
+            socket: socket.into(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<S: From<zmq::Socket>, I, T> From<zmq::Socket> for Compat<S, I, T> {
+    fn from(socket: zmq::Socket) -> Self {
+        Self {
+            socket: socket.into(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<I: Iterator<Item = T>, T: Into<Message>> Compat<crate::Request, I, T> {
+    /// Send request to REP/ROUTER socket, see [`Request::send`].
+    ///
+    /// [`Request::send`]: ../request/struct.Request.html#method.send
+    pub async fn send<S: Into<MultipartIter<I, T>>>(
+        &self,
+        msg: S,
+    ) -> Result<(), RequestReplyError> {
+        self.socket.send(msg.into()).await
+    }
+}
+
+impl<I: Iterator<Item = T>, T: Into<Message>> Compat<crate::Reply, I, T> {
+    /// Send reply to REQ/DEALER socket, see [`Reply::send`].
+    ///
+    /// [`Reply::send`]: ../reply/struct.Reply.html#method.send
+    pub async fn send<S: Into<MultipartIter<I, T>>>(
+        &self,
+        msg: S,
+    ) -> Result<(), RequestReplyError> {
+        self.socket.send(msg.into()).await
+    }
+}
+
+/// Generic DEALER socket, see [`Dealer`](../dealer/struct.Dealer.html).
+pub type Dealer<I, T> = Compat<crate::Dealer, I, T>;
+/// Generic PAIR socket, see [`Pair`](../pair/struct.Pair.html).
+pub type Pair<I, T> = Compat<crate::Pair, I, T>;
+/// Generic PUB socket, see [`Publish`](../publish/struct.Publish.html).
+pub type Publish<I, T> = Compat<crate::Publish, I, T>;
+/// Generic PUSH socket, see [`Push`](../push/struct.Push.html).
+pub type Push<I, T> = Compat<crate::Push, I, T>;
+/// Generic REP socket, see [`Reply`](../reply/struct.Reply.html).
+pub type Reply<I, T> = Compat<crate::Reply, I, T>;
+/// Generic REQ socket, see [`Request`](../request/struct.Request.html).
+pub type Request<I, T> = Compat<crate::Request, I, T>;
+/// Generic ROUTER socket, see [`Router`](../router/struct.Router.html).
+pub type Router<I, T> = Compat<crate::Router, I, T>;
+/// Generic XPUB socket, see [`XPublish`](../xpublish/struct.XPublish.html).
+pub type XPublish<I, T> = Compat<crate::XPublish, I, T>;
+
+/// Create a generic ZMQ socket with DEALER type
+pub fn dealer<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, Dealer<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::DEALER, endpoint))
+}
+
+/// Create a generic ZMQ socket with PAIR type
+pub fn pair<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, Pair<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::PAIR, endpoint))
+}
+
+/// Create a generic ZMQ socket with PUB type
+pub fn publish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, Publish<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::PUB, endpoint))
+}
+
+/// Create a generic ZMQ socket with PUSH type
+pub fn push<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, Push<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::PUSH, endpoint))
+}
+
+/// Create a generic ZMQ socket with REP type
+pub fn reply<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, Reply<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::REP, endpoint))
+}
+
+/// Create a generic ZMQ socket with REQ type
+pub fn request<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, Request<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::REQ, endpoint))
+}
+
+/// Create a generic ZMQ socket with ROUTER type
+pub fn router<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, Router<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::ROUTER, endpoint))
+}
+
+/// Create a generic ZMQ socket with XPUB type
+pub fn xpublish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
+    endpoint: &str,
+) -> Result<SocketBuilder<'_, XPublish<I, T>>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::XPUB, endpoint))
+}
@@ -0,0 +1,365 @@
+//! Context lifecycle management
+//!
+//! Every socket lives in a ØMQ context, which owns the I/O threads doing the actual network
+//! work. Sockets can only talk over `inproc://` endpoints when they share a context.
+//!
+//! A [`ContextHandle`] wraps a context and keeps track of the sockets built from it, so the
+//! whole set can be shut down together with [`shutdown`]. Sockets built without
+//! [`with_context`] or [`with_handle`] use a crate-wide default handle, see [`global`]. Its
+//! options can be chosen once, before the first socket is built, with [`set_default`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{ContextHandle, Result, SinkExt};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     ContextHandle::builder()
+//!         .io_threads(2)
+//!         .ipv6(true)
+//!         .build()?
+//!         .set_default()
+//!         .ok();
+//!
+//!     let mut zmq = async_zmq::push("inproc://jobs")?.bind()?;
+//!     zmq.send(vec!["job"]).await?;
+//!     drop(zmq);
+//!
+//!     ContextHandle::global().shutdown().await;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Receiving streams end once their socket is interrupted this way, so loops like
+//! `while let Some(msg) = socket.next().await` finish on their own. Tasks that wait on other
+//! things can watch for the shutdown with [`shutdown_signal`], e.g. through
+//! `StreamExt::take_until`.
+//!
+//! [`ContextHandle`]: struct.ContextHandle.html
+//! [`shutdown_signal`]: struct.ContextHandle.html#method.shutdown_signal
+//! [`shutdown`]: struct.ContextHandle.html#method.shutdown
+//! [`global`]: struct.ContextHandle.html#method.global
+//! [`set_default`]: struct.ContextHandle.html#method.set_default
+//! [`with_context`]: ../struct.SocketBuilder.html#method.with_context
+//! [`with_handle`]: ../struct.SocketBuilder.html#method.with_handle
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+use futures::{
+    channel::oneshot,
+    future::{poll_fn, select},
+};
+use futures_timer::Delay;
+use once_cell::sync::OnceCell;
+use slab::Slab;
+use zmq::{Error, SocketType};
+
+use crate::{reactor::Interrupter, SocketError};
+
+/// The crate-wide default context handle.
+static DEFAULT: OnceCell<ContextHandle> = OnceCell::new();
+
+/// Builder for a [`ContextHandle`].
+///
+/// [`ContextHandle`]: struct.ContextHandle.html
+#[derive(Clone, Debug, Default)]
+pub struct ContextBuilder {
+    io_threads: Option<i32>,
+    max_sockets: Option<usize>,
+    ipv6: bool,
+    shutdown_timeout: Option<Duration>,
+}
+
+impl ContextBuilder {
+    /// Set the number of ØMQ I/O threads. ØMQ uses one unless told otherwise.
+    pub fn io_threads(self, io_threads: i32) -> Self {
+        Self {
+            io_threads: Some(io_threads),
+            ..self
+        }
+    }
+
+    /// Limit the number of sockets the handle builds at the same time.
+    ///
+    /// Building a socket beyond the limit fails with `EMFILE`.
+    pub fn max_sockets(self, max_sockets: usize) -> Self {
+        Self {
+            max_sockets: Some(max_sockets),
+            ..self
+        }
+    }
+
+    /// Enable IPv6 on every socket the handle builds.
+    pub fn ipv6(self, ipv6: bool) -> Self {
+        Self { ipv6, ..self }
+    }
+
+    /// Limit how long [`shutdown`] waits for the sockets of the handle to be closed, 30
+    /// seconds by default.
+    ///
+    /// [`shutdown`]: struct.ContextHandle.html#method.shutdown
+    pub fn shutdown_timeout(self, shutdown_timeout: Duration) -> Self {
+        Self {
+            shutdown_timeout: Some(shutdown_timeout),
+            ..self
+        }
+    }
+
+    /// Create the context and its handle.
+    pub fn build(self) -> Result<ContextHandle, SocketError> {
+        let context = zmq::Context::new();
+        if let Some(io_threads) = self.io_threads {
+            context.set_io_threads(io_threads)?;
+        }
+
+        Ok(ContextHandle(Arc::new(Shared {
+            context: Mutex::new(Some(context)),
+            max_sockets: self.max_sockets,
+            ipv6: self.ipv6,
+            shutdown_timeout: self.shutdown_timeout.unwrap_or(Duration::from_secs(30)),
+            closed: AtomicBool::new(false),
+            sockets: Mutex::new(Sockets {
+                entries: Slab::new(),
+                idle: Vec::new(),
+            }),
+            signals: Mutex::new(Vec::new()),
+        })))
+    }
+}
+
+/// State shared between a handle, its clones and the registrations of its sockets.
+struct Shared {
+    /// The context itself, taken out once the handle is shut down.
+    context: Mutex<Option<zmq::Context>>,
+    max_sockets: Option<usize>,
+    ipv6: bool,
+    /// How long shutdown waits for the sockets to be closed.
+    shutdown_timeout: Duration,
+    /// Set once shutdown started; no new sockets are built from then on.
+    closed: AtomicBool,
+    sockets: Mutex<Sockets>,
+    /// Tasks waiting for shutdown to start.
+    signals: Mutex<Vec<Waker>>,
+}
+
+/// The sockets currently alive in a context.
+struct Sockets {
+    /// One entry per socket. The interrupter is attached right after the socket was
+    /// registered in the reactor.
+    entries: Slab<Option<Interrupter>>,
+    /// Tasks waiting for the last socket to be closed.
+    idle: Vec<Waker>,
+}
+
+/// A ØMQ context together with the sockets built from it.
+///
+/// Handles are cheap to clone and every clone refers to the same context.
+#[derive(Clone)]
+pub struct ContextHandle(Arc<Shared>);
+
+impl ContextHandle {
+    /// Create a builder for a new context handle.
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::default()
+    }
+
+    /// Create a handle for a new context with default options.
+    pub fn new() -> Self {
+        ContextBuilder::default()
+            .build()
+            .expect("a context without options can always be built")
+    }
+
+    /// The crate-wide default handle, used by sockets that are not given a context.
+    ///
+    /// It is created with default options on first use unless [`set_default`] was called
+    /// before.
+    ///
+    /// [`set_default`]: #method.set_default
+    pub fn global() -> &'static ContextHandle {
+        DEFAULT.get_or_init(ContextHandle::new)
+    }
+
+    /// Make this handle the crate-wide default.
+    ///
+    /// This only works before the default was first used. Otherwise the handle is given back.
+    pub fn set_default(self) -> Result<(), ContextHandle> {
+        DEFAULT.set(self)
+    }
+
+    /// The underlying zmq context, or `None` once the handle was shut down.
+    ///
+    /// Sockets created directly from it are not tracked by the handle.
+    pub fn context(&self) -> Option<zmq::Context> {
+        self.0.context.lock().unwrap().clone()
+    }
+
+    /// The number of sockets built from this handle that are still open.
+    pub fn sockets(&self) -> usize {
+        self.0.sockets.lock().unwrap().entries.len()
+    }
+
+    /// Whether [`shutdown`](#method.shutdown) was called on this handle or one of its clones.
+    pub fn is_shutdown(&self) -> bool {
+        self.0.closed.load(Ordering::Acquire)
+    }
+
+    /// A future that completes once [`shutdown`](#method.shutdown) was called on this handle
+    /// or one of its clones.
+    pub fn shutdown_signal(&self) -> ShutdownSignal {
+        ShutdownSignal(self.0.clone())
+    }
+
+    /// Shut the context down.
+    ///
+    /// Every socket built from this handle is interrupted: pending and further operations on
+    /// it fail with `ETERM`, which lets the tasks owning them notice and drop them. Once the
+    /// last of them is closed the context is terminated on a separate thread, since ØMQ
+    /// blocks there until queued messages are delivered or dropped according to each
+    /// socket's linger.
+    ///
+    /// A socket can only be closed by whoever owns it, so one that is never dropped would
+    /// keep the context alive forever. Shutdown therefore waits at most for the
+    /// [`shutdown_timeout`] of the handle and then lets go of the context anyway. It stays
+    /// interrupted and is terminated as soon as the remaining sockets are dropped.
+    ///
+    /// Building sockets from the handle fails with `ETERM` from now on.
+    ///
+    /// [`shutdown_timeout`]: struct.ContextBuilder.html#method.shutdown_timeout
+    pub async fn shutdown(&self) {
+        self.0.closed.store(true, Ordering::Release);
+        for w in self.0.signals.lock().unwrap().drain(..) {
+            w.wake();
+        }
+
+        for (_, interrupter) in self.0.sockets.lock().unwrap().entries.iter() {
+            if let Some(interrupter) = interrupter {
+                interrupter.interrupt();
+            }
+        }
+
+        let closed = poll_fn(|cx| {
+            let mut sockets = self.0.sockets.lock().unwrap();
+            if sockets.entries.is_empty() {
+                Poll::Ready(())
+            } else {
+                if sockets.idle.iter().all(|w| !w.will_wake(cx.waker())) {
+                    sockets.idle.push(cx.waker().clone());
+                }
+                Poll::Pending
+            }
+        });
+        select(closed, Delay::new(self.0.shutdown_timeout)).await;
+
+        let context = self.0.context.lock().unwrap().take();
+        if let Some(context) = context {
+            let (tx, rx) = oneshot::channel();
+            thread::spawn(move || {
+                drop(context);
+                let _ = tx.send(());
+            });
+            let _ = rx.await;
+        }
+    }
+
+    /// Create a socket in this context and register it with the handle.
+    pub(crate) fn socket(
+        &self,
+        socket_type: SocketType,
+    ) -> Result<(zmq::Socket, zmq::Context, Registration), Error> {
+        if self.is_shutdown() {
+            return Err(Error::ETERM);
+        }
+        let context = self.context().ok_or(Error::ETERM)?;
+
+        let key = {
+            let mut sockets = self.0.sockets.lock().unwrap();
+            if let Some(max_sockets) = self.0.max_sockets {
+                if sockets.entries.len() >= max_sockets {
+                    return Err(Error::EMFILE);
+                }
+            }
+            sockets.entries.insert(None)
+        };
+        let registration = Registration {
+            shared: self.0.clone(),
+            key,
+        };
+
+        let socket = context.socket(socket_type)?;
+        if self.0.ipv6 {
+            socket.set_ipv6(true)?;
+        }
+
+        Ok((socket, context, registration))
+    }
+}
+
+impl Default for ContextHandle {
+    fn default() -> Self {
+        ContextHandle::new()
+    }
+}
+
+/// Future returned by [`ContextHandle::shutdown_signal`].
+///
+/// [`ContextHandle::shutdown_signal`]: struct.ContextHandle.html#method.shutdown_signal
+pub struct ShutdownSignal(Arc<Shared>);
+
+impl Future for ShutdownSignal {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        // Check the flag while holding the lock, so a concurrent shutdown either sees the
+        // waker or we see the flag.
+        let mut signals = self.0.signals.lock().unwrap();
+        if self.0.closed.load(Ordering::Acquire) {
+            return Poll::Ready(());
+        }
+        if signals.iter().all(|w| !w.will_wake(cx.waker())) {
+            signals.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// Ties a socket to the handle it was built from. Dropping it unregisters the socket.
+pub(crate) struct Registration {
+    shared: Arc<Shared>,
+    key: usize,
+}
+
+impl Registration {
+    /// Attach the interrupter of the registered socket, interrupting it right away if the
+    /// handle started shutting down in the meantime.
+    pub(crate) fn attach(&self, interrupter: Interrupter) {
+        // Check the flag while holding the lock, so a concurrent shutdown either sees the
+        // interrupter or we see the flag.
+        let mut sockets = self.shared.sockets.lock().unwrap();
+        if self.shared.closed.load(Ordering::Acquire) {
+            interrupter.interrupt();
+        }
+        if let Some(entry) = sockets.entries.get_mut(self.key) {
+            *entry = Some(interrupter);
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut sockets = self.shared.sockets.lock().unwrap();
+        sockets.entries.remove(self.key);
+        if sockets.entries.is_empty() {
+            for w in sockets.idle.drain(..) {
+                w.wake();
+            }
+        }
+    }
+}
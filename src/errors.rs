@@ -17,17 +17,45 @@
 //! considered a bug; in ØMQ itself, in its documentation or, most likely, in
 //! this crate.
 //!
+//! All of them convert into the unified [`Error`], whose [`kind`] tells them apart again.
+//! It can also carry an [`ErrorContext`] naming the endpoint, socket type and operation that
+//! failed. `bind` and `connect` always fill it in, and so do the errors of sending and
+//! receiving on a socket:
+//!
+//! ```no_run
+//! use async_zmq::{ErrorKind, SocketError};
+//!
+//! match async_zmq::pull("tcp://127.0.0.1:5555").unwrap().bind() {
+//!     Ok(_) => {}
+//!     Err(error) if matches!(error.kind(), ErrorKind::Socket(SocketError::AddressInUse)) => {
+//!         // prints "binding PULL socket to tcp://127.0.0.1:5555: the requested address is
+//!         // already in use"
+//!         eprintln!("{}", error);
+//!     }
+//!     Err(error) => panic!("{}", error),
+//! }
+//! ```
+//!
+//! [`Error`]: struct.Error.html
+//! [`kind`]: struct.Error.html#method.kind
+//! [`ErrorContext`]: struct.ErrorContext.html
 
 use thiserror::Error;
 
-/// The type of errors that can occur when creating a new ØMQ socket.
+/// The type of errors that can occur when creating a new ØMQ socket and binding or connecting
+/// it.
 #[derive(Clone, Copy, Debug, Error)]
 pub enum SocketError {
     /// The requested socket type is invalid.
-    /// Corresponds to ØMQ error code `EINVAL`.
+    /// Corresponds to ØMQ error code `EINVAL` when creating a socket.
     #[error("the requested socket type is invalid")]
     InvalidSocketType,
 
+    /// An argument is invalid, e.g. a malformed endpoint or an out of range option value.
+    /// Corresponds to ØMQ error code `EINVAL` for any operation but creating a socket.
+    #[error("an invalid argument was given")]
+    InvalidArgument,
+
     /// The provided context is invalid.
     /// Corresponds to ØMQ error code `EFAULT`.
     #[error("the provided context is invalid")]
@@ -43,6 +71,37 @@ pub enum SocketError {
     #[error("the context specified was terminated")]
     ContextTerminated,
 
+    /// The requested transport protocol is not supported.
+    /// Corresponds to ØMQ error code `EPROTONOSUPPORT`.
+    #[error("the requested transport protocol is not supported")]
+    ProtocolNotSupported,
+
+    /// The requested transport protocol is not compatible with the socket type.
+    /// Corresponds to ØMQ error code `ENOCOMPATPROTO`.
+    #[error("the requested transport protocol is not compatible with the socket type")]
+    IncompatibleProtocol,
+
+    /// The requested address is already in use.
+    /// Corresponds to ØMQ error code `EADDRINUSE`.
+    #[error("the requested address is already in use")]
+    AddressInUse,
+
+    /// The requested address was not local.
+    /// Corresponds to ØMQ error code `EADDRNOTAVAIL`.
+    #[error("the requested address was not local")]
+    AddressNotAvailable,
+
+    /// The requested address specifies a nonexistent interface.
+    /// Corresponds to ØMQ error code `ENODEV`.
+    #[error("the requested address specifies a nonexistent interface")]
+    NonexistentInterface,
+
+    /// The socket could not be registered in its reactor, either because the
+    /// reactor was shut down or because the operating system refused to watch
+    /// the socket. Reported as ØMQ error code `ETERM` when converted.
+    #[error("the socket could not be registered in the reactor: {0:?}")]
+    ReactorUnavailable(std::io::ErrorKind),
+
     /// ØMQ produced an error variant that is not documented to occur when
     /// creating a new socket. This should never happen and should be treated
     /// as a bug.
@@ -51,12 +110,27 @@ pub enum SocketError {
 }
 
 impl SocketError {
+    /// Convert an error of creating a socket, where `EINVAL` stands for its type.
+    pub(crate) fn creating(error: zmq::Error) -> Self {
+        match error {
+            zmq::Error::EINVAL => SocketError::InvalidSocketType,
+            error => error.into(),
+        }
+    }
+
     fn to_zmq_error(self) -> zmq::Error {
         match self {
             SocketError::InvalidSocketType => zmq::Error::EINVAL,
+            SocketError::InvalidArgument => zmq::Error::EINVAL,
             SocketError::InvalidContext => zmq::Error::EFAULT,
             SocketError::SocketLimitReached => zmq::Error::EMFILE,
             SocketError::ContextTerminated => zmq::Error::ETERM,
+            SocketError::ProtocolNotSupported => zmq::Error::EPROTONOSUPPORT,
+            SocketError::IncompatibleProtocol => zmq::Error::ENOCOMPATPROTO,
+            SocketError::AddressInUse => zmq::Error::EADDRINUSE,
+            SocketError::AddressNotAvailable => zmq::Error::EADDRNOTAVAIL,
+            SocketError::NonexistentInterface => zmq::Error::ENODEV,
+            SocketError::ReactorUnavailable(_) => zmq::Error::ETERM,
             SocketError::Unexpected(error) => error,
         }
     }
@@ -71,31 +145,37 @@ impl From<SocketError> for zmq::Error {
 impl From<zmq::Error> for SocketError {
     fn from(other: zmq::Error) -> Self {
         match other {
-            zmq::Error::EINVAL => SocketError::InvalidSocketType,
+            zmq::Error::EINVAL => SocketError::InvalidArgument,
             zmq::Error::EFAULT => SocketError::InvalidContext,
             zmq::Error::EMFILE => SocketError::SocketLimitReached,
             zmq::Error::ETERM => SocketError::ContextTerminated,
+            zmq::Error::EPROTONOSUPPORT => SocketError::ProtocolNotSupported,
+            zmq::Error::ENOCOMPATPROTO => SocketError::IncompatibleProtocol,
+            zmq::Error::EADDRINUSE => SocketError::AddressInUse,
+            zmq::Error::EADDRNOTAVAIL => SocketError::AddressNotAvailable,
+            zmq::Error::ENODEV => SocketError::NonexistentInterface,
             error => SocketError::Unexpected(error),
         }
     }
 }
 
-/// The type of errors that can occur when sending a ØMQ message.
+/// What went wrong when sending a ØMQ message, the kind of a
+/// [`SendError`](struct.SendError.html).
 ///
 /// The following ØMQ error codes may occur in the underlying ØMQ implementation,
 /// but do not need to be handled by users of this crate:
 ///
 ///  * `EAGAIN` - this crate will automatically retry if this error code is
-///     produced
+///    produced
 ///  * `ENOTSUP` - unsupported operations are prevented by the design of this
-///     crate
+///    crate
 ///  * `EINVAL` - multipart messages are not yet supported
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 ///  * `EFSM` - this applies only to REP/REQ sockets which have their own error
-///     type
+///    type
 #[derive(Clone, Copy, Debug, Error)]
-pub enum SendError {
+pub enum SendErrorKind {
     /// The ØMQ context associated with the specified socket was terminated.
     ///
     /// Note that this error cannot occur unless you access the raw socket
@@ -133,52 +213,53 @@ pub enum SendError {
     Unexpected(#[source] zmq::Error),
 }
 
-impl SendError {
+impl SendErrorKind {
     fn to_zmq_error(self) -> zmq::Error {
         match self {
-            SendError::ContextTerminated => zmq::Error::ETERM,
-            SendError::HostUnreachable => zmq::Error::EHOSTUNREACH,
-            SendError::InvalidMessage => zmq::Error::EFAULT,
-            SendError::Interrupted => zmq::Error::EINTR,
-            SendError::Unexpected(error) => error,
+            SendErrorKind::ContextTerminated => zmq::Error::ETERM,
+            SendErrorKind::HostUnreachable => zmq::Error::EHOSTUNREACH,
+            SendErrorKind::InvalidMessage => zmq::Error::EFAULT,
+            SendErrorKind::Interrupted => zmq::Error::EINTR,
+            SendErrorKind::Unexpected(error) => error,
         }
     }
 }
 
-impl From<SendError> for zmq::Error {
-    fn from(other: SendError) -> Self {
+impl From<SendErrorKind> for zmq::Error {
+    fn from(other: SendErrorKind) -> Self {
         other.to_zmq_error()
     }
 }
 
-impl From<zmq::Error> for SendError {
+impl From<zmq::Error> for SendErrorKind {
     fn from(other: zmq::Error) -> Self {
         match other {
-            zmq::Error::ETERM => SendError::ContextTerminated,
-            zmq::Error::EHOSTUNREACH => SendError::HostUnreachable,
-            zmq::Error::EFAULT => SendError::InvalidMessage,
-            zmq::Error::EINTR => SendError::Interrupted,
-            error => SendError::Unexpected(error),
+            zmq::Error::ETERM => SendErrorKind::ContextTerminated,
+            zmq::Error::EHOSTUNREACH => SendErrorKind::HostUnreachable,
+            zmq::Error::EFAULT => SendErrorKind::InvalidMessage,
+            zmq::Error::EINTR => SendErrorKind::Interrupted,
+            error => SendErrorKind::Unexpected(error),
         }
     }
 }
 
-/// The type of errors that can occur when receiving a ØMQ message.
+/// What went wrong when receiving a ØMQ message, the kind of a
+/// [`RecvError`](struct.RecvError.html).
 ///
 /// The following ØMQ error codes may occur in the underlying ØMQ implementation,
 /// but do not need to be handled by users of this crate:
 ///
 ///  * `EAGAIN` - this crate will automatically retry if this error code is
-///     produced
+///    produced
 ///  * `ENOTSUP` - unsupported operations are prevented by the design of this
-///     crate
+///    crate
 ///  * `EINVAL` - multipart messages are not yet supported
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 ///  * `EFSM` - this applies only to REP/REQ sockets which have their own error
-///     type
+///    type
 #[derive(Clone, Copy, Debug, Error)]
-pub enum RecvError {
+pub enum RecvErrorKind {
     /// The ØMQ context associated with the specified socket was terminated.
     ///
     /// Note that this error cannot occur unless you access the raw socket
@@ -206,46 +287,47 @@ pub enum RecvError {
     Unexpected(#[source] zmq::Error),
 }
 
-impl RecvError {
+impl RecvErrorKind {
     fn to_zmq_error(self) -> zmq::Error {
         match self {
-            RecvError::ContextTerminated => zmq::Error::ETERM,
-            RecvError::Interrupted => zmq::Error::EINTR,
-            RecvError::Unexpected(error) => error,
+            RecvErrorKind::ContextTerminated => zmq::Error::ETERM,
+            RecvErrorKind::Interrupted => zmq::Error::EINTR,
+            RecvErrorKind::Unexpected(error) => error,
         }
     }
 }
 
-impl From<RecvError> for zmq::Error {
-    fn from(other: RecvError) -> Self {
+impl From<RecvErrorKind> for zmq::Error {
+    fn from(other: RecvErrorKind) -> Self {
         other.to_zmq_error()
     }
 }
 
-impl From<zmq::Error> for RecvError {
+impl From<zmq::Error> for RecvErrorKind {
     fn from(other: zmq::Error) -> Self {
         match other {
-            zmq::Error::ETERM => RecvError::ContextTerminated,
-            zmq::Error::EINTR => RecvError::Interrupted,
-            error => RecvError::Unexpected(error),
+            zmq::Error::ETERM => RecvErrorKind::ContextTerminated,
+            zmq::Error::EINTR => RecvErrorKind::Interrupted,
+            error => RecvErrorKind::Unexpected(error),
         }
     }
 }
 
-/// The type of errors that can occur when sending on a request channel.
+/// What went wrong when sending or receiving on a request channel, the kind of a
+/// [`RequestReplyError`](struct.RequestReplyError.html).
 ///
 /// The following ØMQ error codes may occur in the underlying ØMQ implementation,
 /// but do not need to be handled by users of this crate:
 ///
 ///  * `EAGAIN` - this crate will automatically retry if this error code is
-///     produced
+///    produced
 ///  * `ENOTSUP` - unsupported operations are prevented by the design of this
-///     crate
+///    crate
 ///  * `EINVAL` - multipart messages are not yet supported
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 #[derive(Clone, Copy, Debug, Error)]
-pub enum RequestReplyError {
+pub enum RequestReplyErrorKind {
     /// The socket was in the incorrect state for the operation.
     ///
     /// This can occur when sending a message on a request or reply socket when
@@ -286,36 +368,200 @@ pub enum RequestReplyError {
     Unexpected(#[source] zmq::Error),
 }
 
-impl RequestReplyError {
+impl RequestReplyErrorKind {
     fn to_zmq_error(self) -> zmq::Error {
         match self {
-            RequestReplyError::AwaitingReply => zmq::Error::EFSM,
-            RequestReplyError::ContextTerminated => zmq::Error::ETERM,
-            RequestReplyError::HostUnreachable => zmq::Error::EHOSTUNREACH,
-            RequestReplyError::Interrupted => zmq::Error::EINTR,
-            RequestReplyError::Unexpected(error) => error,
+            RequestReplyErrorKind::AwaitingReply => zmq::Error::EFSM,
+            RequestReplyErrorKind::ContextTerminated => zmq::Error::ETERM,
+            RequestReplyErrorKind::HostUnreachable => zmq::Error::EHOSTUNREACH,
+            RequestReplyErrorKind::Interrupted => zmq::Error::EINTR,
+            RequestReplyErrorKind::Unexpected(error) => error,
         }
     }
 }
 
-impl From<RequestReplyError> for zmq::Error {
-    fn from(other: RequestReplyError) -> Self {
+impl From<RequestReplyErrorKind> for zmq::Error {
+    fn from(other: RequestReplyErrorKind) -> Self {
         other.to_zmq_error()
     }
 }
 
-impl From<zmq::Error> for RequestReplyError {
+impl From<zmq::Error> for RequestReplyErrorKind {
     fn from(other: zmq::Error) -> Self {
         match other {
-            zmq::Error::EFSM => RequestReplyError::AwaitingReply,
-            zmq::Error::ETERM => RequestReplyError::ContextTerminated,
-            zmq::Error::EHOSTUNREACH => RequestReplyError::HostUnreachable,
-            zmq::Error::EINTR => RequestReplyError::Interrupted,
-            error => RequestReplyError::Unexpected(error),
+            zmq::Error::EFSM => RequestReplyErrorKind::AwaitingReply,
+            zmq::Error::ETERM => RequestReplyErrorKind::ContextTerminated,
+            zmq::Error::EHOSTUNREACH => RequestReplyErrorKind::HostUnreachable,
+            zmq::Error::EINTR => RequestReplyErrorKind::Interrupted,
+            error => RequestReplyErrorKind::Unexpected(error),
         }
     }
 }
 
+/// What went wrong when closing a socket, the kind of a [`CloseError`](struct.CloseError.html).
+///
+/// Closing hands the messages a socket still queues over to ØMQ first, so it fails for the
+/// same reasons sending does.
+#[derive(Clone, Copy, Debug, Error)]
+pub enum CloseErrorKind {
+    /// The ØMQ context associated with the socket was terminated before everything queued was
+    /// handed over to ØMQ.
+    ///
+    /// Corresponds to ØMQ error code `ETERM`
+    #[error("the context specified was terminated")]
+    ContextTerminated,
+
+    /// A queued message could not be routed.
+    ///
+    /// Corresponds to ØMQ error code `EHOSTUNREACH`.
+    #[error("a queued message cannot be routed")]
+    HostUnreachable,
+
+    /// The operation was interrupted by delivery of a signal before the socket was closed.
+    ///
+    /// Corresponds to ØMQ error code `EINTR`.
+    #[error("the operation was interrupted by delivery of a signal before the socket was closed")]
+    Interrupted,
+
+    /// ØMQ produced an error variant that is not documented to occur when
+    /// closing a socket. This should never happen and should be treated as a
+    /// bug.
+    #[error("an unexpected error occurred: {0}")]
+    Unexpected(#[source] zmq::Error),
+}
+
+impl CloseErrorKind {
+    fn to_zmq_error(self) -> zmq::Error {
+        match self {
+            CloseErrorKind::ContextTerminated => zmq::Error::ETERM,
+            CloseErrorKind::HostUnreachable => zmq::Error::EHOSTUNREACH,
+            CloseErrorKind::Interrupted => zmq::Error::EINTR,
+            CloseErrorKind::Unexpected(error) => error,
+        }
+    }
+}
+
+impl From<CloseErrorKind> for zmq::Error {
+    fn from(other: CloseErrorKind) -> Self {
+        other.to_zmq_error()
+    }
+}
+
+impl From<zmq::Error> for CloseErrorKind {
+    fn from(other: zmq::Error) -> Self {
+        match other {
+            zmq::Error::ETERM => CloseErrorKind::ContextTerminated,
+            zmq::Error::EHOSTUNREACH => CloseErrorKind::HostUnreachable,
+            zmq::Error::EINTR => CloseErrorKind::Interrupted,
+            error => CloseErrorKind::Unexpected(error),
+        }
+    }
+}
+
+/// Defines an error type pairing a kind with the context of the socket it occurred on.
+macro_rules! socket_error {
+    ($(#[$doc:meta])* $name:ident($kind:ident)) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug)]
+        pub struct $name {
+            kind: $kind,
+            context: Option<Box<ErrorContext>>,
+        }
+
+        impl $name {
+            /// What went wrong.
+            pub fn kind(&self) -> $kind {
+                self.kind
+            }
+
+            /// Where it went wrong, if known.
+            pub fn context(&self) -> Option<&ErrorContext> {
+                self.context.as_deref()
+            }
+
+            /// Attach the context the error occurred in, replacing any previous one.
+            pub fn with_context(self, context: ErrorContext) -> Self {
+                Self {
+                    context: Some(Box::new(context)),
+                    ..self
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match &self.context {
+                    Some(context) => write!(f, "{}: {}", context, self.kind),
+                    None => self.kind.fmt(f),
+                }
+            }
+        }
+
+        impl std::error::Error for $name {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                Some(&self.kind)
+            }
+        }
+
+        impl From<$kind> for $name {
+            fn from(kind: $kind) -> Self {
+                Self {
+                    kind,
+                    context: None,
+                }
+            }
+        }
+
+        impl From<zmq::Error> for $name {
+            fn from(other: zmq::Error) -> Self {
+                $kind::from(other).into()
+            }
+        }
+
+        impl From<$name> for zmq::Error {
+            fn from(other: $name) -> Self {
+                other.kind.into()
+            }
+        }
+    };
+}
+
+socket_error! {
+    /// The type of errors that can occur when sending a ØMQ message.
+    ///
+    /// Errors of a socket carry the [`ErrorContext`](struct.ErrorContext.html) naming its
+    /// endpoint and type, which is kept when converting into the unified
+    /// [`Error`](struct.Error.html).
+    SendError(SendErrorKind)
+}
+
+socket_error! {
+    /// The type of errors that can occur when receiving a ØMQ message.
+    ///
+    /// Errors of a socket carry the [`ErrorContext`](struct.ErrorContext.html) naming its
+    /// endpoint and type, which is kept when converting into the unified
+    /// [`Error`](struct.Error.html).
+    RecvError(RecvErrorKind)
+}
+
+socket_error! {
+    /// The type of errors that can occur when sending or receiving on a request channel.
+    ///
+    /// Errors of a socket carry the [`ErrorContext`](struct.ErrorContext.html) naming its
+    /// endpoint and type, which is kept when converting into the unified
+    /// [`Error`](struct.Error.html).
+    RequestReplyError(RequestReplyErrorKind)
+}
+
+socket_error! {
+    /// The type of errors that can occur when closing a socket.
+    ///
+    /// Errors of a socket carry the [`ErrorContext`](struct.ErrorContext.html) naming its
+    /// endpoint and type, which is kept when converting into the unified
+    /// [`Error`](struct.Error.html).
+    CloseError(CloseErrorKind)
+}
+
 /// The type of errors that can occur when setting or unsetting a subscription
 /// topic.
 ///
@@ -324,7 +570,7 @@ impl From<zmq::Error> for RequestReplyError {
 ///
 ///  * `EINVAL` - the option name is always correct
 ///  * `ENOTSOCK` - the design of this crate prevents sending messages on an
-///     invalid socket
+///    invalid socket
 #[derive(Clone, Copy, Debug, Error)]
 pub enum SubscribeError {
     /// The ØMQ context associated with the specified socket was terminated.
@@ -377,3 +623,596 @@ impl From<zmq::Error> for SubscribeError {
         }
     }
 }
+
+/// An operation on a socket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    /// Binding to an endpoint.
+    Bind,
+    /// Connecting to an endpoint.
+    Connect,
+    /// Unbinding from an endpoint.
+    Unbind,
+    /// Disconnecting from an endpoint.
+    Disconnect,
+    /// Sending a message.
+    Send,
+    /// Receiving a message.
+    Recv,
+    /// Closing the socket.
+    Close,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Bind => f.write_str("binding"),
+            Operation::Connect => f.write_str("connecting"),
+            Operation::Unbind => f.write_str("unbinding"),
+            Operation::Disconnect => f.write_str("disconnecting"),
+            Operation::Send => f.write_str("sending"),
+            Operation::Recv => f.write_str("receiving"),
+            Operation::Close => f.write_str("closing"),
+        }
+    }
+}
+
+/// The error returned when parsing an invalid W3C `traceparent` header into a
+/// [`TraceParent`](../traceparent/struct.TraceParent.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Error)]
+#[error("invalid traceparent header")]
+pub struct ParseTraceParentError;
+
+/// The type of errors that can occur when sending or receiving through an
+/// [`AnySocket`](../any/enum.AnySocket.html).
+#[derive(Clone, Debug, Error)]
+pub enum AnySocketError {
+    /// The socket type does not support the operation, e.g. receiving on a
+    /// PUSH socket.
+    /// Corresponds to ØMQ error code `ENOTSUP`.
+    #[error("{operation} is not supported by {socket_type:?} sockets")]
+    Unsupported {
+        /// The type of the socket the operation was attempted on.
+        socket_type: zmq::SocketType,
+        /// The operation that was attempted.
+        operation: Operation,
+    },
+
+    /// Sending the message failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+
+    /// Receiving a message failed.
+    #[error(transparent)]
+    Recv(#[from] RecvError),
+
+    /// Sending or receiving on a REQ or REP socket failed.
+    #[error(transparent)]
+    RequestReply(#[from] RequestReplyError),
+}
+
+impl AnySocketError {
+    fn to_zmq_error(&self) -> zmq::Error {
+        match self {
+            AnySocketError::Unsupported { .. } => zmq::Error::ENOTSUP,
+            AnySocketError::Send(error) => error.kind().into(),
+            AnySocketError::Recv(error) => error.kind().into(),
+            AnySocketError::RequestReply(error) => error.kind().into(),
+        }
+    }
+}
+
+impl From<AnySocketError> for zmq::Error {
+    fn from(other: AnySocketError) -> Self {
+        other.to_zmq_error()
+    }
+}
+
+/// The type of errors that can occur while forwarding messages from one socket to another,
+/// e.g. in a [`LastValueCache`](../lvc/struct.LastValueCache.html).
+#[derive(Clone, Debug, Error)]
+pub enum ForwardError {
+    /// Receiving from one of the sockets failed.
+    #[error(transparent)]
+    Recv(#[from] RecvError),
+
+    /// Sending to one of the sockets failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+}
+
+impl ForwardError {
+    fn to_zmq_error(&self) -> zmq::Error {
+        match self {
+            ForwardError::Recv(error) => error.kind().into(),
+            ForwardError::Send(error) => error.kind().into(),
+        }
+    }
+}
+
+impl From<ForwardError> for zmq::Error {
+    fn from(other: ForwardError) -> Self {
+        other.to_zmq_error()
+    }
+}
+
+/// The type of errors that can occur in the [`clone`](../clone/index.html) key-value store.
+#[derive(Clone, Debug, Error)]
+pub enum CloneError {
+    /// Receiving a snapshot or an update failed.
+    #[error(transparent)]
+    Recv(#[from] RecvError),
+
+    /// Sending a request, a snapshot or an update failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+
+    /// Subscribing to the updates failed.
+    #[error(transparent)]
+    Subscribe(#[from] SubscribeError),
+
+    /// A peer sent a message that does not follow the protocol.
+    /// Corresponds to ØMQ error code `EPROTO`.
+    #[error("received a malformed message")]
+    Malformed,
+
+    /// Updates were lost between the last one applied and the one received, the store has to
+    /// be fetched again.
+    /// Corresponds to ØMQ error code `EPROTO`.
+    #[error("expected update {expected} but received update {received}")]
+    Gap {
+        /// The sequence number of the update that should have come next.
+        expected: u64,
+        /// The sequence number of the update that came instead.
+        received: u64,
+    },
+}
+
+impl CloneError {
+    fn to_zmq_error(&self) -> zmq::Error {
+        match self {
+            CloneError::Recv(error) => error.kind().into(),
+            CloneError::Send(error) => error.kind().into(),
+            CloneError::Subscribe(error) => (*error).into(),
+            CloneError::Malformed | CloneError::Gap { .. } => zmq::Error::EPROTO,
+        }
+    }
+}
+
+impl From<CloneError> for zmq::Error {
+    fn from(other: CloneError) -> Self {
+        other.to_zmq_error()
+    }
+}
+
+/// The type of errors that can occur in the [`rpc`](../rpc/index.html) client and server.
+#[derive(Clone, Debug, Error)]
+pub enum RpcError {
+    /// Receiving a request or a reply failed.
+    #[error(transparent)]
+    Recv(#[from] RecvError),
+
+    /// Sending a request or a reply failed.
+    #[error(transparent)]
+    Send(#[from] SendError),
+
+    /// No reply arrived within the timeout of the client.
+    /// Corresponds to ØMQ error code `EAGAIN`, like an expired `ZMQ_RCVTIMEO`.
+    #[error("no reply arrived in time")]
+    TimedOut,
+
+    /// The driver of the client stopped, because it failed, the context was terminated or it
+    /// was dropped, or it could not send the request.
+    /// Corresponds to ØMQ error code `ENOTCONN`.
+    #[error("the client is no longer running")]
+    Closed,
+}
+
+impl RpcError {
+    fn to_zmq_error(&self) -> zmq::Error {
+        match self {
+            RpcError::Recv(error) => error.kind().into(),
+            RpcError::Send(error) => error.kind().into(),
+            RpcError::TimedOut => zmq::Error::EAGAIN,
+            RpcError::Closed => zmq::Error::ENOTCONN,
+        }
+    }
+}
+
+impl From<RpcError> for zmq::Error {
+    fn from(other: RpcError) -> Self {
+        other.to_zmq_error()
+    }
+}
+
+/// The type of errors that can occur when calling a method of a
+/// [`service`](../service/index.html).
+///
+/// The server reports the errors of the handler side to the client, which fails the call with
+/// them.
+#[derive(Clone, Debug, Error)]
+pub enum ServiceError {
+    /// Sending the call or receiving its reply failed.
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+
+    /// The server has no method of this name.
+    /// Corresponds to ØMQ error code `ENOTSUP`.
+    #[error("unknown method {0:?}")]
+    UnknownMethod(String),
+
+    /// The request or the reply could not be deserialized.
+    /// Corresponds to ØMQ error code `EPROTO`.
+    #[error("cannot decode the message: {0}")]
+    Decode(String),
+
+    /// The request or the reply could not be serialized.
+    /// Corresponds to ØMQ error code `EPROTO`.
+    #[error("cannot encode the message: {0}")]
+    Encode(String),
+
+    /// The handler returned an error, described by the message.
+    /// Corresponds to ØMQ error code `EPROTO`.
+    #[error("the handler failed: {0}")]
+    Handler(String),
+
+    /// The deadline of the call passed before the handler completed.
+    /// Corresponds to ØMQ error code `EAGAIN`.
+    #[error("the deadline passed before the handler completed")]
+    DeadlineExceeded,
+
+    /// The server sent a reply that does not follow the protocol.
+    /// Corresponds to ØMQ error code `EPROTO`.
+    #[error("received a malformed reply")]
+    Malformed,
+}
+
+impl ServiceError {
+    fn to_zmq_error(&self) -> zmq::Error {
+        match self {
+            ServiceError::Rpc(error) => error.to_zmq_error(),
+            ServiceError::UnknownMethod(_) => zmq::Error::ENOTSUP,
+            ServiceError::DeadlineExceeded => zmq::Error::EAGAIN,
+            ServiceError::Decode(_)
+            | ServiceError::Encode(_)
+            | ServiceError::Handler(_)
+            | ServiceError::Malformed => zmq::Error::EPROTO,
+        }
+    }
+}
+
+impl From<ServiceError> for zmq::Error {
+    fn from(other: ServiceError) -> Self {
+        other.to_zmq_error()
+    }
+}
+
+/// Where an [`Error`](struct.Error.html) occurred. Every part is optional, as not every error
+/// site knows all of them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The endpoint the socket was bound or connected to.
+    pub endpoint: Option<String>,
+    /// The type of the socket.
+    pub socket_type: Option<zmq::SocketType>,
+    /// The operation that failed.
+    pub operation: Option<Operation>,
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let attaching = matches!(self.operation, Some(Operation::Bind | Operation::Connect));
+        let detaching = matches!(
+            self.operation,
+            Some(Operation::Unbind | Operation::Disconnect)
+        );
+        let mut separator = "";
+        if let Some(operation) = self.operation {
+            write!(f, "{}", operation)?;
+            let direct = attaching || detaching || operation == Operation::Close;
+            separator = if direct { " " } else { " on " };
+        }
+        if let Some(socket_type) = self.socket_type {
+            write!(f, "{}{:?} socket", separator, socket_type)?;
+            separator = " ";
+        }
+        if let Some(endpoint) = &self.endpoint {
+            let preposition = match (attaching, detaching) {
+                (true, _) => "to ",
+                (_, true) => "from ",
+                _ => "at ",
+            };
+            write!(f, "{}{}{}", separator, preposition, endpoint)?;
+        }
+        Ok(())
+    }
+}
+
+/// What went wrong in an [`Error`](struct.Error.html), for matching on.
+///
+/// Errors wrapping others, like [`AnySocketError`] or [`ForwardError`], are flattened into the
+/// kind of the error they wrap.
+///
+/// [`AnySocketError`]: enum.AnySocketError.html
+/// [`ForwardError`]: enum.ForwardError.html
+#[derive(Clone, Debug)]
+pub enum ErrorKind {
+    /// Creating, binding or connecting a socket failed.
+    Socket(SocketError),
+    /// Sending a message failed.
+    Send(SendErrorKind),
+    /// Receiving a message failed.
+    Recv(RecvErrorKind),
+    /// Sending or receiving on a REQ or REP socket failed.
+    RequestReply(RequestReplyErrorKind),
+    /// Closing a socket failed.
+    Close(CloseErrorKind),
+    /// Setting or unsetting a subscription failed.
+    Subscribe(SubscribeError),
+    /// The socket type does not support the operation, both are named by the context.
+    /// Corresponds to ØMQ error code `ENOTSUP`.
+    Unsupported,
+    /// A peer broke the protocol of the [`clone`](../clone/index.html) key-value store, always
+    /// [`CloneError::Malformed`] or [`CloneError::Gap`].
+    ///
+    /// [`CloneError::Malformed`]: enum.CloneError.html#variant.Malformed
+    /// [`CloneError::Gap`]: enum.CloneError.html#variant.Gap
+    Clone(CloneError),
+    /// A call of the [`rpc`](../rpc/index.html) client did not complete, always
+    /// [`RpcError::TimedOut`] or [`RpcError::Closed`].
+    ///
+    /// [`RpcError::TimedOut`]: enum.RpcError.html#variant.TimedOut
+    /// [`RpcError::Closed`]: enum.RpcError.html#variant.Closed
+    Rpc(RpcError),
+    /// A method of a [`service`](../service/index.html) failed on the server or its reply
+    /// could not be read, never [`ServiceError::Rpc`].
+    ///
+    /// [`ServiceError::Rpc`]: enum.ServiceError.html#variant.Rpc
+    Service(ServiceError),
+    /// ØMQ reported an error outside of the operations above, e.g. when setting a socket
+    /// option on the raw socket.
+    Zmq(zmq::Error),
+}
+
+impl ErrorKind {
+    fn to_zmq_error(&self) -> zmq::Error {
+        match self {
+            ErrorKind::Socket(error) => (*error).into(),
+            ErrorKind::Send(error) => (*error).into(),
+            ErrorKind::Recv(error) => (*error).into(),
+            ErrorKind::RequestReply(error) => (*error).into(),
+            ErrorKind::Close(error) => (*error).into(),
+            ErrorKind::Subscribe(error) => (*error).into(),
+            ErrorKind::Unsupported => zmq::Error::ENOTSUP,
+            ErrorKind::Clone(error) => error.to_zmq_error(),
+            ErrorKind::Rpc(error) => error.to_zmq_error(),
+            ErrorKind::Service(error) => error.to_zmq_error(),
+            ErrorKind::Zmq(error) => *error,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::Socket(error) => error.fmt(f),
+            ErrorKind::Send(error) => error.fmt(f),
+            ErrorKind::Recv(error) => error.fmt(f),
+            ErrorKind::RequestReply(error) => error.fmt(f),
+            ErrorKind::Close(error) => error.fmt(f),
+            ErrorKind::Subscribe(error) => error.fmt(f),
+            ErrorKind::Unsupported => f.write_str("the operation is not supported by the socket"),
+            ErrorKind::Clone(error) => error.fmt(f),
+            ErrorKind::Rpc(error) => error.fmt(f),
+            ErrorKind::Service(error) => error.fmt(f),
+            ErrorKind::Zmq(error) => error.fmt(f),
+        }
+    }
+}
+
+/// Any error of this crate, optionally with the [`ErrorContext`](struct.ErrorContext.html) it
+/// occurred in.
+///
+/// Every other error type of this crate and `zmq::Error` convert into it, and it converts back
+/// into `zmq::Error`.
+#[derive(Clone, Debug)]
+pub struct Error {
+    kind: ErrorKind,
+    context: Option<Box<ErrorContext>>,
+}
+
+impl Error {
+    /// Create an error without context.
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            context: None,
+        }
+    }
+
+    /// Attach the context the error occurred in, replacing any previous one.
+    pub fn with_context(self, context: ErrorContext) -> Self {
+        Self {
+            context: Some(Box::new(context)),
+            ..self
+        }
+    }
+
+    /// What went wrong.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind.clone()
+    }
+
+    /// Where it went wrong, if known.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context.as_deref()
+    }
+
+    /// The endpoint involved, if known.
+    pub fn endpoint(&self) -> Option<&str> {
+        self.context()?.endpoint.as_deref()
+    }
+
+    /// The type of the socket involved, if known.
+    pub fn socket_type(&self) -> Option<zmq::SocketType> {
+        self.context()?.socket_type
+    }
+
+    /// The operation that failed, if known.
+    pub fn operation(&self) -> Option<Operation> {
+        self.context()?.operation
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{}: {}", context, self.kind),
+            None => self.kind.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Socket(error) => Some(error),
+            ErrorKind::Send(error) => Some(error),
+            ErrorKind::Recv(error) => Some(error),
+            ErrorKind::RequestReply(error) => Some(error),
+            ErrorKind::Close(error) => Some(error),
+            ErrorKind::Subscribe(error) => Some(error),
+            ErrorKind::Unsupported => None,
+            ErrorKind::Clone(error) => Some(error),
+            ErrorKind::Rpc(error) => Some(error),
+            ErrorKind::Service(error) => Some(error),
+            ErrorKind::Zmq(error) => Some(error),
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error::new(kind)
+    }
+}
+
+impl From<Error> for zmq::Error {
+    fn from(other: Error) -> Self {
+        other.kind.to_zmq_error()
+    }
+}
+
+impl From<zmq::Error> for Error {
+    fn from(other: zmq::Error) -> Self {
+        ErrorKind::Zmq(other).into()
+    }
+}
+
+impl From<SocketError> for Error {
+    fn from(other: SocketError) -> Self {
+        ErrorKind::Socket(other).into()
+    }
+}
+
+impl From<SendError> for Error {
+    fn from(other: SendError) -> Self {
+        Error {
+            kind: ErrorKind::Send(other.kind),
+            context: other.context,
+        }
+    }
+}
+
+impl From<RecvError> for Error {
+    fn from(other: RecvError) -> Self {
+        Error {
+            kind: ErrorKind::Recv(other.kind),
+            context: other.context,
+        }
+    }
+}
+
+impl From<RequestReplyError> for Error {
+    fn from(other: RequestReplyError) -> Self {
+        Error {
+            kind: ErrorKind::RequestReply(other.kind),
+            context: other.context,
+        }
+    }
+}
+
+impl From<CloseError> for Error {
+    fn from(other: CloseError) -> Self {
+        Error {
+            kind: ErrorKind::Close(other.kind),
+            context: other.context,
+        }
+    }
+}
+
+impl From<SubscribeError> for Error {
+    fn from(other: SubscribeError) -> Self {
+        ErrorKind::Subscribe(other).into()
+    }
+}
+
+impl From<AnySocketError> for Error {
+    fn from(other: AnySocketError) -> Self {
+        match other {
+            AnySocketError::Unsupported {
+                socket_type,
+                operation,
+            } => Error::new(ErrorKind::Unsupported).with_context(ErrorContext {
+                socket_type: Some(socket_type),
+                operation: Some(operation),
+                ..Default::default()
+            }),
+            AnySocketError::Send(error) => error.into(),
+            AnySocketError::Recv(error) => error.into(),
+            AnySocketError::RequestReply(error) => error.into(),
+        }
+    }
+}
+
+impl From<ForwardError> for Error {
+    fn from(other: ForwardError) -> Self {
+        match other {
+            ForwardError::Recv(error) => error.into(),
+            ForwardError::Send(error) => error.into(),
+        }
+    }
+}
+
+impl From<CloneError> for Error {
+    fn from(other: CloneError) -> Self {
+        match other {
+            CloneError::Recv(error) => error.into(),
+            CloneError::Send(error) => error.into(),
+            CloneError::Subscribe(error) => error.into(),
+            error => ErrorKind::Clone(error).into(),
+        }
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(other: RpcError) -> Self {
+        match other {
+            RpcError::Recv(error) => error.into(),
+            RpcError::Send(error) => error.into(),
+            error => ErrorKind::Rpc(error).into(),
+        }
+    }
+}
+
+impl From<ServiceError> for Error {
+    fn from(other: ServiceError) -> Self {
+        match other {
+            ServiceError::Rpc(error) => error.into(),
+            error => ErrorKind::Service(error).into(),
+        }
+    }
+}
+
+/// A `Result` whose error is the unified [`Error`](struct.Error.html).
+pub type Result<T> = std::result::Result<T, Error>;
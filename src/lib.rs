@@ -20,10 +20,40 @@
 //! let xpub = async_zmq::xpublish("inproc://example")?.with_context(&context).bind();
 //! let sub = subscribe("inproc://example")?.with_context(&context).connect()?;
 //! ```
+//!
+//! Sockets built without a context share the crate-wide default one, so `inproc` endpoints work between them
+//! out of the box. [`ContextHandle`] configures that context and shuts it down together with its sockets.
 //! 
 //! Since the use case of this crate is mostly for sending/recieving multipart message. So it provides [`Multipart`]
-//! which is a type alias for `Vec<Message>` when recieving message on type implemented with `Stream`, and [`MultipartIter`]
-//! which is a generic struct make any queue can turn into iterator and then send via type  implemented with `Sink`.
+//! which is a type alias for `Vec<Message>` when recieving message on type implemented with `Stream`. Types implemented
+//! with `Sink` accept anything implementing [`IntoMultipart`]: a `Vec` of frames, a single `Message`, or a [`MultipartIter`]
+//! which is a generic struct make any queue can turn into iterator.
+//!
+//! ## Features
+//!
+//! Besides the socket types, the crate provides:
+//!
+//! - [`context`]: configuring the shared context and shutting it down with its sockets.
+//! - [`any`]: sockets whose type is only known at runtime.
+//! - [`traits`]: the [`ZmqSend`], [`ZmqRecv`] and [`ZmqSocketExt`] traits for code generic over the socket type.
+//! - [`buf`]: receiving into reusable buffers instead of allocating per message.
+//! - [`stats`]: per-socket traffic counts, also published through `metrics` with the `metrics` feature.
+//! - [`monitor`]: socket lifecycle events and peer liveness.
+//! - [`metadata`]: the properties ØMQ attaches to received messages.
+//! - [`retry`]: retrying to bind or connect with backoff.
+//! - [`rpc`]: concurrent request-reply over DEALER and ROUTER sockets.
+//! - [`service`]: methods called by name with serde-serialized requests and replies, with the `serde` feature.
+//! - [`lvc`] and [`clone`]: the last value cache and clone patterns.
+//! - [`traceparent`]: W3C trace context propagation.
+//! - [`testing`]: in-memory sockets to unit test code using `Sink` and `Stream`, with the `testing` feature.
+//! - [`compat`]: the generic socket types of earlier releases.
+//!
+//! With the `tracing` feature enabled, binding and connecting run in spans, and sent and received messages,
+//! reactor registrations and wakeup latencies are traced as events.
+//!
+//! Only the stable socket types are wrapped. The draft ones (CLIENT/SERVER, RADIO/DISH, SCATTER/GATHER, PEER and
+//! CHANNEL) are not available yet: [`zmq`] has no socket type for them, nor the routing ids and groups they attach
+//! to single frames.
 //!
 //! To learn more about each socket type usage. See [modules](#modules) below.
 //!
@@ -31,44 +61,93 @@
 //! [`zmq`]: https://crates.io/crates/zmq
 //! [`async-std`]: https://crates.io/crates/async-std
 //! [`Multipart`]: type.Multipart.html
+//! [`ContextHandle`]: context/struct.ContextHandle.html
 //! [`MultipartIter`]: struct.MultipartIter.html
+//! [`context`]: context/index.html
+//! [`any`]: any/index.html
+//! [`buf`]: buf/index.html
+//! [`monitor`]: monitor/index.html
+//! [`metadata`]: metadata/index.html
+//! [`retry`]: retry/index.html
+//! [`rpc`]: rpc/index.html
+//! [`lvc`]: lvc/index.html
+//! [`clone`]: clone/index.html
+//! [`traceparent`]: traceparent/index.html
+//! [`stats`]: stats/index.html
+//! [`testing`]: testing/index.html
+//! [`service`]: service/index.html
+//! [`traits`]: traits/index.html
+//! [`ZmqSend`]: traits/trait.ZmqSend.html
+//! [`ZmqRecv`]: traits/trait.ZmqRecv.html
+//! [`ZmqSocketExt`]: traits/trait.ZmqSocketExt.html
+//! [`IntoMultipart`]: trait.IntoMultipart.html
+//! [`compat`]: compat/index.html
 
 
 #![deny(unused_extern_crates, unsafe_code)]
 #![warn(missing_docs, rust_2018_idioms, unreachable_pub)]
 
+pub mod any;
+pub mod buf;
+pub mod clone;
+pub mod compat;
+pub mod context;
 pub mod dealer;
 pub mod errors;
+pub mod lvc;
+pub mod metadata;
+pub mod monitor;
 pub mod pair;
 pub mod publish;
 pub mod pull;
 pub mod push;
 pub mod reply;
 pub mod request;
+pub mod retry;
 pub mod router;
+pub mod rpc;
+#[cfg(feature = "serde")]
+pub mod service;
+pub mod stats;
 pub mod stream;
 pub mod subscribe;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod traceparent;
+pub mod traits;
 pub mod xpublish;
 pub mod xsubscribe;
 
 mod reactor;
 mod socket;
 
+pub use crate::any::AnySocket;
+pub use crate::buf::{MultipartBuf, MultipartPool};
+pub use crate::context::{ContextBuilder, ContextHandle, ShutdownSignal};
 pub use crate::dealer::{dealer, Dealer};
 pub use crate::errors::*;
+pub use crate::metadata::Metadata;
+pub use crate::monitor::{Monitor, MonitorEvent, PeerLiveness, PeerStatus};
 pub use crate::pair::{pair, Pair};
 pub use crate::publish::{publish, Publish};
 pub use crate::pull::{pull, Pull};
 pub use crate::push::{push, Push};
-pub use crate::reactor::AsRawSocket;
+pub use crate::reactor::{AsRawSocket, Reactor};
 pub use crate::reply::{reply, Reply};
 pub use crate::request::{request, Request};
+pub use crate::retry::RetryPolicy;
 pub use crate::router::{router, Router};
-pub use crate::socket::{Multipart, MultipartIter, SocketBuilder};
+pub use crate::socket::{
+    BuildSocket, IntoMultipart, Multipart, MultipartIter, RecvBatch, SocketBuilder,
+    TracedRecvBatch,
+};
+pub use crate::stats::SocketStats;
 pub use crate::stream::{stream, ZmqStream};
 pub use crate::subscribe::{subscribe, Subscribe};
+pub use crate::traceparent::TraceParent;
+pub use crate::traits::{ZmqRecv, ZmqSend, ZmqSocketExt};
 pub use crate::xpublish::{xpublish, XPublish};
 pub use crate::xsubscribe::{xsubscribe, XSubscribe};
 pub use futures::sink::{Sink, SinkExt};
 pub use futures::stream::{Stream, StreamExt};
-pub use zmq::{self, Context, Error, Message, Result};
+pub use zmq::{self, Context, Message, SocketType};
@@ -0,0 +1,193 @@
+//! Last-value cache between publishers and subscribers
+//!
+//! Subscribers joining a PUB-SUB feed only see the messages published after they subscribed.
+//! A [`LastValueCache`] sits between the publishers and the subscribers, keeps the last
+//! message of every topic and replays it as soon as a subscription for that topic arrives, so
+//! new subscribers start out with the current value.
+//!
+//! The cache reads from an [`XSubscribe`] socket connected to the publishers and forwards to an
+//! [`XPublish`] socket the subscribers connect to. Subscriptions travel the other way.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use async_zmq::{lvc::LastValueCache, Result};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let frontend = async_zmq::xsubscribe("tcp://127.0.0.1:5555")?.connect()?;
+//!     let backend = async_zmq::xpublish("tcp://127.0.0.1:5556")?.bind()?;
+//!
+//!     LastValueCache::new(frontend, backend)?
+//!         .max_topics(1024)
+//!         .ttl(Duration::from_secs(60))
+//!         .run()
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`LastValueCache`]: struct.LastValueCache.html
+//! [`XSubscribe`]: ../xsubscribe/struct.XSubscribe.html
+//! [`XPublish`]: ../xpublish/struct.XPublish.html
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use futures::future::{select, Either};
+use zmq::Message;
+
+use crate::{
+    traits::sealed::Sealed,
+    xpublish::{SubscriptionEvent, SubscriptionKind},
+    ForwardError, Multipart, SinkExt, StreamExt, SubscribeError, XPublish, XSubscribe,
+};
+
+/// A cache of the last message published on every topic, see the [module documentation].
+///
+/// Messages are cached under their first frame. A subscription replays every cached message
+/// whose topic starts with the subscribed prefix. ØMQ delivers the replayed messages to every
+/// subscriber of the topic, so subscribers that were around already receive them a second time.
+///
+/// [module documentation]: index.html
+pub struct LastValueCache {
+    frontend: XSubscribe,
+    backend: XPublish,
+    cache: Cache,
+}
+
+/// The cached messages, by topic.
+struct Cache {
+    entries: HashMap<Vec<u8>, Entry>,
+    max_topics: Option<usize>,
+    ttl: Option<Duration>,
+}
+
+struct Entry {
+    frames: Vec<Vec<u8>>,
+    updated: Instant,
+}
+
+impl LastValueCache {
+    /// Create a cache forwarding from `frontend` to `backend`.
+    ///
+    /// Turns on verbose mode on `backend`, so every new subscriber's subscription reaches the
+    /// cache and not only the first one to each topic.
+    pub fn new(frontend: XSubscribe, backend: XPublish) -> Result<Self, SubscribeError> {
+        backend.set_verbose(true)?;
+        Ok(Self {
+            frontend,
+            backend,
+            cache: Cache {
+                entries: HashMap::new(),
+                max_topics: None,
+                ttl: None,
+            },
+        })
+    }
+
+    /// Keep at most `max` topics, evicting the one updated longest ago to make room for a new
+    /// one. Unlimited by default.
+    pub fn max_topics(mut self, max: usize) -> Self {
+        self.cache.max_topics = Some(max);
+        self
+    }
+
+    /// Stop replaying messages that are older than `ttl`. Messages never expire by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.cache.ttl = Some(ttl);
+        self
+    }
+
+    /// Forward messages and subscriptions until one of the sockets fails.
+    pub async fn run(mut self) -> Result<(), ForwardError> {
+        loop {
+            let mut subscriptions = self.backend.subscriptions();
+            let next = match select(self.frontend.next(), subscriptions.next()).await {
+                Either::Left((msg, _)) => Either::Left(msg),
+                Either::Right((event, _)) => Either::Right(event),
+            };
+            match next {
+                Either::Left(Some(msg)) => self.publish(msg?).await?,
+                Either::Right(Some(event)) => self.subscribe(event?).await?,
+                // The context was terminated.
+                Either::Left(None) | Either::Right(None) => return Ok(()),
+            }
+        }
+    }
+
+    async fn publish(&mut self, msg: Multipart) -> Result<(), ForwardError> {
+        if let Some(topic) = msg.first() {
+            let frames = msg.iter().map(|frame| frame.to_vec()).collect();
+            self.cache.insert(topic.to_vec(), frames);
+        }
+        Ok(self.backend.send(msg).await?)
+    }
+
+    async fn subscribe(&mut self, event: SubscriptionEvent) -> Result<(), ForwardError> {
+        let flag = match event.kind {
+            SubscriptionKind::Subscribe => 1,
+            SubscriptionKind::Unsubscribe => 0,
+        };
+        let mut subscription = vec![flag];
+        subscription.extend_from_slice(&event.topic);
+        // Subscription messages never block on an XSUB socket.
+        self.frontend
+            .as_raw_socket()
+            .send(subscription, zmq::DONTWAIT)
+            .map_err(|e| ForwardError::Send(self.frontend.zmq_socket().send_error(e)))?;
+
+        if event.kind == SubscriptionKind::Subscribe {
+            for frames in self.cache.matching(&event.topic) {
+                self.backend.send(frames).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Cache {
+    fn insert(&mut self, topic: Vec<u8>, frames: Vec<Vec<u8>>) {
+        self.expire();
+        if let Some(max) = self.max_topics {
+            if !self.entries.contains_key(&topic) && self.entries.len() >= max {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.updated)
+                    .map(|(topic, _)| topic.clone());
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        if self.max_topics != Some(0) {
+            let updated = Instant::now();
+            self.entries.insert(topic, Entry { frames, updated });
+        }
+    }
+
+    /// The cached messages under topics starting with `prefix`, oldest first.
+    fn matching(&mut self, prefix: &[u8]) -> Vec<Vec<Message>> {
+        self.expire();
+        let mut entries: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|(topic, _)| topic.starts_with(prefix))
+            .map(|(_, entry)| entry)
+            .collect();
+        entries.sort_by_key(|entry| entry.updated);
+        entries
+            .into_iter()
+            .map(|entry| entry.frames.iter().map(|frame| frame[..].into()).collect())
+            .collect()
+    }
+
+    fn expire(&mut self) {
+        if let Some(ttl) = self.ttl {
+            self.entries
+                .retain(|_, entry| entry.updated.elapsed() < ttl);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Message metadata accessors
+//!
+//! Every frame received from a connected peer carries the properties ØMQ learned about that
+//! peer during the handshake. The [`Metadata`] trait exposes the ones this crate cares about
+//! as typed values instead of raw [`Message::gets`] lookups.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{Metadata, Result, StreamExt};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let mut zmq = async_zmq::pull("tcp://127.0.0.1:5555")?
+//!         .plain_server()
+//!         .zap_domain("internal")
+//!         .bind()?;
+//!
+//!     while let Some(msg) = zmq.next().await {
+//!         let mut msg = msg?;
+//!         let user = msg[0].user_id().map(str::to_owned);
+//!         println!("{:?} sent {:?}", user, msg[0].as_str());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Metadata`]: trait.Metadata.html
+//! [`Message::gets`]: ../struct.Message.html#method.gets
+
+use zmq::{Message, SocketType};
+
+/// Typed access to the metadata properties attached to a received frame.
+///
+/// Properties are only present on frames that were actually received from a peer. Frames
+/// built locally always return `None`.
+pub trait Metadata {
+    /// The user id the ZAP handler authenticated the peer as.
+    ///
+    /// Corresponds to the `User-Id` property. With the PLAIN mechanism this is usually the
+    /// username the client presented.
+    fn user_id(&mut self) -> Option<&str>;
+
+    /// The address of the peer that sent the frame.
+    ///
+    /// Corresponds to the `Peer-Address` property.
+    fn peer_address(&mut self) -> Option<&str>;
+
+    /// The socket type of the peer that sent the frame.
+    ///
+    /// Corresponds to the `Socket-Type` property.
+    fn peer_socket_type(&mut self) -> Option<SocketType>;
+}
+
+impl Metadata for Message {
+    fn user_id(&mut self) -> Option<&str> {
+        self.gets("User-Id")
+    }
+
+    fn peer_address(&mut self) -> Option<&str> {
+        self.gets("Peer-Address")
+    }
+
+    fn peer_socket_type(&mut self) -> Option<SocketType> {
+        let socket_type = match self.gets("Socket-Type")? {
+            "PAIR" => SocketType::PAIR,
+            "PUB" => SocketType::PUB,
+            "SUB" => SocketType::SUB,
+            "REQ" => SocketType::REQ,
+            "REP" => SocketType::REP,
+            "DEALER" => SocketType::DEALER,
+            "ROUTER" => SocketType::ROUTER,
+            "PULL" => SocketType::PULL,
+            "PUSH" => SocketType::PUSH,
+            "XPUB" => SocketType::XPUB,
+            "XSUB" => SocketType::XSUB,
+            "STREAM" => SocketType::STREAM,
+            _ => return None,
+        };
+        Some(socket_type)
+    }
+}
@@ -0,0 +1,213 @@
+//! Socket lifecycle events and peer liveness
+//!
+//! Every socket type offers a `monitor` method, which returns a [`Monitor`] stream of the
+//! connection events ØMQ reports for that socket, and a `peer_liveness` method, which narrows
+//! those events down to peers coming and going.
+//!
+//! Peer liveness is most useful together with the heartbeat options on [`SocketBuilder`]:
+//! without heartbeats a peer that silently vanished (e.g. a half-dead TCP connection) is only
+//! noticed once the operating system gives up on it, which can take hours.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use async_zmq::{monitor::PeerStatus, Result, StreamExt};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let zmq = async_zmq::dealer("tcp://127.0.0.1:5555")?
+//!         .heartbeat_ivl(Duration::from_secs(1))
+//!         .heartbeat_timeout(Duration::from_secs(3))
+//!         .connect()?;
+//!
+//!     let mut liveness = zmq.peer_liveness()?;
+//!     while let Some(status) = liveness.next().await {
+//!         if let PeerStatus::Dead(endpoint) = status? {
+//!             println!("lost {}", endpoint);
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`Monitor`]: struct.Monitor.html
+//! [`SocketBuilder`]: ../struct.SocketBuilder.html
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+
+use futures::{ready, stream::FusedStream};
+use zmq::{SocketEvent, SocketType};
+
+use crate::{
+    reactor::{AsRawSocket, ZmqSocket},
+    RecvError, SocketError, Stream,
+};
+
+/// Counter used to give every monitor its own inproc endpoint.
+static MONITOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Events a [`Monitor`] can report, in the order `SocketEvent::from_raw` knows them.
+///
+/// [`Monitor`]: struct.Monitor.html
+const KNOWN_EVENTS: [SocketEvent; 15] = [
+    SocketEvent::CONNECTED,
+    SocketEvent::CONNECT_DELAYED,
+    SocketEvent::CONNECT_RETRIED,
+    SocketEvent::LISTENING,
+    SocketEvent::BIND_FAILED,
+    SocketEvent::ACCEPTED,
+    SocketEvent::ACCEPT_FAILED,
+    SocketEvent::CLOSED,
+    SocketEvent::CLOSE_FAILED,
+    SocketEvent::DISCONNECTED,
+    SocketEvent::MONITOR_STOPPED,
+    SocketEvent::HANDSHAKE_FAILED_NO_DETAIL,
+    SocketEvent::HANDSHAKE_SUCCEEDED,
+    SocketEvent::HANDSHAKE_FAILED_PROTOCOL,
+    SocketEvent::HANDSHAKE_FAILED_AUTH,
+];
+
+/// A lifecycle event reported for a monitored socket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonitorEvent {
+    /// What happened.
+    pub event: SocketEvent,
+    /// Event specific value, e.g. the file descriptor for `CONNECTED` or the errno for
+    /// `BIND_FAILED`.
+    pub value: u32,
+    /// The endpoint the event relates to.
+    pub endpoint: String,
+}
+
+/// Stream of lifecycle events of a socket.
+///
+/// Use the `monitor` method of any socket type to create one. Only one monitor can be
+/// active on a socket at a time; creating another one replaces the previous one, which then
+/// stops yielding events.
+pub struct Monitor {
+    socket: ZmqSocket,
+}
+
+impl Monitor {
+    pub(crate) fn new(target: &ZmqSocket, events: &[SocketEvent]) -> Result<Self, SocketError> {
+        let context = target.context().ok_or(SocketError::InvalidContext)?;
+        let endpoint = format!(
+            "inproc://async-zmq.monitor.{}",
+            MONITOR_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        let mask = events
+            .iter()
+            .fold(0, |mask, event| mask | i32::from(event.to_raw()));
+
+        target.as_socket().monitor(&endpoint, mask)?;
+        let socket = context.socket(SocketType::PAIR)?;
+        socket.connect(&endpoint)?;
+
+        Ok(Self {
+            socket: ZmqSocket::register(socket, Some(context.clone()), None, target.reactor())?,
+        })
+    }
+
+    /// Represent as `Socket` from zmq crate in case you want to call its methods.
+    pub fn as_raw_socket(&self) -> &zmq::Socket {
+        self.socket.as_socket()
+    }
+}
+
+impl Stream for Monitor {
+    type Item = Result<MonitorEvent, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let msg = match ready!(self.socket.poll_next(cx)) {
+                Some(msg) => msg.map_err(|e| self.socket.recv_error(e))?,
+                None => return Poll::Ready(None),
+            };
+            // The first frame is a 16-bit event id followed by a 32-bit value, the second one
+            // the affected endpoint. Anything else is not a message we know how to read.
+            if msg.len() != 2 || msg[0].len() != 6 {
+                continue;
+            }
+            let raw = u16::from_le_bytes([msg[0][0], msg[0][1]]);
+            let event = match KNOWN_EVENTS.iter().find(|event| event.to_raw() == raw) {
+                Some(event) => *event,
+                None => continue,
+            };
+
+            return Poll::Ready(Some(Ok(MonitorEvent {
+                event,
+                value: u32::from_le_bytes([msg[0][2], msg[0][3], msg[0][4], msg[0][5]]),
+                endpoint: String::from_utf8_lossy(&msg[1]).into_owned(),
+            })));
+        }
+    }
+}
+
+impl FusedStream for Monitor {
+    fn is_terminated(&self) -> bool {
+        self.socket.is_terminated()
+    }
+}
+
+/// Change in the liveness of a peer, as reported by [`PeerLiveness`].
+///
+/// [`PeerLiveness`]: struct.PeerLiveness.html
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerStatus {
+    /// The peer at the endpoint completed its handshake and is exchanging messages.
+    Alive(String),
+    /// The peer at the endpoint was disconnected, either because the connection was closed or
+    /// because no heartbeat arrived within the heartbeat timeout.
+    Dead(String),
+}
+
+/// Stream of peers becoming alive or dead on a socket.
+///
+/// Use the `peer_liveness` method of any socket type to create one. It is built on a
+/// [`Monitor`], so it replaces any monitor already active on the socket.
+///
+/// ØMQ does not tell a heartbeat timeout apart from any other disconnection, so both are
+/// reported as [`PeerStatus::Dead`].
+///
+/// [`Monitor`]: struct.Monitor.html
+/// [`PeerStatus::Dead`]: enum.PeerStatus.html#variant.Dead
+pub struct PeerLiveness(Monitor);
+
+impl PeerLiveness {
+    pub(crate) fn new(target: &ZmqSocket) -> Result<Self, SocketError> {
+        Ok(Self(Monitor::new(
+            target,
+            &[SocketEvent::HANDSHAKE_SUCCEEDED, SocketEvent::DISCONNECTED],
+        )?))
+    }
+}
+
+impl Stream for PeerLiveness {
+    type Item = Result<PeerStatus, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let monitor = &mut self.get_mut().0;
+        loop {
+            let event = match ready!(Pin::new(&mut *monitor).poll_next(cx)) {
+                Some(Ok(event)) => event,
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            };
+            let status = match event.event {
+                SocketEvent::HANDSHAKE_SUCCEEDED => PeerStatus::Alive(event.endpoint),
+                SocketEvent::DISCONNECTED => PeerStatus::Dead(event.endpoint),
+                _ => continue,
+            };
+            return Poll::Ready(Some(Ok(status)));
+        }
+    }
+}
+
+impl FusedStream for PeerLiveness {
+    fn is_terminated(&self) -> bool {
+        self.0.is_terminated()
+    }
+}
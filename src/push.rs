@@ -14,7 +14,7 @@
 //! async fn main() -> Result<()> {
 //!     let mut zmq = async_zmq::push("tcp://127.0.0.1:5555")?.bind()?;
 //!
-//!     zmq.send(vec!["topic", "broadcast message"].into()).await?;
+//!     zmq.send(vec!["topic", "broadcast message"]).await?;
 //!     Ok(())
 //! }
 //! ```
@@ -27,61 +27,89 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
-use zmq::{Message, SocketType};
+use zmq::SocketType;
 
 use crate::{
     reactor::{AsRawSocket, ZmqSocket},
-    socket::{MultipartIter, Sender, SocketBuilder},
+    socket::{socket_methods, IntoMultipart, Multipart, Sender, SocketBuilder},
+    traits::{self, sealed::Sealed, ZmqSend, ZmqSocketExt},
     SendError, Sink, SocketError,
 };
 
 /// Create a ZMQ socket with PUSH type
-pub fn push<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Push<I, T>>, SocketError> {
-    Ok(SocketBuilder::new(SocketType::PUSH, endpoint))
+pub fn push(endpoint: &str) -> Result<SocketBuilder<'_, Push>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::PUSH, endpoint))
 }
 
 /// The async wrapper of ZMQ socket with PUSH type
-pub struct Push<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Sender<I, T>);
+pub struct Push(Sender);
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Push<I, T> {
+impl Push {
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    socket_methods!(send 0);
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Push<I, T> {
+impl<M: IntoMultipart> Sink<M> for Push {
     type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_ready(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 
-    fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        Pin::new(&mut self.get_mut().0)
-            .start_send(item)
-            .map_err(Into::into)
+    fn start_send(self: Pin<&mut Self>, item: M) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().0).start_send(item.into_multipart())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_flush(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_close(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Push<I, T> {
+impl ZmqSend for Push {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        traits::poll_send_sink(self, cx, msg)
+    }
+}
+
+impl ZmqSocketExt for Push {
+    fn socket_type(&self) -> SocketType {
+        SocketType::PUSH
+    }
+}
+
+impl Sealed for Push {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        &self.0.socket
+    }
+}
+
+impl From<ZmqSocket> for Push {
+    fn from(socket: ZmqSocket) -> Self {
+        Self(Sender::new(socket))
+    }
+}
+
+impl From<zmq::Socket> for Push {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
     fn from(socket: zmq::Socket) -> Self {
-        Self(Sender {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
-        })
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
     }
 }
@@ -1,10 +1,39 @@
+use std::cell::Cell;
 use std::io;
 
 use mio::unix::EventedFd;
 use mio::{Evented, Poll, PollOpt, Ready, Token};
-use zmq::Socket;
+use zmq::{Context, Socket};
 
-pub(crate) struct ZmqSocket(pub(crate) Socket);
+use crate::context::Registration;
+use crate::stats::Stats;
+use crate::traceparent::Envelope;
+
+/// Public only to appear in the sealed trait behind `ZmqSocketExt`, the module is private.
+pub struct ZmqSocket {
+    pub(crate) socket: Socket,
+    /// The context the socket was created in, if known. Needed to open inproc
+    /// sockets that talk to this one, like monitors.
+    pub(crate) context: Option<Context>,
+    /// The type of the socket, named by the errors of sends and receives.
+    pub(crate) socket_type: zmq::SocketType,
+    /// The endpoint the socket was bound or connected to, if known.
+    pub(crate) endpoint: Option<String>,
+    /// How many messages a `Sink` over this socket accepts before waiting for it to take them.
+    pub(crate) send_queue: usize,
+    /// Set once a receive failed with `ETERM`, which ends the streams over this socket.
+    pub(crate) terminated: Cell<bool>,
+    /// An error that cut a batch short after messages were already taken off the socket,
+    /// reported by the next receive instead.
+    pub(crate) deferred_error: Cell<Option<zmq::Error>>,
+    /// Traffic going through the socket.
+    pub(crate) stats: Stats,
+    /// Handles the trace context header frame, if enabled.
+    pub(crate) trace: Option<Envelope>,
+    /// Keeps the socket registered with the `ContextHandle` it was built from, if any.
+    /// Declared last so it is only dropped once the socket is closed.
+    pub(crate) registration: Option<Registration>,
+}
 
 impl Evented for ZmqSocket {
     fn register(
@@ -14,7 +43,7 @@ impl Evented for ZmqSocket {
         interest: Ready,
         opts: PollOpt,
     ) -> io::Result<()> {
-        EventedFd(&self.0.get_fd()?).register(poll, token, interest, opts)
+        EventedFd(&self.socket.get_fd()?).register(poll, token, interest, opts)
     }
 
     fn reregister(
@@ -24,10 +53,10 @@ impl Evented for ZmqSocket {
         interest: Ready,
         opts: PollOpt,
     ) -> io::Result<()> {
-        EventedFd(&self.0.get_fd()?).reregister(poll, token, interest, opts)
+        EventedFd(&self.socket.get_fd()?).reregister(poll, token, interest, opts)
     }
 
     fn deregister(&self, poll: &Poll) -> io::Result<()> {
-        EventedFd(&self.0.get_fd()?).deregister(poll)
+        EventedFd(&self.socket.get_fd()?).deregister(poll)
     }
 }
@@ -2,13 +2,27 @@
 pub(crate) mod evented;
 mod watcher;
 
-use crate::socket::{Multipart, MultipartIter};
-pub(crate) use watcher::Watcher;
+use crate::context::Registration;
+use crate::socket::Multipart;
+use crate::stats::{Direction, SocketStats};
+use crate::traceparent::TraceParent;
+use crate::{ErrorContext, Operation, RecvError, RequestReplyError, SendError, SocketError};
+pub use watcher::Reactor;
+pub(crate) use watcher::{Interrupter, Watcher};
 
-use futures::ready;
+use futures::{
+    channel::oneshot,
+    future::{poll_fn, select, Either},
+    ready,
+};
+use futures_timer::Delay;
+use std::cell::Cell;
+use std::collections::VecDeque;
 use std::io::{self, ErrorKind};
 use std::task::{Context, Poll};
-use zmq::Error;
+use std::thread;
+use std::time::Duration;
+use zmq::{Error, SocketType};
 
 /// Trait to get the raw zmq socket.
 pub trait AsRawSocket {
@@ -18,69 +32,456 @@ pub trait AsRawSocket {
 
 pub(crate) type ZmqSocket = Watcher<evented::ZmqSocket>;
 
+/// The messages of a batch, and their trace contexts if the socket handles them.
+pub(crate) type Batch = (Vec<Multipart>, Vec<Option<TraceParent>>);
+
 impl ZmqSocket {
     fn poll_event(&self, event: zmq::PollEvents) -> Result<(), io::Error> {
         if self.as_socket().get_events()?.contains(event) {
             Ok(())
         } else {
+            self.get_ref().stats.would_block();
             Err(io::Error::new(ErrorKind::WouldBlock, Error::EAGAIN))
         }
     }
 
-    pub(crate) fn send<I: Iterator<Item = T>, T: Into<zmq::Message>>(
+    /// Wait until ØMQ is ready for an operation in `direction`, keeping track of the time
+    /// spent waiting.
+    fn poll_ready(&self, cx: &mut Context<'_>, direction: Direction) -> Poll<()> {
+        let poll = match direction {
+            Direction::Send => self.poll_write_with(cx, |_| self.poll_event(zmq::POLLOUT)),
+            Direction::Recv => self.poll_read_with(cx, |_| self.poll_event(zmq::POLLIN)),
+        };
+        let stats = &self.get_ref().stats;
+        if poll.is_pending() {
+            stats.pending(direction);
+            return Poll::Pending;
+        }
+        stats.ready(direction);
+        #[cfg(feature = "metrics")]
+        stats.publish_wakeups(self.wakeups());
+        Poll::Ready(())
+    }
+
+    /// Add the trace context header frame to `msg`, if enabled.
+    pub(crate) fn inject_trace_context(&self, msg: &mut Multipart) {
+        if let Some(trace) = &self.get_ref().trace {
+            trace.inject(msg);
+        }
+    }
+
+    /// The trace context of the message received last, if enabled and it had one.
+    pub(crate) fn last_trace_context(&self) -> Option<TraceParent> {
+        self.get_ref().trace.as_ref()?.last()
+    }
+
+    /// A snapshot of the traffic that went through the socket.
+    pub(crate) fn stats(&self) -> SocketStats {
+        self.get_ref().stats.snapshot(self.wakeups())
+    }
+
+    /// The context the socket was created in, if it was built through [`SocketBuilder`].
+    ///
+    /// [`SocketBuilder`]: ../struct.SocketBuilder.html
+    pub(crate) fn context(&self) -> Option<&zmq::Context> {
+        self.get_ref().context.as_ref()
+    }
+
+    /// How many messages a `Sink` over this socket accepts before waiting for it.
+    pub(crate) fn send_queue(&self) -> usize {
+        self.get_ref().send_queue
+    }
+
+    /// Hand the frames of one message over to ØMQ.
+    ///
+    /// `frames` keeps what was not sent when this returns `Pending`, so the same message can
+    /// be resumed on the next call.
+    pub(crate) fn send(
+        &self,
+        cx: &mut Context<'_>,
+        frames: &mut VecDeque<zmq::Message>,
+    ) -> Poll<Result<(), Error>> {
+        ready!(self.poll_send_ready(cx))?;
+        // ROUTER and STREAM sockets report `POLLOUT` while the pipe of the addressed peer may
+        // still be full, wait for ØMQ to signal a change then.
+        self.poll_write_with(cx, |_| match self.send_ready(frames) {
+            Err(Error::EAGAIN) => {
+                self.get_ref().stats.would_block();
+                Err(io::Error::new(ErrorKind::WouldBlock, Error::EAGAIN))
+            }
+            sent => Ok(sent),
+        })
+        .map(|sent| sent.unwrap_or(Err(Error::EAGAIN)))
+    }
+
+    /// Hand `msg` over to ØMQ once it is ready, adding the trace context header frame first.
+    ///
+    /// `msg` is taken once ØMQ is ready and left in place while this returns `Pending`.
+    pub(crate) fn poll_send_msg(
         &self,
         cx: &mut Context<'_>,
-        buffer: &mut MultipartIter<I, T>,
+        msg: &mut Option<Multipart>,
     ) -> Poll<Result<(), Error>> {
-        let _ = ready!(self.poll_write_with(cx, |_| { self.poll_event(zmq::POLLOUT) }));
-        //ready!()?;
+        ready!(self.poll_send_ready(cx))?;
+        if let Some(mut msg) = msg.take() {
+            self.inject_trace_context(&mut msg);
+            self.send_ready(&mut msg.into())?;
+        }
+        Poll::Ready(Ok(()))
+    }
 
-        let mut buffer = buffer.0.by_ref().peekable();
-        while let Some(msg) = buffer.next() {
+    /// Wait until ØMQ is ready to send.
+    fn poll_send_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        if self.is_interrupted() {
+            return Poll::Ready(Err(Error::ETERM));
+        }
+        ready!(self.poll_ready(cx, Direction::Send));
+        Poll::Ready(Ok(()))
+    }
+
+    /// Hand the frames of a message over to ØMQ, which reported to be ready.
+    ///
+    /// Fails with `EAGAIN`, leaving `frames` as they were, if a ROUTER or STREAM socket cannot
+    /// send to the peer the message is addressed to yet.
+    fn send_ready(&self, frames: &mut VecDeque<zmq::Message>) -> Result<(), Error> {
+        let socket = self.get_ref();
+        let stats = &socket.stats;
+        let routed = matches!(socket.socket_type, SocketType::ROUTER | SocketType::STREAM);
+        let mut first = true;
+        while let Some(msg) = frames.pop_front() {
             let mut flags = zmq::DONTWAIT;
-            if buffer.peek().is_some() {
+            if !frames.is_empty() {
                 flags |= zmq::SNDMORE;
             }
 
+            let len = msg.len();
+            // ØMQ only ever refuses the first frame, which is the routing id for the sockets
+            // that may refuse it while reporting `POLLOUT`. Keep a copy to try it again.
+            let retry = (first && routed).then(|| zmq::Message::from(&msg[..]));
+            first = false;
             match self.as_socket().send(msg, flags) {
-                Ok(_) => {}
-                Err(Error::EAGAIN) => return Poll::Pending,
-                Err(e) => return Poll::Ready(Err(e)),
+                Ok(_) => stats.frame_sent(len),
+                Err(Error::EAGAIN) => match retry {
+                    Some(retry) => {
+                        frames.push_front(retry);
+                        return Err(Error::EAGAIN);
+                    }
+                    None => {
+                        // Any other socket only reports `POLLOUT` with a peer that can take
+                        // the message, so only the last peer going away in between gets here.
+                        // The frame went with it, drop the rest of the message the same way
+                        // ØMQ drops messages for peers that vanish.
+                        frames.clear();
+                        stats.message_dropped();
+                        return Ok(());
+                    }
+                },
+                Err(Error::EHOSTUNREACH) if socket.socket_type == SocketType::STREAM => {
+                    // The TCP connection closed before its disconnect was read, drop the data
+                    // like ØMQ drops what it still had queued for that connection.
+                    frames.clear();
+                    stats.message_dropped();
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
             }
         }
+        stats.message_sent();
 
-        Poll::Ready(Ok(()))
+        Ok(())
     }
 
     pub(crate) fn recv(&self, cx: &mut Context<'_>) -> Poll<Result<Multipart, Error>> {
-        let _ = ready!(self.poll_read_with(cx, |_| { self.poll_event(zmq::POLLIN) }));
+        let mut buffer = Vec::new();
+        ready!(self.recv_into(cx, &mut buffer))?;
+        Poll::Ready(Ok(buffer))
+    }
 
+    /// Receive a message along with the trace context it carried, if enabled and it had one.
+    pub(crate) fn recv_traced(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(Multipart, Option<TraceParent>), Error>> {
         let mut buffer = Vec::new();
+        let context = ready!(self.poll_recv_ready(cx, &mut buffer))?;
+        Poll::Ready(Ok((buffer, context)))
+    }
+
+    /// Receive a message into `buffer`, reusing the frames it already holds.
+    ///
+    /// `buffer` ends up with exactly as many frames as the message had.
+    pub(crate) fn recv_into(
+        &self,
+        cx: &mut Context<'_>,
+        buffer: &mut Multipart,
+    ) -> Poll<Result<(), Error>> {
+        self.poll_recv_ready(cx, buffer).map_ok(drop)
+    }
+
+    /// Wait for a message and receive it into `buffer`, returning its trace context.
+    fn poll_recv_ready(
+        &self,
+        cx: &mut Context<'_>,
+        buffer: &mut Multipart,
+    ) -> Poll<Result<Option<TraceParent>, Error>> {
+        if let Some(error) = self.get_ref().deferred_error.take() {
+            return Poll::Ready(Err(error));
+        }
+        if self.is_interrupted() {
+            return Poll::Ready(Err(Error::ETERM));
+        }
+        ready!(self.poll_ready(cx, Direction::Recv));
+
+        Poll::Ready(self.recv_ready(buffer))
+    }
+
+    /// Receive the next message as an item of a stream, which ends once ØMQ reported the
+    /// context terminated.
+    pub(crate) fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<Result<Multipart, Error>>> {
+        if self.is_terminated() {
+            return Poll::Ready(None);
+        }
+        self.until_terminated(ready!(self.recv(cx)))
+    }
+
+    /// Receive the next batch of at most `max` messages as an item of a stream, see
+    /// [`poll_next`](#method.poll_next) and [`recv_batch`](#method.recv_batch).
+    pub(crate) fn poll_next_batch(
+        &self,
+        cx: &mut Context<'_>,
+        max: usize,
+    ) -> Poll<Option<Result<Batch, Error>>> {
+        if self.is_terminated() {
+            return Poll::Ready(None);
+        }
+        self.until_terminated(ready!(self.recv_batch(cx, max)))
+    }
+
+    /// Whether a stream over this socket ended because the context was terminated.
+    pub(crate) fn is_terminated(&self) -> bool {
+        self.get_ref().terminated.get()
+    }
+
+    fn until_terminated<T>(&self, result: Result<T, Error>) -> Poll<Option<Result<T, Error>>> {
+        match result {
+            Err(Error::ETERM) => {
+                self.get_ref().terminated.set(true);
+                Poll::Ready(None)
+            }
+            result => Poll::Ready(Some(result)),
+        }
+    }
+
+    /// Receive up to `max` messages, waiting for the first one only.
+    ///
+    /// Further messages are taken for as long as `ZMQ_EVENTS` reports `POLLIN`, without going
+    /// back through the reactor. An error while taking them ends the batch with the messages
+    /// taken so far, and the next receive reports it.
+    ///
+    /// The trace contexts of the messages are only collected if the socket handles them, so
+    /// batches of other sockets do not allocate for them.
+    pub(crate) fn recv_batch(
+        &self,
+        cx: &mut Context<'_>,
+        max: usize,
+    ) -> Poll<Result<Batch, Error>> {
+        let traced = self.get_ref().trace.is_some();
+        let (msg, context) = ready!(self.recv_traced(cx))?;
+        let mut batch = vec![msg];
+        let mut contexts = Vec::new();
+        if traced {
+            contexts.push(context);
+        }
+        while batch.len() < max {
+            let mut buffer = Vec::new();
+            let received = self.as_socket().get_events().and_then(|events| {
+                if events.contains(zmq::POLLIN) {
+                    self.recv_ready(&mut buffer).map(Some)
+                } else {
+                    Ok(None)
+                }
+            });
+            match received {
+                Ok(Some(context)) => {
+                    batch.push(buffer);
+                    if traced {
+                        contexts.push(context);
+                    }
+                }
+                Ok(None) | Err(Error::EAGAIN) => break,
+                Err(error) => {
+                    self.get_ref().deferred_error.set(Some(error));
+                    break;
+                }
+            }
+        }
+
+        Poll::Ready(Ok((batch, contexts)))
+    }
+
+    /// Receive a message that ØMQ reported to be ready into `buffer`, returning its trace
+    /// context.
+    fn recv_ready(&self, buffer: &mut Multipart) -> Result<Option<TraceParent>, Error> {
+        let stats = &self.get_ref().stats;
+        let mut received = 0;
         let mut more = true;
 
         while more {
-            let mut msg = zmq::Message::new();
-            match self.as_socket().recv(&mut msg, zmq::DONTWAIT) {
+            if received == buffer.len() {
+                buffer.push(zmq::Message::new());
+            }
+            let msg = &mut buffer[received];
+            match self.as_socket().recv(msg, zmq::DONTWAIT) {
                 Ok(_) => {
                     more = msg.get_more();
-                    buffer.push(msg);
+                    received += 1;
+                    stats.frame_received(msg.len());
                 }
-                Err(e) => return Poll::Ready(Err(e)),
+                Err(e) => return Err(e),
             }
         }
+        buffer.truncate(received);
+        stats.message_received();
+        Ok(self
+            .get_ref()
+            .trace
+            .as_ref()
+            .and_then(|trace| trace.extract(buffer)))
+    }
 
-        Poll::Ready(Ok(buffer))
+    /// Where an error of `operation` on this socket occurred.
+    pub(crate) fn error_context(&self, operation: Operation) -> ErrorContext {
+        let socket = self.get_ref();
+        ErrorContext {
+            endpoint: socket.endpoint.clone(),
+            socket_type: Some(socket.socket_type),
+            operation: Some(operation),
+        }
+    }
+
+    /// Turn an error of sending on this socket into a `SendError` naming the socket.
+    pub(crate) fn send_error(&self, error: Error) -> SendError {
+        SendError::from(error).with_context(self.error_context(Operation::Send))
+    }
+
+    /// Turn an error of receiving on this socket into a `RecvError` naming the socket.
+    pub(crate) fn recv_error(&self, error: Error) -> RecvError {
+        RecvError::from(error).with_context(self.error_context(Operation::Recv))
+    }
+
+    /// Turn an error of `operation` on this REQ or REP socket into a `RequestReplyError`
+    /// naming the socket.
+    pub(crate) fn request_reply_error(
+        &self,
+        operation: Operation,
+        error: Error,
+    ) -> RequestReplyError {
+        RequestReplyError::from(error).with_context(self.error_context(operation))
+    }
+
+    /// Wait until `frames` have been handed over to ØMQ, giving up once `deadline` fires.
+    ///
+    /// Returns whether the whole message was sent.
+    pub(crate) async fn flush_until(
+        &self,
+        frames: &mut VecDeque<zmq::Message>,
+        deadline: &mut Delay,
+    ) -> Result<bool, Error> {
+        let flush = poll_fn(|cx| self.send(cx, frames));
+        match select(flush, deadline).await {
+            Either::Left((result, _)) => result.map(|_| true),
+            Either::Right(_) => Ok(false),
+        }
+    }
+
+    /// Deregister the socket from the reactor and close it.
+    ///
+    /// ØMQ keeps trying to deliver messages still queued on the socket for `linger`. If the
+    /// socket holds the last handle to its context, the context is terminated on a separate
+    /// thread, since that blocks until every queued message was either delivered or dropped.
+    pub(crate) async fn close(self, linger: Duration) -> Result<(), Error> {
+        let evented::ZmqSocket {
+            socket,
+            context,
+            registration,
+            ..
+        } = self.into_inner();
+        socket.set_linger(crate::socket::millis(linger))?;
+        drop(socket);
+        drop(registration);
+
+        if let Some(context) = context {
+            let (tx, rx) = oneshot::channel();
+            thread::spawn(move || {
+                drop(context);
+                let _ = tx.send(());
+            });
+            let _ = rx.await;
+        }
+
+        Ok(())
     }
 }
 
-impl From<zmq::Socket> for ZmqSocket {
-    fn from(socket: zmq::Socket) -> Self {
-        Watcher::new(evented::ZmqSocket(socket))
+impl ZmqSocket {
+    /// Register a socket in `reactor`, attaching it to the context handle it was built from if
+    /// there is one.
+    pub(crate) fn register(
+        socket: zmq::Socket,
+        context: Option<zmq::Context>,
+        registration: Option<Registration>,
+        reactor: &Reactor,
+    ) -> Result<Self, SocketError> {
+        let socket_type = socket.get_socket_type()?;
+        let mut watcher = Watcher::new(
+            evented::ZmqSocket {
+                socket,
+                context,
+                socket_type,
+                endpoint: None,
+                send_queue: 1,
+                terminated: Cell::new(false),
+                deferred_error: Cell::new(None),
+                stats: Default::default(),
+                trace: None,
+                registration: None,
+            },
+            reactor,
+        )
+        .map_err(|e| SocketError::ReactorUnavailable(e.kind()))?;
+        #[cfg(feature = "tracing")]
+        watcher
+            .get_ref()
+            .stats
+            .identify(watcher.token().0, socket_type);
+        if let Some(registration) = registration {
+            registration.attach(watcher.interrupter());
+            watcher.get_mut().registration = Some(registration);
+        }
+        Ok(watcher)
+    }
+
+    /// Register a raw socket in `reactor`, or the global one.
+    pub(crate) fn from_raw(
+        socket: zmq::Socket,
+        reactor: Option<&Reactor>,
+    ) -> Result<Self, SocketError> {
+        let reactor = match reactor {
+            Some(reactor) => reactor,
+            None => Reactor::global().map_err(|e| SocketError::ReactorUnavailable(e.kind()))?,
+        };
+        let endpoint = socket
+            .get_last_endpoint()?
+            .ok()
+            .filter(|endpoint| !endpoint.is_empty());
+        let mut socket = ZmqSocket::register(socket, None, None, reactor)?;
+        socket.get_mut().endpoint = endpoint;
+        Ok(socket)
     }
 }
 
 impl AsRawSocket for ZmqSocket {
     fn as_socket(&self) -> &zmq::Socket {
-        &self.get_ref().0
+        &self.get_ref().socket
     }
 }
@@ -1,10 +1,14 @@
 // TODO: async-std doesn't expose watcher at the moment. Remove this file. once our events are able to add to register.
 #![allow(dead_code)]
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+#[cfg(feature = "tracing")]
+use std::time::Instant;
 
 use mio::{self, Evented};
-use once_cell::sync::Lazy;
+use once_cell::sync::OnceCell;
 use slab::Slab;
 
 use std::io;
@@ -21,6 +25,13 @@ struct Entry {
 
     /// Tasks that are blocked on writing to this I/O handle.
     writers: Mutex<Writers>,
+
+    /// Flag indicating the handle was interrupted and should not be used any more.
+    /// (cf. `Interrupter::interrupt`)
+    interrupted: AtomicBool,
+
+    /// How many tasks blocked on this I/O handle the polling thread woke up.
+    wakeups: AtomicU64,
 }
 
 /// The set of `Waker`s interested in read readiness.
@@ -31,6 +42,9 @@ struct Readers {
     ready: bool,
     /// The `Waker`s blocked on reading.
     wakers: Vec<Waker>,
+    /// When the `Waker`s were last woken up, to trace how long the tasks took to get polled.
+    #[cfg(feature = "tracing")]
+    woken_at: Option<Instant>,
 }
 
 /// The set of `Waker`s interested in write readiness.
@@ -41,10 +55,13 @@ struct Writers {
     ready: bool,
     /// The `Waker`s blocked on writing.
     wakers: Vec<Waker>,
+    /// When the `Waker`s were last woken up, to trace how long the tasks took to get polled.
+    #[cfg(feature = "tracing")]
+    woken_at: Option<Instant>,
 }
 
 /// The state of a networking driver.
-struct Reactor {
+struct Driver {
     /// A mio instance that polls for new events.
     poller: mio::Poll,
 
@@ -56,109 +73,117 @@ struct Reactor {
 
     /// An identifier for the notification handle.
     notify_token: mio::Token,
+
+    /// Flag indicating the driver was shut down and its polling thread should exit.
+    shutdown: AtomicBool,
+
+    /// The polling thread, taken out when it is joined on shutdown.
+    thread: Mutex<Option<JoinHandle<io::Result<()>>>>,
 }
 
-impl Reactor {
-    /// Creates a new reactor for polling I/O events.
-    fn new() -> io::Result<Reactor> {
+impl Driver {
+    /// Creates a new driver for polling I/O events.
+    fn new() -> io::Result<Driver> {
         let poller = mio::Poll::new()?;
         let notify_reg = mio::Registration::new2();
 
-        let mut reactor = Reactor {
+        let mut driver = Driver {
             poller,
             entries: Mutex::new(Slab::new()),
             notify_reg,
             notify_token: mio::Token(0),
+            shutdown: AtomicBool::new(false),
+            thread: Mutex::new(None),
         };
 
         // Register a dummy I/O handle for waking up the polling thread.
-        let entry = reactor.register(&reactor.notify_reg.0)?;
-        reactor.notify_token = entry.token;
+        let entry = driver.register(&driver.notify_reg.0)?;
+        driver.notify_token = entry.token;
 
-        Ok(reactor)
+        Ok(driver)
     }
 
     /// Registers an I/O event source and returns its associated entry.
     fn register(&self, source: &dyn Evented) -> io::Result<Arc<Entry>> {
         let mut entries = self.entries.lock().unwrap();
 
+        // Refuse new sources once the polling thread is gone, nobody would wake them up.
+        if self.shutdown.load(Ordering::Acquire) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "the reactor was shut down",
+            ));
+        }
+
         // Reserve a vacant spot in the slab and use its key as the token value.
         let vacant = entries.vacant_entry();
         let token = mio::Token(vacant.key());
 
+        // Register the I/O event source in the poller.
+        let interest = mio::Ready::all();
+        let opts = mio::PollOpt::edge();
+        self.poller.register(source, token, interest, opts)?;
+
         // Allocate an entry and insert it into the slab.
         let entry = Arc::new(Entry {
             token,
             readers: Mutex::new(Readers {
                 ready: false,
                 wakers: Vec::new(),
+                #[cfg(feature = "tracing")]
+                woken_at: None,
             }),
             writers: Mutex::new(Writers {
                 ready: false,
                 wakers: Vec::new(),
+                #[cfg(feature = "tracing")]
+                woken_at: None,
             }),
+            interrupted: AtomicBool::new(false),
+            wakeups: AtomicU64::new(0),
         });
         vacant.insert(entry.clone());
-
-        // Register the I/O event source in the poller.
-        let interest = mio::Ready::all();
-        let opts = mio::PollOpt::edge();
-        self.poller.register(source, token, interest, opts)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(token = token.0, "registered I/O handle");
 
         Ok(entry)
     }
 
     /// Deregisters an I/O event source associated with an entry.
     fn deregister(&self, source: &dyn Evented, entry: &Entry) -> io::Result<()> {
-        // Deregister the I/O object from the mio instance.
-        self.poller.deregister(source)?;
-
         // Remove the entry associated with the I/O object.
         self.entries.lock().unwrap().remove(entry.token.0);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(token = entry.token.0, "deregistered I/O handle");
 
-        Ok(())
+        // Deregister the I/O object from the mio instance.
+        self.poller.deregister(source)
     }
 
-    // fn notify(&self) {
-    //     self.notify_reg
-    //         .1
-    //         .set_readiness(mio::Ready::readable())
-    //         .unwrap();
-    // }
+    /// Wakes up the polling thread.
+    fn notify(&self) -> io::Result<()> {
+        self.notify_reg.1.set_readiness(mio::Ready::readable())
+    }
 }
 
-/// The state of the global networking driver.
-static REACTOR: Lazy<Reactor> = Lazy::new(|| {
-    // Spawn a thread that waits on the poller for new events and wakes up tasks blocked on I/O
-    // handles.
-    std::thread::Builder::new()
-        .name("async-std/net".to_string())
-        .spawn(move || {
-            main_loop().expect("async networking thread has panicked");
-        })
-        .expect("cannot start a thread driving blocking tasks");
-
-    Reactor::new().expect("cannot initialize reactor")
-});
-
-/// Waits on the poller for new events and wakes up tasks blocked on I/O handles.
-fn main_loop() -> io::Result<()> {
-    let reactor = &REACTOR;
+/// Waits on the poller for new events and wakes up tasks blocked on I/O handles, until the
+/// driver is shut down.
+fn main_loop(driver: &Driver) -> io::Result<()> {
     let mut events = mio::Events::with_capacity(1000);
 
-    loop {
+    while !driver.shutdown.load(Ordering::Acquire) {
         // Block on the poller until at least one new event comes in.
-        reactor.poller.poll(&mut events, None)?;
+        driver.poller.poll(&mut events, None)?;
 
         // Lock the entire entry table while we're processing new events.
-        let entries = reactor.entries.lock().unwrap();
+        let entries = driver.entries.lock().unwrap();
 
         for event in events.iter() {
             let token = event.token();
 
-            if token == reactor.notify_token {
+            if token == driver.notify_token {
                 // If this is the notification token, we just need the notification state.
-                reactor.notify_reg.1.set_readiness(mio::Ready::empty())?;
+                driver.notify_reg.1.set_readiness(mio::Ready::empty())?;
             } else {
                 // Otherwise, look for the entry associated with this token.
                 if let Some(entry) = entries.get(token.0) {
@@ -169,6 +194,13 @@ fn main_loop() -> io::Result<()> {
                     if !(readiness & reader_interests()).is_empty() {
                         let mut readers = entry.readers.lock().unwrap();
                         readers.ready = true;
+                        entry
+                            .wakeups
+                            .fetch_add(readers.wakers.len() as u64, Ordering::Relaxed);
+                        #[cfg(feature = "tracing")]
+                        if !readers.wakers.is_empty() {
+                            readers.woken_at = Some(Instant::now());
+                        }
                         for w in readers.wakers.drain(..) {
                             w.wake();
                         }
@@ -178,6 +210,13 @@ fn main_loop() -> io::Result<()> {
                     if !(readiness & writer_interests()).is_empty() {
                         let mut writers = entry.writers.lock().unwrap();
                         writers.ready = true;
+                        entry
+                            .wakeups
+                            .fetch_add(writers.wakers.len() as u64, Ordering::Relaxed);
+                        #[cfg(feature = "tracing")]
+                        if !writers.wakers.is_empty() {
+                            writers.woken_at = Some(Instant::now());
+                        }
                         for w in writers.wakers.drain(..) {
                             w.wake();
                         }
@@ -186,18 +225,129 @@ fn main_loop() -> io::Result<()> {
             }
         }
     }
+
+    Ok(())
+}
+
+/// The global reactor, used by sockets that are not given one.
+static GLOBAL: OnceCell<Reactor> = OnceCell::new();
+
+/// Counter used to give every reactor thread its own name.
+static REACTOR_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A handle to a reactor: a thread polling registered sockets and waking up the tasks waiting
+/// on them.
+///
+/// Sockets use a global reactor unless [`SocketBuilder::with_reactor`] is given another one.
+/// Separate reactors are useful to keep unrelated runtimes from sharing a polling thread, or to
+/// tear everything down in tests.
+///
+/// Handles are cheap to clone and every clone refers to the same reactor. Sockets keep a
+/// handle to the reactor they are registered in, and once the last handle is dropped the
+/// polling thread exits on its own.
+///
+/// [`SocketBuilder::with_reactor`]: ../struct.SocketBuilder.html#method.with_reactor
+#[derive(Clone)]
+pub struct Reactor(Arc<Owner>);
+
+/// The driver as seen from the handles, as opposed to the polling thread which keeps a driver
+/// of its own.
+struct Owner(Arc<Driver>);
+
+impl Drop for Owner {
+    fn drop(&mut self) {
+        // Nobody can register sockets or wait on them any more, have the polling thread exit
+        // without waiting for it.
+        self.0.shutdown.store(true, Ordering::Release);
+        let _ = self.0.notify();
+    }
+}
+
+impl Reactor {
+    /// Create a new reactor and start its polling thread.
+    pub fn new() -> io::Result<Reactor> {
+        let driver = Arc::new(Driver::new()?);
+
+        // Spawn a thread that waits on the poller for new events and wakes up tasks blocked on
+        // I/O handles.
+        let thread = {
+            let driver = driver.clone();
+            std::thread::Builder::new()
+                .name(format!(
+                    "async-zmq/reactor-{}",
+                    REACTOR_ID.fetch_add(1, Ordering::Relaxed)
+                ))
+                .spawn(move || main_loop(&driver))?
+        };
+        *driver.thread.lock().unwrap() = Some(thread);
+
+        Ok(Reactor(Arc::new(Owner(driver))))
+    }
+
+    fn driver(&self) -> &Driver {
+        &(self.0).0
+    }
+
+    /// The global reactor, started on first use.
+    pub fn global() -> io::Result<&'static Reactor> {
+        GLOBAL.get_or_try_init(Reactor::new)
+    }
+
+    /// Whether [`shutdown`](#method.shutdown) was called on this reactor or one of its clones.
+    pub fn is_shutdown(&self) -> bool {
+        self.driver().shutdown.load(Ordering::Acquire)
+    }
+
+    /// Shut the reactor down and wait for its polling thread to exit.
+    ///
+    /// Every socket registered in it is interrupted: pending and further operations on it fail
+    /// with `ETERM`. Registering new sockets fails from now on.
+    pub fn shutdown(&self) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("shutting down reactor");
+        {
+            // Set the flag while holding the entry table, so no socket can register
+            // concurrently and miss the interruption.
+            let entries = self.driver().entries.lock().unwrap();
+            self.driver().shutdown.store(true, Ordering::Release);
+            for (_, entry) in entries.iter() {
+                Interrupter(entry.clone()).interrupt();
+            }
+        }
+
+        // If the polling thread cannot be woken up it is stuck in the poller for good, so
+        // there is nothing to join.
+        if self.driver().notify().is_ok() {
+            if let Some(thread) = self.driver().thread.lock().unwrap().take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Reactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reactor")
+            .field("shutdown", &self.is_shutdown())
+            .finish()
+    }
 }
 
 /// An I/O handle powered by the networking driver.
 ///
 /// This handle wraps an I/O event source and exposes a "futurized" interface on top of it,
 /// implementing traits `AsyncRead` and `AsyncWrite`.
-pub(crate) struct Watcher<T: Evented> {
+///
+/// Public only to appear in the sealed trait behind `ZmqSocketExt`, the module is private.
+pub struct Watcher<T: Evented> {
     /// Data associated with the I/O handle.
     entry: Arc<Entry>,
 
     /// The I/O event source.
     source: Option<T>,
+
+    /// The reactor the I/O event source is registered in.
+    reactor: Reactor,
 }
 
 impl<T: Evented> Watcher<T> {
@@ -205,13 +355,17 @@ impl<T: Evented> Watcher<T> {
     ///
     /// The provided I/O event source will be kept registered inside the reactor's poller for the
     /// lifetime of the returned I/O handle.
-    pub(crate) fn new(source: T) -> Watcher<T> {
-        Watcher {
-            entry: REACTOR
-                .register(&source)
-                .expect("cannot register an I/O event source"),
+    pub(crate) fn new(source: T, reactor: &Reactor) -> io::Result<Watcher<T>> {
+        Ok(Watcher {
+            entry: reactor.driver().register(&source)?,
             source: Some(source),
-        }
+            reactor: reactor.clone(),
+        })
+    }
+
+    /// Returns the reactor the I/O handle is registered in.
+    pub(crate) fn reactor(&self) -> &Reactor {
+        &self.reactor
     }
 
     /// Returns a reference to the inner I/O event source.
@@ -219,6 +373,11 @@ impl<T: Evented> Watcher<T> {
         self.source.as_ref().unwrap()
     }
 
+    /// Returns a mutable reference to the inner I/O event source.
+    pub(crate) fn get_mut(&mut self) -> &mut T {
+        self.source.as_mut().unwrap()
+    }
+
     /// Polls the inner I/O source for a non-blocking read operation.
     ///
     /// If the operation returns an error of the `io::ErrorKind::WouldBlock` kind, the current task
@@ -231,6 +390,12 @@ impl<T: Evented> Watcher<T> {
     where
         F: FnMut(&'a T) -> io::Result<R>,
     {
+        #[cfg(feature = "tracing")]
+        trace_wakeup(
+            &self.entry,
+            &mut self.entry.readers.lock().unwrap().woken_at,
+        );
+
         // If the operation isn't blocked, return its result.
         match f(self.source.as_ref().unwrap()) {
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
@@ -240,6 +405,13 @@ impl<T: Evented> Watcher<T> {
         // Lock the waker list.
         let mut readers = self.entry.readers.lock().unwrap();
 
+        // An interruption that raced with us already drained the wakers, have the task poll
+        // again to notice it.
+        if self.is_interrupted() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
         // Try running the operation again.
         match f(self.source.as_ref().unwrap()) {
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
@@ -268,6 +440,12 @@ impl<T: Evented> Watcher<T> {
     where
         F: FnMut(&'a T) -> io::Result<R>,
     {
+        #[cfg(feature = "tracing")]
+        trace_wakeup(
+            &self.entry,
+            &mut self.entry.writers.lock().unwrap().woken_at,
+        );
+
         // If the operation isn't blocked, return its result.
         match f(self.source.as_ref().unwrap()) {
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
@@ -277,6 +455,13 @@ impl<T: Evented> Watcher<T> {
         // Lock the waker list.
         let mut writers = self.entry.writers.lock().unwrap();
 
+        // An interruption that raced with us already drained the wakers, have the task poll
+        // again to notice it.
+        if self.is_interrupted() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
         // Try running the operation again.
         match f(self.source.as_ref().unwrap()) {
             Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
@@ -302,6 +487,13 @@ impl<T: Evented> Watcher<T> {
     pub(crate) fn poll_read_ready(&self, cx: &mut Context<'_>) -> Poll<()> {
         // Lock the waker list.
         let mut readers = self.entry.readers.lock().unwrap();
+
+        // An interruption that raced with us already drained the wakers, have the task poll
+        // again to notice it.
+        if self.is_interrupted() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
         if readers.ready {
             return Poll::Ready(());
         }
@@ -320,6 +512,13 @@ impl<T: Evented> Watcher<T> {
     pub(crate) fn poll_write_ready(&self, cx: &mut Context<'_>) -> Poll<()> {
         // Lock the waker list.
         let mut writers = self.entry.writers.lock().unwrap();
+
+        // An interruption that raced with us already drained the wakers, have the task poll
+        // again to notice it.
+        if self.is_interrupted() {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
         if writers.ready {
             return Poll::Ready(());
         }
@@ -330,25 +529,65 @@ impl<T: Evented> Watcher<T> {
         Poll::Pending
     }
 
+    /// Returns a handle that can interrupt this I/O handle from another thread.
+    pub(crate) fn interrupter(&self) -> Interrupter {
+        Interrupter(self.entry.clone())
+    }
+
+    /// Returns the token this I/O handle is registered with.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn token(&self) -> mio::Token {
+        self.entry.token
+    }
+
+    /// Returns how many tasks blocked on this I/O handle the polling thread woke up so far.
+    pub(crate) fn wakeups(&self) -> u64 {
+        self.entry.wakeups.load(Ordering::Relaxed)
+    }
+
+    /// Returns whether this I/O handle was interrupted through its `Interrupter`.
+    pub(crate) fn is_interrupted(&self) -> bool {
+        self.entry.interrupted.load(Ordering::Acquire)
+    }
+
     /// Deregisters and returns the inner I/O source.
     ///
     /// This method is typically used to convert `Watcher`s to raw file descriptors/handles.
-    #[allow(dead_code)]
     pub(crate) fn into_inner(mut self) -> T {
         let source = self.source.take().unwrap();
-        REACTOR
-            .deregister(&source, &self.entry)
-            .expect("cannot deregister I/O event source");
+        // The source is handed back either way, a failure leaves nothing to clean up.
+        let _ = self.reactor.driver().deregister(&source, &self.entry);
         source
     }
 }
 
+/// Handle to interrupt a registered I/O handle.
+///
+/// Interrupting marks the handle as unusable and wakes up every task blocked on it, so they
+/// can observe the interruption instead of waiting for an event that may never come.
+#[derive(Clone, Debug)]
+pub(crate) struct Interrupter(Arc<Entry>);
+
+impl Interrupter {
+    /// Interrupts the I/O handle and wakes up all of its blocked tasks.
+    pub(crate) fn interrupt(&self) {
+        self.0.interrupted.store(true, Ordering::Release);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(token = self.0.token.0, "interrupted I/O handle");
+
+        for w in self.0.readers.lock().unwrap().wakers.drain(..) {
+            w.wake();
+        }
+        for w in self.0.writers.lock().unwrap().wakers.drain(..) {
+            w.wake();
+        }
+    }
+}
+
 impl<T: Evented> Drop for Watcher<T> {
     fn drop(&mut self) {
         if let Some(ref source) = self.source {
-            REACTOR
-                .deregister(source, &self.entry)
-                .expect("cannot deregister I/O event source");
+            let _ = self.reactor.driver().deregister(source, &self.entry);
         }
     }
 }
@@ -362,6 +601,18 @@ impl<T: Evented + fmt::Debug> fmt::Debug for Watcher<T> {
     }
 }
 
+/// Traces how long after its wakeup a task blocked on `entry` got polled.
+#[cfg(feature = "tracing")]
+fn trace_wakeup(entry: &Entry, woken_at: &mut Option<Instant>) {
+    if let Some(woken_at) = woken_at.take() {
+        tracing::trace!(
+            token = entry.token.0,
+            latency = ?woken_at.elapsed(),
+            "polled after wakeup"
+        );
+    }
+}
+
 /// Returns a mask containing flags that interest tasks reading from I/O handles.
 #[inline]
 fn reader_interests() -> mio::Ready {
@@ -369,9 +620,12 @@ fn reader_interests() -> mio::Ready {
 }
 
 /// Returns a mask containing flags that interest tasks writing into I/O handles.
+///
+/// The file descriptor of a ØMQ socket only ever becomes readable, whichever of `POLLIN` or
+/// `POLLOUT` changed, so writers have to be woken up on read readiness as well.
 #[inline]
 fn writer_interests() -> mio::Ready {
-    mio::Ready::writable() | hup()
+    mio::Ready::readable() | mio::Ready::writable() | hup()
 }
 
 /// Returns a flag containing the hangup status.
@@ -28,59 +28,104 @@ use std::{
     pin::Pin,
     sync::atomic::{AtomicBool, Ordering},
     task::{Context, Poll},
+    time::Duration,
 };
 
-use zmq::{Message, SocketType};
+use zmq::SocketType;
 
 use crate::{
     reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, MultipartIter, Sender, SocketBuilder},
-    RecvError, RequestReplyError, SocketError,
+    socket::{socket_methods, IntoMultipart, Multipart, Sender, SocketBuilder},
+    traits::{sealed::Sealed, ZmqRecv, ZmqSend, ZmqSocketExt},
+    CloseError, Operation, RecvError, RequestReplyError, SendError, SocketError, TraceParent,
 };
 
-use futures::{future::poll_fn, Stream};
+use futures::{future::poll_fn, ready, stream::FusedStream, Stream};
 
 /// Create a ZMQ socket with REP type
-pub fn reply<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Reply<I, T>>, SocketError> {
-    Ok(SocketBuilder::new(SocketType::REP, endpoint))
+pub fn reply(endpoint: &str) -> Result<SocketBuilder<'_, Reply>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::REP, endpoint))
 }
 
 /// The async wrapper of ZMQ socket with REP type
-pub struct Reply<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
-    inner: Sender<I, T>,
+pub struct Reply {
+    inner: Sender,
     received: AtomicBool,
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Reply<I, T> {
-    fn from(socket: zmq::Socket) -> Self {
+impl ZmqSend for Reply {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        ready!(self.inner.socket.poll_send_msg(cx, msg))
+            .map_err(|e| self.inner.socket.send_error(e))?;
+        self.received.store(false, Ordering::Relaxed);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl ZmqRecv for Reply {
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        let msg =
+            ready!(self.inner.socket.recv(cx)).map_err(|e| self.inner.socket.recv_error(e))?;
+        self.received.store(true, Ordering::Relaxed);
+        Poll::Ready(Ok(msg))
+    }
+}
+
+impl ZmqSocketExt for Reply {
+    fn socket_type(&self) -> SocketType {
+        SocketType::REP
+    }
+}
+
+impl Sealed for Reply {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        &self.inner.socket
+    }
+}
+
+impl From<ZmqSocket> for Reply {
+    fn from(socket: ZmqSocket) -> Self {
         Self {
-            inner: Sender {
-                socket: ZmqSocket::from(socket),
-                buffer: None,
-            },
+            inner: Sender::new(socket),
             received: AtomicBool::new(false),
         }
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
+impl From<zmq::Socket> for Reply {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
+    fn from(socket: zmq::Socket) -> Self {
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
+    }
+}
+
+impl Reply {
     /// Receive request from REQ/DEALER socket. This should be the first method to be called, and then
     /// continue with receive/send pattern in synchronous way.
     pub async fn recv(&self) -> Result<Multipart, RequestReplyError> {
-        let msg = poll_fn(|cx| self.inner.socket.recv(cx)).await?;
+        let msg = poll_fn(|cx| self.inner.socket.recv(cx))
+            .await
+            .map_err(|e| self.inner.socket.request_reply_error(Operation::Recv, e))?;
         self.received.store(true, Ordering::Relaxed);
         Ok(msg)
     }
 
     /// Send reply to REQ/DEALER socket. [`recv`](#method.recv) must be called first in order to reply.
-    pub async fn send<S: Into<MultipartIter<I, T>>>(
-        &self,
-        msg: S,
-    ) -> Result<(), RequestReplyError> {
-        let mut msg = msg.into();
-        poll_fn(move |cx| self.inner.socket.send(cx, &mut msg)).await?;
+    pub async fn send<M: IntoMultipart>(&self, msg: M) -> Result<(), RequestReplyError> {
+        let mut msg = Some(msg.into_multipart());
+        poll_fn(|cx| self.inner.socket.poll_send_msg(cx, &mut msg))
+            .await
+            .map_err(|e| self.inner.socket.request_reply_error(Operation::Send, e))?;
         self.received.store(false, Ordering::Relaxed);
         Ok(())
     }
@@ -89,12 +134,54 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Reply<I, T> {
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.inner.socket.as_socket()
     }
+
+    socket_methods!();
+
+    /// The W3C trace context of the message received last, if the socket was built with
+    /// [`trace_context`] and the message carried one.
+    ///
+    /// Use [`recv_traced`](#method.recv_traced) to get the context along with its message,
+    /// this one is only accurate as long as no other message was received since.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub fn last_trace_context(&self) -> Option<TraceParent> {
+        self.inner.socket.last_trace_context()
+    }
+
+    /// Receive the next message along with its W3C trace context, if the socket was built
+    /// with [`trace_context`] and the message carried one.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub async fn recv_traced(&self) -> Result<(Multipart, Option<TraceParent>), RequestReplyError> {
+        let received = poll_fn(|cx| self.inner.socket.recv_traced(cx))
+            .await
+            .map_err(|e| self.inner.socket.request_reply_error(Operation::Recv, e))?;
+        self.received.store(true, Ordering::Relaxed);
+        Ok(received)
+    }
+
+    /// Close the socket, giving ØMQ at most `linger` to deliver anything still queued on it.
+    ///
+    /// Messages are only accepted by [`send`](#method.send) once ØMQ queued them, so this
+    /// always reports zero discarded messages.
+    pub async fn close(self, linger: Duration) -> Result<usize, CloseError> {
+        self.inner.close(linger).await
+    }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for Reply<I, T> {
+impl Stream for Reply {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Poll::Ready(Some(Ok(futures::ready!(self.inner.socket.recv(cx))?)))
+        self.inner
+            .socket
+            .poll_next(cx)
+            .map(|poll| poll.map(|result| result.map_err(|e| self.inner.socket.recv_error(e))))
+    }
+}
+
+impl FusedStream for Reply {
+    fn is_terminated(&self) -> bool {
+        self.inner.socket.is_terminated()
     }
 }
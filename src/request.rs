@@ -25,54 +25,66 @@
 
 use crate::{
     reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, MultipartIter, Sender, SocketBuilder},
-    RequestReplyError, SocketError,
+    socket::{socket_methods, IntoMultipart, Multipart, Sender, SocketBuilder},
+    traits::{sealed::Sealed, ZmqRecv, ZmqSend, ZmqSocketExt},
+    CloseError, Operation, RecvError, RequestReplyError, SendError, SocketError, TraceParent,
 };
-use futures::future::poll_fn;
+use futures::{future::poll_fn, ready};
 use std::sync::atomic::{AtomicBool, Ordering};
-use zmq::{Message, SocketType};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use zmq::SocketType;
 
 /// Create a ZMQ socket with REQ type
-pub fn request<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Request<I, T>>, SocketError> {
-    Ok(SocketBuilder::new(SocketType::REQ, endpoint))
+pub fn request(endpoint: &str) -> Result<SocketBuilder<'_, Request>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::REQ, endpoint))
 }
 
 /// The async wrapper of ZMQ socket with REQ type
-pub struct Request<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
-    inner: Sender<I, T>,
+pub struct Request {
+    inner: Sender,
     received: AtomicBool,
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Request<I, T> {
-    fn from(socket: zmq::Socket) -> Self {
+impl From<ZmqSocket> for Request {
+    fn from(socket: ZmqSocket) -> Self {
         Self {
-            inner: Sender {
-                socket: ZmqSocket::from(socket),
-                buffer: None,
-            },
+            inner: Sender::new(socket),
             received: AtomicBool::new(false),
         }
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
+impl From<zmq::Socket> for Request {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
+    fn from(socket: zmq::Socket) -> Self {
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
+    }
+}
+
+impl Request {
     /// Send request to REP/ROUTER socket. This should be the first method to be called, and then
     /// continue with send/receive pattern in synchronous way.
-    pub async fn send<S: Into<MultipartIter<I, T>>>(
-        &self,
-        msg: S,
-    ) -> Result<(), RequestReplyError> {
-        let mut msg = msg.into();
-        poll_fn(move |cx| self.inner.socket.send(cx, &mut msg)).await?;
+    pub async fn send<M: IntoMultipart>(&self, msg: M) -> Result<(), RequestReplyError> {
+        let mut msg = Some(msg.into_multipart());
+        poll_fn(|cx| self.inner.socket.poll_send_msg(cx, &mut msg))
+            .await
+            .map_err(|e| self.inner.socket.request_reply_error(Operation::Send, e))?;
         self.received.store(false, Ordering::Relaxed);
         Ok(())
     }
 
     /// Receive reply from REP/ROUTER socket. [`send`](#method.send) must be called first in order to receive reply.
     pub async fn recv(&self) -> Result<Multipart, RequestReplyError> {
-        let msg = poll_fn(|cx| self.inner.socket.recv(cx)).await?;
+        let msg = poll_fn(|cx| self.inner.socket.recv(cx))
+            .await
+            .map_err(|e| self.inner.socket.request_reply_error(Operation::Recv, e))?;
         self.received.store(true, Ordering::Relaxed);
         Ok(msg)
     }
@@ -81,4 +93,71 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Request<I, T> {
     pub async fn as_raw_socket(&self) -> &zmq::Socket {
         self.inner.socket.as_socket()
     }
+
+    socket_methods!();
+
+    /// The W3C trace context of the message received last, if the socket was built with
+    /// [`trace_context`] and the message carried one.
+    ///
+    /// Use [`recv_traced`](#method.recv_traced) to get the context along with its message,
+    /// this one is only accurate as long as no other message was received since.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub fn last_trace_context(&self) -> Option<TraceParent> {
+        self.inner.socket.last_trace_context()
+    }
+
+    /// Receive the next message along with its W3C trace context, if the socket was built
+    /// with [`trace_context`] and the message carried one.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub async fn recv_traced(&self) -> Result<(Multipart, Option<TraceParent>), RequestReplyError> {
+        let received = poll_fn(|cx| self.inner.socket.recv_traced(cx))
+            .await
+            .map_err(|e| self.inner.socket.request_reply_error(Operation::Recv, e))?;
+        self.received.store(true, Ordering::Relaxed);
+        Ok(received)
+    }
+
+    /// Close the socket, giving ØMQ at most `linger` to deliver anything still queued on it.
+    ///
+    /// Messages are only accepted by [`send`](#method.send) once ØMQ queued them, so this
+    /// always reports zero discarded messages.
+    pub async fn close(self, linger: Duration) -> Result<usize, CloseError> {
+        self.inner.close(linger).await
+    }
+}
+
+impl ZmqSend for Request {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        ready!(self.inner.socket.poll_send_msg(cx, msg))
+            .map_err(|e| self.inner.socket.send_error(e))?;
+        self.received.store(false, Ordering::Relaxed);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl ZmqRecv for Request {
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        let msg =
+            ready!(self.inner.socket.recv(cx)).map_err(|e| self.inner.socket.recv_error(e))?;
+        self.received.store(true, Ordering::Relaxed);
+        Poll::Ready(Ok(msg))
+    }
+}
+
+impl ZmqSocketExt for Request {
+    fn socket_type(&self) -> SocketType {
+        SocketType::REQ
+    }
+}
+
+impl Sealed for Request {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        &self.inner.socket
+    }
 }
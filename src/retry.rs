@@ -0,0 +1,243 @@
+//! Retrying to bind or connect a socket
+//!
+//! Binding fails with `EADDRINUSE` while the previous instance of a service still holds the
+//! address, e.g. during a rolling restart, and binding an `ipc://` endpoint fails while its
+//! directory does not exist yet. [`SocketBuilder::bind_with_retry`] and
+//! [`SocketBuilder::connect_with_retry`] keep trying according to a [`RetryPolicy`], waiting
+//! longer after every failed attempt.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use async_zmq::{Result, RetryPolicy};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let policy = RetryPolicy::new()
+//!         .initial_delay(Duration::from_millis(50))
+//!         .max_attempts(20)
+//!         .on_attempt(|attempt| {
+//!             if let Some(error) = attempt.error {
+//!                 eprintln!("attempt {} failed: {}", attempt.number, error);
+//!             }
+//!         });
+//!     let zmq = async_zmq::pull("tcp://127.0.0.1:5555")?
+//!         .bind_with_retry(policy)
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`SocketBuilder::bind_with_retry`]: ../struct.SocketBuilder.html#method.bind_with_retry
+//! [`SocketBuilder::connect_with_retry`]: ../struct.SocketBuilder.html#method.connect_with_retry
+//! [`RetryPolicy`]: struct.RetryPolicy.html
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use futures_timer::Delay;
+
+use crate::{Error, ErrorKind, SocketError};
+
+/// When and how often to retry a failed bind or connect.
+///
+/// The delay starts at [`initial_delay`](#method.initial_delay) and is multiplied by
+/// [`multiplier`](#method.multiplier) after every attempt, up to
+/// [`max_delay`](#method.max_delay). By default up to 10 attempts are made, starting with
+/// 100ms, doubling up to 10s, with 20% jitter, and only errors that can go away on their own
+/// are retried, see [`retry_if`](#method.retry_if).
+pub struct RetryPolicy {
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: f64,
+    jitter: f64,
+    max_attempts: Option<usize>,
+    classifier: Box<dyn Fn(&Error) -> bool + Send + Sync>,
+    on_attempt: Option<AttemptCallback>,
+}
+
+type AttemptCallback = Box<dyn FnMut(&Attempt<'_>) + Send>;
+
+/// A bind or connect attempt, as reported to [`RetryPolicy::on_attempt`].
+///
+/// [`RetryPolicy::on_attempt`]: struct.RetryPolicy.html#method.on_attempt
+#[derive(Debug)]
+pub struct Attempt<'a> {
+    /// The number of the attempt, starting at 1.
+    pub number: usize,
+    /// Why the attempt failed, or `None` if it succeeded.
+    pub error: Option<&'a Error>,
+    /// How long until the next attempt, or `None` if there is none.
+    pub retry_in: Option<Duration>,
+}
+
+impl RetryPolicy {
+    /// Create the default policy.
+    pub fn new() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_attempts: Some(10),
+            classifier: Box::new(is_transient),
+            on_attempt: None,
+        }
+    }
+
+    /// Wait `delay` after the first failed attempt.
+    pub fn initial_delay(self, delay: Duration) -> Self {
+        Self {
+            initial_delay: delay,
+            ..self
+        }
+    }
+
+    /// Never wait longer than `delay` between two attempts.
+    pub fn max_delay(self, delay: Duration) -> Self {
+        Self {
+            max_delay: delay,
+            ..self
+        }
+    }
+
+    /// Multiply the delay by `multiplier` after every failed attempt. Values below 1 are
+    /// treated as 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `multiplier` is not finite.
+    pub fn multiplier(self, multiplier: f64) -> Self {
+        assert!(multiplier.is_finite(), "retry multiplier must be finite");
+        Self {
+            multiplier: multiplier.max(1.0),
+            ..self
+        }
+    }
+
+    /// Shorten every delay by a random fraction of up to `jitter`, so that many sockets
+    /// retrying at once spread out. Clamped to `0.0..=1.0`, zero disables jitter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `jitter` is not finite.
+    pub fn jitter(self, jitter: f64) -> Self {
+        assert!(jitter.is_finite(), "retry jitter must be finite");
+        Self {
+            jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Give up after `attempts` attempts, the first one included. Zero is treated as one.
+    pub fn max_attempts(self, attempts: usize) -> Self {
+        Self {
+            max_attempts: Some(attempts.max(1)),
+            ..self
+        }
+    }
+
+    /// Keep trying until an attempt succeeds or fails with an error that is not retried.
+    pub fn unlimited_attempts(self) -> Self {
+        Self {
+            max_attempts: None,
+            ..self
+        }
+    }
+
+    /// Only retry errors for which `classifier` returns true.
+    ///
+    /// By default `EADDRINUSE`, `EADDRNOTAVAIL`, `ENODEV` and `ENOENT` are retried, which
+    /// cover an address still held by another process, an interface or a directory that is
+    /// not there yet.
+    pub fn retry_if<F>(self, classifier: F) -> Self
+    where
+        F: Fn(&Error) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            classifier: Box::new(classifier),
+            ..self
+        }
+    }
+
+    /// Call `callback` after every attempt, successful or not.
+    pub fn on_attempt<F>(self, callback: F) -> Self
+    where
+        F: FnMut(&Attempt<'_>) + Send + 'static,
+    {
+        Self {
+            on_attempt: Some(Box::new(callback)),
+            ..self
+        }
+    }
+
+    /// Run `attempt` until it succeeds, fails with an error that is not retried, or the
+    /// attempts run out.
+    pub(crate) async fn run<T>(
+        mut self,
+        mut attempt: impl FnMut() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut delay = self.initial_delay;
+        let mut number = 1;
+        loop {
+            let result = attempt();
+            let retry_in = match &result {
+                Err(error)
+                    if (self.classifier)(error)
+                        && !matches!(self.max_attempts, Some(max) if number >= max) =>
+                {
+                    Some(self.jittered(delay))
+                }
+                _ => None,
+            };
+            if let Some(callback) = &mut self.on_attempt {
+                callback(&Attempt {
+                    number,
+                    error: result.as_ref().err(),
+                    retry_in,
+                });
+            }
+
+            match retry_in {
+                Some(retry_in) => Delay::new(retry_in).await,
+                None => return result,
+            }
+            // Saturate rather than overflow, however many attempts there were.
+            delay = Duration::try_from_secs_f64(delay.as_secs_f64() * self.multiplier)
+                .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+            number += 1;
+        }
+    }
+
+    fn jittered(&self, delay: Duration) -> Duration {
+        let delay = delay.min(self.max_delay);
+        if self.jitter == 0.0 {
+            return delay;
+        }
+        // A fresh `RandomState` is randomly keyed, which is all the randomness jitter needs.
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        Duration::try_from_secs_f64(delay.as_secs_f64() * (1.0 - self.jitter * random))
+            .unwrap_or(delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The default classifier of [`RetryPolicy`](struct.RetryPolicy.html).
+fn is_transient(error: &Error) -> bool {
+    matches!(
+        error.kind(),
+        ErrorKind::Socket(
+            SocketError::AddressInUse
+                | SocketError::AddressNotAvailable
+                | SocketError::NonexistentInterface
+                | SocketError::Unexpected(zmq::Error::ENOENT)
+        )
+    )
+}
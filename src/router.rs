@@ -24,68 +24,148 @@ use std::task::{Context, Poll};
 
 use crate::{
     reactor::{AsRawSocket, ZmqSocket},
-    socket::{Broker, Multipart, MultipartIter, SocketBuilder},
-    RecvError, SendError, Sink, SocketError, Stream,
+    socket::{socket_methods, Broker, IntoMultipart, Multipart, RecvBatch, SocketBuilder},
+    traits::{self, sealed::Sealed, ZmqRecv, ZmqSend, ZmqSocketExt},
+    RecvError, SendError, Sink, SocketError, Stream, TraceParent,
 };
-use zmq::{Message, SocketType};
+use futures::stream::FusedStream;
+use zmq::SocketType;
 
 /// Create a ZMQ socket with ROUTER type
-pub fn router<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, Router<I, T>>, SocketError> {
-    Ok(SocketBuilder::new(SocketType::ROUTER, endpoint))
+pub fn router(endpoint: &str) -> Result<SocketBuilder<'_, Router>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::ROUTER, endpoint))
 }
 
 /// The async wrapper of ZMQ socket with ROUTER type
-pub struct Router<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Broker<I, T>);
+pub struct Router(Broker);
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Router<I, T> {
+impl Router {
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    socket_methods!(send 0);
+
+    /// The W3C trace context of the message received last, if the socket was built with
+    /// [`trace_context`] and the message carried one.
+    ///
+    /// Use [`recv_traced`](#method.recv_traced) to get the context along with its message,
+    /// this one is only accurate as long as no other message was received since.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub fn last_trace_context(&self) -> Option<TraceParent> {
+        self.0.socket.last_trace_context()
+    }
+
+    /// Receive the next message along with its W3C trace context, if the socket was built
+    /// with [`trace_context`] and the message carried one.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub async fn recv_traced(&mut self) -> Result<(Multipart, Option<TraceParent>), RecvError> {
+        self.0.recv_traced().await
+    }
+
+    /// Receive the next message into `buffer`, reusing the frames it already holds instead of
+    /// allocating new ones.
+    ///
+    /// `buffer` is resized to the number of frames received. Together with a
+    /// [`MultipartBuf`] this keeps a receive loop free of per-message allocations.
+    ///
+    /// [`MultipartBuf`]: ../buf/struct.MultipartBuf.html
+    pub async fn recv_into(&mut self, buffer: &mut Multipart) -> Result<(), RecvError> {
+        self.0.recv_into(buffer).await
+    }
+
+    /// Stream received messages in batches of at most `max`, see [`RecvBatch`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    ///
+    /// [`RecvBatch`]: ../struct.RecvBatch.html
+    pub fn recv_batch(&mut self, max: usize) -> RecvBatch<'_> {
+        RecvBatch::new(&self.0.socket, max)
+    }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Router<I, T> {
+impl<M: IntoMultipart> Sink<M> for Router {
     type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_ready(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 
-    fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        Pin::new(&mut self.get_mut().0)
-            .start_send(item)
-            .map_err(Into::into)
+    fn start_send(self: Pin<&mut Self>, item: M) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().0).start_send(item.into_multipart())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_flush(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_close(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for Router<I, T> {
+impl Stream for Router {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().0)
-            .poll_next(cx)
-            .map(|poll| poll.map(|result| result.map_err(Into::into)))
+        Pin::new(&mut self.get_mut().0).poll_next(cx)
+    }
+}
+
+impl FusedStream for Router {
+    fn is_terminated(&self) -> bool {
+        self.0.socket.is_terminated()
+    }
+}
+
+impl ZmqSend for Router {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        traits::poll_send_sink(self, cx, msg)
+    }
+}
+
+impl ZmqRecv for Router {
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        traits::poll_recv_stream(self, cx)
+    }
+}
+
+impl ZmqSocketExt for Router {
+    fn socket_type(&self) -> SocketType {
+        SocketType::ROUTER
+    }
+}
+
+impl Sealed for Router {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        &self.0.socket
+    }
+}
+
+impl From<ZmqSocket> for Router {
+    fn from(socket: ZmqSocket) -> Self {
+        Self(Broker::new(socket))
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for Router<I, T> {
+impl From<zmq::Socket> for Router {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
     fn from(socket: zmq::Socket) -> Self {
-        Self(Broker {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
-        })
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
     }
 }
@@ -0,0 +1,406 @@
+//! Concurrent request-reply over DEALER and ROUTER sockets
+//!
+//! A REQ socket waits for the reply to every request before it sends the next one. An
+//! [`RpcClient`] sends requests over a [`Dealer`] instead, tagging each with a correlation id,
+//! so any number of them can be in flight at once, and hands every reply to the call that
+//! matches its id. An [`RpcServer`] receives the requests on a [`Router`], runs a handler for
+//! each of them, up to a limit at once, and routes every reply back to the client it came from.
+//!
+//! Requests travel as an empty delimiter frame, an 8 byte big-endian correlation id and the
+//! frames of the request. The server replies with whatever envelope preceded the delimiter, the
+//! delimiter, the correlation id and the frames of the reply, so brokers forwarding between
+//! ROUTER and DEALER sockets can sit in between.
+//!
+//! The client needs its [`RpcDriver`] to be running, which does the actual sending and
+//! receiving: spawn [`RpcDriver::run`] on the executor of your choice.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use async_zmq::{rpc::{RpcClient, RpcServer}, Multipart, Result};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let router = async_zmq::router("tcp://127.0.0.1:5555")?.bind()?;
+//!     let server = RpcServer::new(router, |request: Multipart| async move { request })
+//!         .max_concurrency(32);
+//!     async_std::task::spawn(server.run());
+//!
+//!     let dealer = async_zmq::dealer("tcp://127.0.0.1:5555")?.connect()?;
+//!     let (client, driver) = RpcClient::new(dealer);
+//!     async_std::task::spawn(driver.run());
+//!
+//!     let client = client.timeout(Duration::from_secs(5));
+//!     let (first, second) = futures::join!(client.call(vec!["first"]), client.call(vec!["second"]));
+//!     println!("{:?} {:?}", first?, second?);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`RpcClient`]: struct.RpcClient.html
+//! [`RpcServer`]: struct.RpcServer.html
+//! [`RpcDriver`]: struct.RpcDriver.html
+//! [`RpcDriver::run`]: struct.RpcDriver.html#method.run
+//! [`Dealer`]: ../dealer/struct.Dealer.html
+//! [`Router`]: ../router/struct.Router.html
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+use futures::channel::{mpsc, oneshot};
+use futures::future::{poll_fn, select, Either};
+use futures::stream::FuturesUnordered;
+use futures_timer::Delay;
+use zmq::Message;
+
+use crate::{
+    Dealer, IntoMultipart, Multipart, RecvErrorKind, Router, RpcError, SendErrorKind, SinkExt,
+    StreamExt, ZmqSend,
+};
+
+/// The state the handles of a client share with its driver.
+#[derive(Default)]
+struct Shared {
+    next_id: AtomicU64,
+    /// The calls waiting for a reply, by correlation id.
+    pending: Mutex<HashMap<u64, oneshot::Sender<Multipart>>>,
+}
+
+/// Adds the time left until the deadline of a call to its request, which excludes the
+/// delimiter and the correlation id.
+pub(crate) type StampDeadline = fn(&mut Multipart, Duration);
+
+/// A request on its way to the driver.
+struct Request {
+    /// The correlation id of the call.
+    id: u64,
+    frames: Multipart,
+    /// When the call times out, and how to tell the peer about it.
+    deadline: Option<(Instant, StampDeadline)>,
+}
+
+impl Request {
+    /// The frames to send, with the time left until the deadline as of now.
+    fn into_frames(self) -> Multipart {
+        let mut frames = self.frames;
+        if let Some((deadline, stamp)) = self.deadline {
+            let mut request = frames.split_off(2);
+            stamp(
+                &mut request,
+                deadline.saturating_duration_since(Instant::now()),
+            );
+            frames.append(&mut request);
+        }
+        frames
+    }
+}
+
+/// Sends requests and waits for their replies, see the [module documentation].
+///
+/// Clones share the same driver and can all have calls in flight at once.
+///
+/// [module documentation]: index.html
+#[derive(Clone)]
+pub struct RpcClient {
+    requests: mpsc::Sender<Request>,
+    shared: Arc<Shared>,
+    timeout: Option<Duration>,
+    stamp_deadline: Option<StampDeadline>,
+}
+
+/// Sends the requests of an [`RpcClient`] and receives their replies, see
+/// [`run`](#method.run).
+///
+/// [`RpcClient`]: struct.RpcClient.html
+pub struct RpcDriver {
+    dealer: Dealer,
+    requests: mpsc::Receiver<Request>,
+    shared: Arc<Shared>,
+}
+
+/// Removes a call from the pending ones once it completed or was dropped.
+struct Pending<'a> {
+    shared: &'a Shared,
+    id: u64,
+}
+
+impl Drop for Pending<'_> {
+    fn drop(&mut self) {
+        self.shared.pending.lock().unwrap().remove(&self.id);
+    }
+}
+
+impl RpcClient {
+    /// Create a client sending its requests over `dealer`, and the driver that has to run for
+    /// the calls to complete.
+    pub fn new(dealer: Dealer) -> (Self, RpcDriver) {
+        let (requests, receiver) = mpsc::channel(0);
+        let shared = Arc::new(Shared::default());
+        let client = Self {
+            requests,
+            shared: shared.clone(),
+            timeout: None,
+            stamp_deadline: None,
+        };
+        let driver = RpcDriver {
+            dealer,
+            requests: receiver,
+            shared,
+        };
+        (client, driver)
+    }
+
+    /// Fail calls with [`RpcError::TimedOut`] if no reply arrived within `timeout`, counting
+    /// from the call. Calls wait for as long as it takes by default.
+    ///
+    /// [`RpcError::TimedOut`]: ../errors/enum.RpcError.html#variant.TimedOut
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Have `stamp` add the time left until the timeout to every request as it is sent.
+    #[cfg(feature = "serde")]
+    pub(crate) fn stamp_deadline(self, stamp: StampDeadline) -> Self {
+        Self {
+            stamp_deadline: Some(stamp),
+            ..self
+        }
+    }
+
+    /// Send `request` and wait for its reply.
+    ///
+    /// Dropping the returned future cancels the call: a reply arriving afterwards is discarded.
+    pub async fn call<M: IntoMultipart>(&self, request: M) -> Result<Multipart, RpcError> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        self.shared.pending.lock().unwrap().insert(id, sender);
+        let _pending = Pending {
+            shared: &self.shared,
+            id,
+        };
+
+        let mut frames = vec![Message::new(), Message::from(&id.to_be_bytes()[..])];
+        frames.extend(request.into_multipart());
+        let request = Request {
+            id,
+            frames,
+            deadline: deadline.zip(self.stamp_deadline),
+        };
+        let mut requests = self.requests.clone();
+        let exchange = async move {
+            requests.send(request).await.map_err(|_| RpcError::Closed)?;
+            receiver.await.map_err(|_| RpcError::Closed)
+        };
+
+        match self.timeout {
+            Some(timeout) => {
+                futures::pin_mut!(exchange);
+                match select(exchange, Delay::new(timeout)).await {
+                    Either::Left((reply, _)) => reply,
+                    Either::Right(_) => Err(RpcError::TimedOut),
+                }
+            }
+            None => exchange.await,
+        }
+    }
+}
+
+impl RpcDriver {
+    /// Send requests and hand out replies until every [`RpcClient`] was dropped, the context
+    /// was terminated or the socket failed.
+    ///
+    /// Calls still waiting for a reply once this returns, or once the driver or the future
+    /// returned by this method is dropped, fail with [`RpcError::Closed`]. So do calls whose
+    /// request cannot be sent, e.g. because its peer is gone. Replies that match no waiting
+    /// call, e.g. because the call timed out, are discarded. Interrupted receives are retried,
+    /// only errors ØMQ does not document end the driver early.
+    ///
+    /// [`RpcClient`]: struct.RpcClient.html
+    /// [`RpcError::Closed`]: ../errors/enum.RpcError.html#variant.Closed
+    pub async fn run(mut self) -> Result<(), RpcError> {
+        let mut request = None;
+        // The correlation id of the request being handed over to ØMQ. Replies keep being
+        // received meanwhile, the peer may be waiting for room to send them before it reads any
+        // more requests.
+        let mut sending = None;
+        poll_fn(|cx| loop {
+            if sending.is_none() {
+                match self.requests.poll_next_unpin(cx) {
+                    Poll::Ready(Some(next)) => {
+                        sending = Some(next.id);
+                        request = Some(next.into_frames());
+                    }
+                    // Every client was dropped.
+                    Poll::Ready(None) => return Poll::Ready(Ok(())),
+                    Poll::Pending => {}
+                }
+            }
+            let sent = match sending.map(|id| (id, self.dealer.poll_send(cx, &mut request))) {
+                Some((_, Poll::Ready(Ok(())))) => true,
+                Some((id, Poll::Ready(Err(e)))) => match e.kind() {
+                    SendErrorKind::ContextTerminated => return Poll::Ready(Ok(())),
+                    // The request is lost, fail its call right away.
+                    SendErrorKind::HostUnreachable
+                    | SendErrorKind::InvalidMessage
+                    | SendErrorKind::Interrupted => {
+                        self.shared.pending.lock().unwrap().remove(&id);
+                        true
+                    }
+                    SendErrorKind::Unexpected(_) => return Poll::Ready(Err(e.into())),
+                },
+                Some((_, Poll::Pending)) | None => false,
+            };
+            if sent {
+                sending = None;
+            }
+            match self.dealer.poll_next_unpin(cx) {
+                Poll::Ready(Some(Ok(reply))) => self.resolve(reply),
+                Poll::Ready(Some(Err(e))) => match e.kind() {
+                    RecvErrorKind::ContextTerminated => return Poll::Ready(Ok(())),
+                    RecvErrorKind::Interrupted => {}
+                    RecvErrorKind::Unexpected(_) => return Poll::Ready(Err(e.into())),
+                },
+                // The context was terminated.
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending if !sent => return Poll::Pending,
+                Poll::Pending => {}
+            }
+        })
+        .await
+    }
+
+    /// Hand `reply` to the call waiting for it, if there is one.
+    fn resolve(&self, mut reply: Multipart) {
+        if reply.len() < 2 || !reply[0].is_empty() {
+            return;
+        }
+        let id = match <[u8; 8]>::try_from(&reply[1][..]) {
+            Ok(id) => u64::from_be_bytes(id),
+            Err(_) => return,
+        };
+        if let Some(sender) = self.shared.pending.lock().unwrap().remove(&id) {
+            reply.drain(..2);
+            let _ = sender.send(reply);
+        }
+    }
+}
+
+impl Drop for RpcDriver {
+    fn drop(&mut self) {
+        // Nothing hands out replies any more, fail the calls waiting for one.
+        self.shared.pending.lock().unwrap().clear();
+    }
+}
+
+/// Answers the requests of [`RpcClient`]s, see the [module documentation].
+///
+/// [`RpcClient`]: struct.RpcClient.html
+/// [module documentation]: index.html
+pub struct RpcServer<F> {
+    router: Router,
+    handler: F,
+    max_concurrency: usize,
+}
+
+impl<F, Fut> RpcServer<F>
+where
+    F: Fn(Multipart) -> Fut,
+    Fut: Future<Output = Multipart>,
+{
+    /// Create a server receiving requests on `router` and answering each with the reply
+    /// `handler` returns for it.
+    pub fn new(router: Router, handler: F) -> Self {
+        Self {
+            router,
+            handler,
+            max_concurrency: 16,
+        }
+    }
+
+    /// Handle at most `max` requests at once, further ones wait in ØMQ's queue. 16 by default,
+    /// zero is treated as one.
+    pub fn max_concurrency(self, max: usize) -> Self {
+        Self {
+            max_concurrency: max.max(1),
+            ..self
+        }
+    }
+
+    /// Handle requests until the context was terminated.
+    ///
+    /// The handlers run concurrently within the returned future. Requests that do not follow
+    /// the protocol are discarded, and so are replies that cannot be sent, e.g. because their
+    /// client disconnected. Interrupted receives are retried, only errors ØMQ does not document
+    /// end the server early.
+    pub async fn run(self) -> Result<(), RpcError> {
+        let Self {
+            mut router,
+            handler,
+            max_concurrency,
+        } = self;
+        let mut running = FuturesUnordered::new();
+        loop {
+            let next = if running.is_empty() {
+                Either::Left(router.next().await)
+            } else if running.len() >= max_concurrency {
+                Either::Right(running.next().await)
+            } else {
+                match select(router.next(), running.next()).await {
+                    Either::Left((request, _)) => Either::Left(request),
+                    Either::Right((reply, _)) => Either::Right(reply),
+                }
+            };
+            match next {
+                Either::Left(Some(Err(e))) => match e.kind() {
+                    RecvErrorKind::ContextTerminated => return Ok(()),
+                    RecvErrorKind::Interrupted => {}
+                    RecvErrorKind::Unexpected(_) => return Err(e.into()),
+                },
+                Either::Left(Some(Ok(request))) => {
+                    if let Some((envelope, request)) = split_envelope(request) {
+                        let reply = handler(request);
+                        running.push(async move {
+                            let mut frames = envelope;
+                            frames.extend(reply.await);
+                            frames
+                        });
+                    }
+                }
+                Either::Right(Some(reply)) => match router.send(reply).await {
+                    Ok(()) => {}
+                    Err(e) => match e.kind() {
+                        SendErrorKind::ContextTerminated => return Ok(()),
+                        // The reply is lost, e.g. because its client is gone.
+                        SendErrorKind::HostUnreachable
+                        | SendErrorKind::InvalidMessage
+                        | SendErrorKind::Interrupted => {}
+                        SendErrorKind::Unexpected(_) => return Err(e.into()),
+                    },
+                },
+                // The context was terminated.
+                Either::Left(None) => return Ok(()),
+                Either::Right(None) => {}
+            }
+        }
+    }
+}
+
+/// Split a request into the frames up to and including its correlation id, and the request
+/// itself.
+fn split_envelope(mut request: Multipart) -> Option<(Multipart, Multipart)> {
+    let delimiter = request.iter().position(|frame| frame.is_empty())?;
+    if request.get(delimiter + 1).map(|id| id.len()) != Some(8) {
+        return None;
+    }
+    let body = request.split_off(delimiter + 2);
+    Some((request, body))
+}
@@ -0,0 +1,395 @@
+//! Named methods with typed requests and replies over [`rpc`](../rpc/index.html)
+//!
+//! A [`ServiceServer`] dispatches the calls it receives on a [`Router`] to the handler
+//! registered for their method name, an `async fn(Req) -> Result<Resp, E>`, or one also taking
+//! the [`CallContext`] that tells the deadline of the call. A [`ServiceClient`] calls them over a
+//! [`Dealer`], by name with [`ServiceClient::call`] or through a typed [`Method`] handle.
+//! Requests and replies are serialized as JSON with serde, and any number of calls can be in
+//! flight at once, as with the [`rpc`](../rpc/index.html) module they are built on.
+//!
+//! A call travels as the method name, any header frames of the form `name: value`, and the
+//! serialized request. With a [timeout](struct.ServiceClient.html#method.timeout) set, the
+//! client sends a `deadline: <milliseconds>` header with the time left as the call goes out,
+//! and the server stops the handler once it passed. The server replies with an `ok` frame and
+//! the serialized reply, or an `error` frame, one of the codes `unknown-method`, `decode`,
+//! `handler` or `deadline-exceeded`, and a message, which the client turns into a
+//! [`ServiceError`].
+//!
+//! This module is only available with the `serde` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use async_zmq::{service::{ServiceClient, ServiceServer}, Result};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let router = async_zmq::router("tcp://127.0.0.1:5555")?.bind()?;
+//!     let server = ServiceServer::new(router)
+//!         .method("add", |(a, b): (i64, i64)| async move {
+//!             a.checked_add(b).ok_or("overflow")
+//!         })
+//!         .method_with_context("greet", |name: String, call| async move {
+//!             println!("{:?} left to greet {}", call.time_left(), name);
+//!             Ok::<_, std::convert::Infallible>(format!("Hello, {}!", name))
+//!         });
+//!     async_std::task::spawn(server.run());
+//!
+//!     let dealer = async_zmq::dealer("tcp://127.0.0.1:5555")?.connect()?;
+//!     let (client, driver) = ServiceClient::new(dealer);
+//!     async_std::task::spawn(driver.run());
+//!
+//!     let client = client.timeout(Duration::from_secs(5));
+//!     let sum: i64 = client.call("add", &(1, 2)).await?;
+//!     let greet = client.method::<str, String>("greet");
+//!     println!("{} {}", sum, greet.call("world").await?);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`ServiceServer`]: struct.ServiceServer.html
+//! [`ServiceClient`]: struct.ServiceClient.html
+//! [`ServiceClient::call`]: struct.ServiceClient.html#method.call
+//! [`Method`]: struct.Method.html
+//! [`CallContext`]: struct.CallContext.html
+//! [`ServiceError`]: ../errors/enum.ServiceError.html
+//! [`Dealer`]: ../dealer/struct.Dealer.html
+//! [`Router`]: ../router/struct.Router.html
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::{self, select, BoxFuture, Either, FutureExt};
+use futures_timer::Delay;
+use serde::{de::DeserializeOwned, Serialize};
+use zmq::Message;
+
+use crate::{
+    rpc::{RpcClient, RpcDriver, RpcServer},
+    Dealer, Multipart, Router, RpcError, ServiceError,
+};
+
+const OK: &[u8] = b"ok";
+const ERROR: &[u8] = b"error";
+const DEADLINE: &str = "deadline";
+
+/// A registered method, taking the serialized request.
+type Handler = Box<
+    dyn Fn(&[u8], CallContext) -> BoxFuture<'static, Result<Vec<u8>, ServiceError>> + Send + Sync,
+>;
+
+/// Calls the methods of a [`ServiceServer`], see the [module documentation].
+///
+/// Clones share the same driver and can all have calls in flight at once.
+///
+/// [`ServiceServer`]: struct.ServiceServer.html
+/// [module documentation]: index.html
+#[derive(Clone)]
+pub struct ServiceClient {
+    rpc: RpcClient,
+}
+
+impl ServiceClient {
+    /// Create a client calling methods over `dealer`, and the driver that has to run for the
+    /// calls to complete, see [`RpcDriver::run`].
+    ///
+    /// [`RpcDriver::run`]: ../rpc/struct.RpcDriver.html#method.run
+    pub fn new(dealer: Dealer) -> (Self, RpcDriver) {
+        let (rpc, driver) = RpcClient::new(dealer);
+        let rpc = rpc.stamp_deadline(stamp_deadline);
+        (Self { rpc }, driver)
+    }
+
+    /// Fail calls with [`RpcError::TimedOut`] if no reply arrived within `timeout`, counting
+    /// from the call, and have the server stop their handlers once it passed. Calls wait for as
+    /// long as it takes by default.
+    ///
+    /// [`RpcError::TimedOut`]: ../errors/enum.RpcError.html#variant.TimedOut
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            rpc: self.rpc.timeout(timeout),
+        }
+    }
+
+    /// Call `method` with `request` and wait for its reply.
+    ///
+    /// Dropping the returned future cancels the call: a reply arriving afterwards is discarded.
+    pub async fn call<Req, Resp>(&self, method: &str, request: &Req) -> Result<Resp, ServiceError>
+    where
+        Req: Serialize + ?Sized,
+        Resp: DeserializeOwned,
+    {
+        let body = serde_json::to_vec(request).map_err(|e| ServiceError::Encode(e.to_string()))?;
+        let frames = vec![Message::from(method), Message::from(body)];
+        let reply = self.rpc.call(frames).await?;
+        match reply.first().map(|status| &status[..]) {
+            Some(OK) if reply.len() == 2 => {
+                serde_json::from_slice(&reply[1]).map_err(|e| ServiceError::Decode(e.to_string()))
+            }
+            Some(ERROR) if reply.len() == 3 => Err(remote_error(&reply[1], &reply[2])),
+            _ => Err(ServiceError::Malformed),
+        }
+    }
+
+    /// A handle calling `method` with requests of type `Req` and replies of type `Resp`.
+    pub fn method<Req, Resp>(&self, method: &str) -> Method<Req, Resp>
+    where
+        Req: Serialize + ?Sized,
+        Resp: DeserializeOwned,
+    {
+        Method {
+            client: self.clone(),
+            name: method.to_owned(),
+            types: PhantomData,
+        }
+    }
+}
+
+/// Calls one method of a [`ServiceServer`] with typed requests and replies, see
+/// [`ServiceClient::method`].
+///
+/// [`ServiceServer`]: struct.ServiceServer.html
+/// [`ServiceClient::method`]: struct.ServiceClient.html#method.method
+pub struct Method<Req: ?Sized, Resp> {
+    client: ServiceClient,
+    name: String,
+    types: PhantomData<fn(&Req) -> Resp>,
+}
+
+impl<Req: ?Sized, Resp> Clone for Method<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            name: self.name.clone(),
+            types: PhantomData,
+        }
+    }
+}
+
+impl<Req, Resp> Method<Req, Resp>
+where
+    Req: Serialize + ?Sized,
+    Resp: DeserializeOwned,
+{
+    /// The name of the method.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Call the method with `request`, see [`ServiceClient::call`].
+    ///
+    /// [`ServiceClient::call`]: struct.ServiceClient.html#method.call
+    pub async fn call(&self, request: &Req) -> Result<Resp, ServiceError> {
+        self.client.call(&self.name, request).await
+    }
+}
+
+/// What a handler gets to know about the call it answers, see
+/// [`ServiceServer::method_with_context`].
+///
+/// [`ServiceServer::method_with_context`]: struct.ServiceServer.html#method.method_with_context
+#[derive(Clone, Debug)]
+pub struct CallContext {
+    deadline: Option<Instant>,
+}
+
+impl CallContext {
+    /// When the server gives up on the handler, `None` if the client set no timeout.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The time left until the [`deadline`](#method.deadline), zero once it passed.
+    pub fn time_left(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+/// Dispatches calls to the handlers registered for their method, see the
+/// [module documentation].
+///
+/// [module documentation]: index.html
+pub struct ServiceServer {
+    router: Router,
+    methods: HashMap<String, Handler>,
+    max_concurrency: usize,
+}
+
+impl ServiceServer {
+    /// Create a server receiving calls on `router`, with no methods yet.
+    pub fn new(router: Router) -> Self {
+        Self {
+            router,
+            methods: HashMap::new(),
+            max_concurrency: 16,
+        }
+    }
+
+    /// Answer calls of `name` with `handler`, replacing the handler registered for it before.
+    ///
+    /// Errors the handler returns are sent to the client as their `Display` message.
+    pub fn method<Req, Resp, E, F, Fut>(self, name: &str, handler: F) -> Self
+    where
+        Req: DeserializeOwned,
+        Resp: Serialize,
+        E: Display,
+        F: Fn(Req) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Resp, E>> + Send + 'static,
+    {
+        self.method_with_context(name, move |request, _| handler(request))
+    }
+
+    /// Answer calls of `name` with `handler`, like [`method`](#method.method), which also gets
+    /// the [`CallContext`] of the call.
+    ///
+    /// [`CallContext`]: struct.CallContext.html
+    pub fn method_with_context<Req, Resp, E, F, Fut>(mut self, name: &str, handler: F) -> Self
+    where
+        Req: DeserializeOwned,
+        Resp: Serialize,
+        E: Display,
+        F: Fn(Req, CallContext) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Resp, E>> + Send + 'static,
+    {
+        let handler: Handler = Box::new(move |body, call| match serde_json::from_slice(body) {
+            Ok(request) => handler(request, call)
+                .map(|reply| match reply {
+                    Ok(reply) => serde_json::to_vec(&reply).map_err(|e| {
+                        ServiceError::Handler(format!("cannot encode the reply: {}", e))
+                    }),
+                    Err(e) => Err(ServiceError::Handler(e.to_string())),
+                })
+                .boxed(),
+            Err(e) => future::ready(Err(ServiceError::Decode(e.to_string()))).boxed(),
+        });
+        self.methods.insert(name.to_owned(), handler);
+        self
+    }
+
+    /// Handle at most `max` calls at once, see [`RpcServer::max_concurrency`].
+    ///
+    /// [`RpcServer::max_concurrency`]: ../rpc/struct.RpcServer.html#method.max_concurrency
+    pub fn max_concurrency(self, max: usize) -> Self {
+        Self {
+            max_concurrency: max,
+            ..self
+        }
+    }
+
+    /// Handle calls until the context was terminated, see [`RpcServer::run`].
+    ///
+    /// The handlers run concurrently within the returned future.
+    ///
+    /// [`RpcServer::run`]: ../rpc/struct.RpcServer.html#method.run
+    pub async fn run(self) -> Result<(), RpcError> {
+        let methods = Arc::new(self.methods);
+        let server = RpcServer::new(self.router, move |request| {
+            dispatch(methods.clone(), request)
+        });
+        server.max_concurrency(self.max_concurrency).run().await
+    }
+}
+
+/// Run the handler `request` calls and encode its outcome as a reply.
+async fn dispatch(methods: Arc<HashMap<String, Handler>>, request: Multipart) -> Multipart {
+    let outcome = match parse_call(&request) {
+        Ok((method, deadline, body)) => match methods.get(method) {
+            Some(handler) => with_deadline(handler(body, CallContext { deadline }), deadline).await,
+            None => Err(ServiceError::UnknownMethod(method.to_owned())),
+        },
+        Err(e) => Err(e),
+    };
+    match outcome {
+        Ok(reply) => vec![Message::from(OK), Message::from(reply)],
+        Err(e) => {
+            let (code, message) = error_frames(&e);
+            vec![
+                Message::from(ERROR),
+                Message::from(code),
+                Message::from(message.as_str()),
+            ]
+        }
+    }
+}
+
+/// Split `request` into its method name, its deadline and its body.
+fn parse_call(request: &Multipart) -> Result<(&str, Option<Instant>, &[u8]), ServiceError> {
+    let decode = |message: &str| ServiceError::Decode(message.to_owned());
+    if request.len() < 2 {
+        return Err(decode("the call has no method or no body"));
+    }
+    let method = request[0]
+        .as_str()
+        .ok_or_else(|| decode("the method name is not UTF-8"))?;
+    let mut deadline = None;
+    for header in &request[1..request.len() - 1] {
+        let header = header
+            .as_str()
+            .ok_or_else(|| decode("a header is not UTF-8"))?;
+        match header.split_once(':') {
+            Some((DEADLINE, millis)) => {
+                let millis = millis
+                    .trim()
+                    .parse()
+                    .map_err(|_| decode("the deadline is not a number of milliseconds"))?;
+                deadline = Instant::now().checked_add(Duration::from_millis(millis));
+            }
+            // Headers this server does not know about.
+            Some(_) => {}
+            None => return Err(decode("a header is not of the form `name: value`")),
+        }
+    }
+    Ok((method, deadline, &request[request.len() - 1]))
+}
+
+/// Wait for `handler`, giving up once `deadline` passed.
+async fn with_deadline(
+    handler: BoxFuture<'static, Result<Vec<u8>, ServiceError>>,
+    deadline: Option<Instant>,
+) -> Result<Vec<u8>, ServiceError> {
+    match deadline {
+        Some(deadline) => {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match select(handler, Delay::new(left)).await {
+                Either::Left((reply, _)) => reply,
+                Either::Right(_) => Err(ServiceError::DeadlineExceeded),
+            }
+        }
+        None => handler.await,
+    }
+}
+
+/// Add the `deadline` header with the time `left` right after the method name of `request`.
+fn stamp_deadline(request: &mut Multipart, left: Duration) {
+    let header = format!("{}: {}", DEADLINE, left.as_millis());
+    request.insert(1, Message::from(header.as_str()));
+}
+
+/// The code and message an error of the server side travels as.
+fn error_frames(error: &ServiceError) -> (&'static str, String) {
+    match error {
+        ServiceError::UnknownMethod(method) => ("unknown-method", method.clone()),
+        ServiceError::Decode(message) => ("decode", message.clone()),
+        ServiceError::Handler(message) => ("handler", message.clone()),
+        ServiceError::DeadlineExceeded => ("deadline-exceeded", String::new()),
+        error => ("handler", error.to_string()),
+    }
+}
+
+/// The error the server reported with `code` and `message`.
+fn remote_error(code: &[u8], message: &[u8]) -> ServiceError {
+    let message = String::from_utf8_lossy(message).into_owned();
+    match code {
+        b"unknown-method" => ServiceError::UnknownMethod(message),
+        b"decode" => ServiceError::Decode(message),
+        b"handler" => ServiceError::Handler(message),
+        b"deadline-exceeded" => ServiceError::DeadlineExceeded,
+        _ => ServiceError::Malformed,
+    }
+}
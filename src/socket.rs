@@ -1,9 +1,18 @@
+use std::collections::VecDeque;
 use std::convert::Into;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
 
-use crate::{reactor::ZmqSocket, Message, Sink, Stream};
-use futures::ready;
+use crate::{
+    reactor::{AsRawSocket, Batch, ZmqSocket},
+    traceparent::{CurrentTraceParent, Envelope},
+    AnySocket, CloseError, ContextHandle, ErrorContext, Message, Operation, Reactor, RecvError,
+    RetryPolicy, SendError, Sink, SocketError, Stream, TraceParent,
+};
+use futures::{future::poll_fn, ready, stream::FusedStream};
+use futures_timer::Delay;
 use zmq::Error;
 
 /// Multipart Iterator for Sending under `Sink`.
@@ -29,6 +38,50 @@ impl<T: Into<Message>> From<T> for MultipartIter<std::vec::IntoIter<T>, T> {
     }
 }
 
+/// Conversion into the frames of one message, accepted by the `Sink` of every socket type.
+///
+/// It is implemented for [`Multipart`] and any other `Vec` of frames, for [`MultipartIter`]
+/// and for a single [`Message`], so each of them can be sent as is:
+///
+/// ```no_run
+/// use async_zmq::{Message, Result, SinkExt};
+///
+/// #[async_std::main]
+/// async fn main() -> Result<()> {
+///     let mut zmq = async_zmq::push("tcp://127.0.0.1:5555")?.bind()?;
+///
+///     zmq.send(vec!["topic", "message"]).await?;
+///     zmq.send(Message::from("single frame")).await?;
+///     Ok(())
+/// }
+/// ```
+///
+/// [`Multipart`]: type.Multipart.html
+/// [`MultipartIter`]: struct.MultipartIter.html
+/// [`Message`]: struct.Message.html
+pub trait IntoMultipart {
+    /// Turn `self` into the frames of a message.
+    fn into_multipart(self) -> Multipart;
+}
+
+impl<T: Into<Message>> IntoMultipart for Vec<T> {
+    fn into_multipart(self) -> Multipart {
+        self.into_iter().map(Into::into).collect()
+    }
+}
+
+impl<I: Iterator<Item = T>, T: Into<Message>> IntoMultipart for MultipartIter<I, T> {
+    fn into_multipart(self) -> Multipart {
+        self.0.map(Into::into).collect()
+    }
+}
+
+impl IntoMultipart for Message {
+    fn into_multipart(self) -> Multipart {
+        vec![self]
+    }
+}
+
 /// Alias type of Multipart.
 ///
 /// This is the type what we receive from zmq socket via [`Stream`]. Users can choose
@@ -38,23 +91,92 @@ impl<T: Into<Message>> From<T> for MultipartIter<std::vec::IntoIter<T>, T> {
 /// [`Stream`]: trait.Stream.html
 pub type Multipart = Vec<Message>;
 
+/// Security mechanism configured on a socket before it binds or connects.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Security<'a> {
+    /// Act as a PLAIN server, accepting clients authenticated through ZAP.
+    PlainServer,
+    /// Act as a PLAIN client with the given credentials.
+    PlainClient {
+        username: &'a str,
+        password: &'a str,
+    },
+}
+
 /// ZMQ socket builder. It lets user to either bind or connect the socket of their choice.
 pub struct SocketBuilder<'a, T> {
     pub(crate) context: Option<&'a zmq::Context>,
+    pub(crate) handle: Option<&'a ContextHandle>,
+    pub(crate) reactor: Option<&'a Reactor>,
     pub(crate) socket_type: zmq::SocketType,
     pub(crate) endpoint: &'a str,
+    pub(crate) security: Option<Security<'a>>,
+    pub(crate) zap_domain: Option<&'a str>,
+    pub(crate) heartbeat_ivl: Option<Duration>,
+    pub(crate) heartbeat_timeout: Option<Duration>,
+    pub(crate) heartbeat_ttl: Option<Duration>,
+    pub(crate) send_queue: Option<usize>,
+    pub(crate) trace_context: bool,
+    pub(crate) current_trace_parent: Option<CurrentTraceParent>,
     _phantom: std::marker::PhantomData<T>,
 }
 
+/// A socket type a [`SocketBuilder`] can build, which every socket type of this crate is.
+///
+/// The builder hands the socket types a socket already registered in a reactor, with the
+/// options of the builder applied, which is internal to this crate. The trait is sealed for that
+/// reason: it cannot be implemented outside of this crate, but generic code can name it.
+///
+/// [`SocketBuilder`]: struct.SocketBuilder.html
+pub trait BuildSocket: sealed::FromRegistered {
+    /// Wrap a socket created with the zmq crate, registering it in the global reactor.
+    ///
+    /// Unlike the `From<zmq::Socket>` implementations, which panic, this fails with
+    /// [`SocketError::ReactorUnavailable`] if the global reactor cannot be started or was shut
+    /// down.
+    ///
+    /// [`SocketError::ReactorUnavailable`]: errors/enum.SocketError.html#variant.ReactorUnavailable
+    fn from_raw(socket: zmq::Socket) -> Result<Self, SocketError> {
+        ZmqSocket::from_raw(socket, None).map(Self::from)
+    }
+
+    /// Wrap a socket created with the zmq crate, registering it in `reactor`, see
+    /// [`from_raw`](#method.from_raw).
+    fn from_raw_with_reactor(socket: zmq::Socket, reactor: &Reactor) -> Result<Self, SocketError> {
+        ZmqSocket::from_raw(socket, Some(reactor)).map(Self::from)
+    }
+}
+
+impl<T: sealed::FromRegistered> BuildSocket for T {}
+
+pub(crate) mod sealed {
+    use crate::reactor::ZmqSocket;
+
+    /// Lets [`BuildSocket`](../trait.BuildSocket.html) build the socket types.
+    pub trait FromRegistered: From<ZmqSocket> {}
+
+    impl<T: From<ZmqSocket>> FromRegistered for T {}
+}
+
 impl<'a, T> SocketBuilder<'a, T>
 where
-    T: From<zmq::Socket>,
+    T: BuildSocket,
 {
-    pub(crate) fn new(socket_type: zmq::SocketType, endpoint: &'a str) -> Self {
+    pub(crate) fn typed(socket_type: zmq::SocketType, endpoint: &'a str) -> Self {
         Self {
             context: None,
+            handle: None,
+            reactor: None,
             socket_type,
             endpoint,
+            security: None,
+            zap_domain: None,
+            heartbeat_ivl: None,
+            heartbeat_timeout: None,
+            heartbeat_ttl: None,
+            send_queue: None,
+            trace_context: false,
+            current_trace_parent: None,
             _phantom: Default::default(),
         }
     }
@@ -65,62 +187,407 @@ where
     }
 
     /// Create the zmq socket with given context
+    ///
+    /// Sockets created this way are not tracked by any [`ContextHandle`].
+    ///
+    /// [`ContextHandle`]: struct.ContextHandle.html
     pub fn with_context(self, context: &'a zmq::Context) -> Self {
         Self {
             context: Some(context),
-            socket_type: self.socket_type,
-            endpoint: self.endpoint,
-            _phantom: Default::default(),
+            ..self
+        }
+    }
+
+    /// Create the zmq socket in the context of the given handle, which keeps track of it.
+    ///
+    /// Without this or [`with_context`](#method.with_context) the socket is created from
+    /// [`ContextHandle::global`](struct.ContextHandle.html#method.global).
+    pub fn with_handle(self, handle: &'a ContextHandle) -> Self {
+        Self {
+            handle: Some(handle),
+            ..self
+        }
+    }
+
+    /// Use the PLAIN security mechanism as a server.
+    ///
+    /// Clients are authenticated by the ZAP handler installed on the context, and the
+    /// authenticated user id can be read from received frames with
+    /// [`Metadata::user_id`](trait.Metadata.html#tymethod.user_id). The mechanism in use is
+    /// reported by [`ZmqSocketExt::mechanism`](trait.ZmqSocketExt.html#method.mechanism).
+    pub fn plain_server(self) -> Self {
+        Self {
+            security: Some(Security::PlainServer),
+            ..self
+        }
+    }
+
+    /// Use the PLAIN security mechanism as a client with the given credentials.
+    pub fn plain_client(self, username: &'a str, password: &'a str) -> Self {
+        Self {
+            security: Some(Security::PlainClient { username, password }),
+            ..self
+        }
+    }
+
+    /// Set the ZAP domain used when authenticating peers.
+    ///
+    /// With the NULL mechanism, setting a domain is what makes ØMQ consult the ZAP handler
+    /// at all, which is useful for filtering peers by IP address.
+    pub fn zap_domain(self, domain: &'a str) -> Self {
+        Self {
+            zap_domain: Some(domain),
+            ..self
+        }
+    }
+
+    /// Send a ZMTP PING to the peer every `interval`.
+    ///
+    /// Heartbeats are disabled unless this is set. Corresponds to `ZMQ_HEARTBEAT_IVL`.
+    pub fn heartbeat_ivl(self, interval: Duration) -> Self {
+        Self {
+            heartbeat_ivl: Some(interval),
+            ..self
+        }
+    }
+
+    /// Drop the connection if no traffic arrives within `timeout` after a PING was sent.
+    ///
+    /// Defaults to the heartbeat interval. Corresponds to `ZMQ_HEARTBEAT_TIMEOUT`.
+    pub fn heartbeat_timeout(self, timeout: Duration) -> Self {
+        Self {
+            heartbeat_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Ask the peer to drop the connection if it hears nothing from us within `ttl`.
+    ///
+    /// The value is rounded down to deciseconds. Corresponds to `ZMQ_HEARTBEAT_TTL`.
+    pub fn heartbeat_ttl(self, ttl: Duration) -> Self {
+        Self {
+            heartbeat_ttl: Some(ttl),
+            ..self
+        }
+    }
+
+    /// Let the `Sink` of the socket accept up to `capacity` messages while the socket cannot
+    /// take them, instead of just one.
+    ///
+    /// Queued messages are handed over to ØMQ in order by `poll_flush`, or by `poll_ready`
+    /// once the queue is full. A capacity of zero is treated as one.
+    pub fn send_queue(self, capacity: usize) -> Self {
+        Self {
+            send_queue: Some(capacity.max(1)),
+            ..self
+        }
+    }
+
+    /// Take W3C trace context header frames out of received messages, see
+    /// [`traceparent`](traceparent/index.html).
+    pub fn trace_context(self) -> Self {
+        Self {
+            trace_context: true,
+            ..self
+        }
+    }
+
+    /// Like [`trace_context`](#method.trace_context), and also put the context returned by
+    /// `current` in front of every message sent, unless it returns `None`.
+    ///
+    /// `current` is called whenever the `Sink` of the socket accepts a message, from within the
+    /// task sending it.
+    pub fn trace_context_with<F>(self, current: F) -> Self
+    where
+        F: Fn() -> Option<TraceParent> + Send + Sync + 'static,
+    {
+        Self {
+            trace_context: true,
+            current_trace_parent: Some(Arc::new(current)),
+            ..self
+        }
+    }
+
+    /// Register the socket in the given reactor instead of the global one.
+    pub fn with_reactor(self, reactor: &'a Reactor) -> Self {
+        Self {
+            reactor: Some(reactor),
+            ..self
         }
     }
 
     /// Connect to the ZMQ endpoint based on given URI
-    pub fn connect(self) -> Result<T, Error> {
-        let socket = match self.context {
-            Some(cx) => cx.socket(self.socket_type)?,
-            None => zmq::Context::new().socket(self.socket_type)?,
-        };
+    ///
+    /// The error names the endpoint, the socket type and the operation, see
+    /// [`ErrorContext`](errors/struct.ErrorContext.html).
+    pub fn connect(self) -> Result<T, crate::Error> {
+        self.attach(Operation::Connect, |socket, endpoint| {
+            socket.connect(endpoint)
+        })
+    }
+
+    /// Bind to the ZMQ endpoint based on given URI
+    ///
+    /// The error names the endpoint, the socket type and the operation, see
+    /// [`ErrorContext`](errors/struct.ErrorContext.html).
+    pub fn bind(self) -> Result<T, crate::Error> {
+        self.attach(Operation::Bind, |socket, endpoint| socket.bind(endpoint))
+    }
+
+    /// Bind to the ZMQ endpoint, retrying failed attempts according to `policy`
+    ///
+    /// The error is the one of the last attempt, see [`retry`](retry/index.html).
+    pub async fn bind_with_retry(self, policy: RetryPolicy) -> Result<T, crate::Error> {
+        policy
+            .run(|| self.attach(Operation::Bind, |socket, endpoint| socket.bind(endpoint)))
+            .await
+    }
 
-        socket.connect(self.endpoint)?;
+    /// Connect to the ZMQ endpoint, retrying failed attempts according to `policy`
+    ///
+    /// The error is the one of the last attempt, see [`retry`](retry/index.html).
+    pub async fn connect_with_retry(self, policy: RetryPolicy) -> Result<T, crate::Error> {
+        policy
+            .run(|| {
+                self.attach(Operation::Connect, |socket, endpoint| {
+                    socket.connect(endpoint)
+                })
+            })
+            .await
+    }
+
+    fn attach(
+        &self,
+        operation: Operation,
+        attach: impl FnOnce(&zmq::Socket, &str) -> Result<(), Error>,
+    ) -> Result<T, crate::Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!(
+            "attach",
+            %operation,
+            socket_type = ?self.socket_type,
+            endpoint = self.endpoint,
+        )
+        .entered();
+
+        let mut socket = self
+            .socket()
+            .and_then(|socket| {
+                attach(socket.as_socket(), self.endpoint)?;
+                Ok(socket)
+            })
+            .map_err(|error| {
+                let error = crate::Error::from(error).with_context(ErrorContext {
+                    endpoint: Some(self.endpoint.to_owned()),
+                    socket_type: Some(self.socket_type),
+                    operation: Some(operation),
+                });
+                #[cfg(feature = "tracing")]
+                tracing::debug!(%error, "failed");
+                error
+            })?;
+        socket.get_mut().endpoint = Some(self.endpoint.to_owned());
+        #[cfg(feature = "tracing")]
+        tracing::debug!("attached");
+        #[cfg(feature = "metrics")]
+        socket
+            .get_ref()
+            .stats
+            .record(self.socket_type, self.endpoint);
         Ok(T::from(socket))
     }
 
-    /// Bind to the ZMQ endpoint based on given URI
-    pub fn bind(self) -> Result<T, Error> {
-        let socket = match self.context {
-            Some(cx) => cx.socket(self.socket_type)?,
-            None => zmq::Context::new().socket(self.socket_type)?,
+    /// Create the socket, apply every configured option to it and register it in the reactor.
+    fn socket(&self) -> Result<ZmqSocket, SocketError> {
+        let (socket, context, registration) = match self.context {
+            Some(cx) => (
+                cx.socket(self.socket_type).map_err(SocketError::creating)?,
+                cx.clone(),
+                None,
+            ),
+            None => {
+                let handle = match self.handle {
+                    Some(handle) => handle,
+                    None => ContextHandle::global(),
+                };
+                let (socket, context, registration) = handle
+                    .socket(self.socket_type)
+                    .map_err(SocketError::creating)?;
+                (socket, context, Some(registration))
+            }
         };
 
-        socket.bind(self.endpoint)?;
-        Ok(T::from(socket))
+        match self.security {
+            Some(Security::PlainServer) => socket.set_plain_server(true)?,
+            Some(Security::PlainClient { username, password }) => {
+                socket.set_plain_username(Some(username))?;
+                socket.set_plain_password(Some(password))?;
+            }
+            None => {}
+        }
+
+        if let Some(domain) = self.zap_domain {
+            socket.set_zap_domain(domain)?;
+        }
+
+        if let Some(interval) = self.heartbeat_ivl {
+            socket.set_heartbeat_ivl(millis(interval))?;
+        }
+        if let Some(timeout) = self.heartbeat_timeout {
+            socket.set_heartbeat_timeout(millis(timeout))?;
+        }
+        if let Some(ttl) = self.heartbeat_ttl {
+            socket.set_heartbeat_ttl(millis(ttl))?;
+        }
+
+        let reactor = match self.reactor {
+            Some(reactor) => reactor,
+            None => Reactor::global().map_err(|e| SocketError::ReactorUnavailable(e.kind()))?,
+        };
+        let mut socket = ZmqSocket::register(socket, Some(context), registration, reactor)?;
+        if let Some(capacity) = self.send_queue {
+            socket.get_mut().send_queue = capacity;
+        }
+        if self.trace_context {
+            socket.get_mut().trace =
+                Envelope::new(self.socket_type, self.current_trace_parent.clone());
+        }
+        Ok(socket)
     }
 }
 
-pub(crate) struct Sender<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
+impl<'a> SocketBuilder<'a, AnySocket> {
+    /// Create a builder for a socket whose type is only known at runtime, see [`AnySocket`].
+    ///
+    /// [`AnySocket`]: any/enum.AnySocket.html
+    pub fn new(socket_type: zmq::SocketType, endpoint: &'a str) -> Self {
+        Self::typed(socket_type, endpoint)
+    }
+}
+
+/// Convert a duration into the millisecond `i32` that ØMQ socket options expect, saturating
+/// instead of wrapping around.
+pub(crate) fn millis(duration: Duration) -> i32 {
+    duration.as_millis().try_into().unwrap_or(i32::MAX)
+}
+
+/// Messages a `Sink` accepted but did not hand over to ØMQ yet, oldest first.
+pub(crate) struct Outgoing {
+    queue: VecDeque<VecDeque<Message>>,
+    capacity: usize,
+}
+
+impl Outgoing {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// The number of messages waiting in the queue.
+    pub(crate) fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn push(&mut self, item: Multipart) {
+        self.queue.push_back(item.into());
+    }
+
+    /// Send queued messages until there is room for another one.
+    fn poll_ready(&mut self, socket: &ZmqSocket, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        while self.queue.len() >= self.capacity {
+            ready!(self.poll_send_front(socket, cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Send every queued message, in order.
+    fn poll_flush(&mut self, socket: &ZmqSocket, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        while !self.queue.is_empty() {
+            ready!(self.poll_send_front(socket, cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_send_front(
+        &mut self,
+        socket: &ZmqSocket,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Error>> {
+        if let Some(frames) = self.queue.front_mut() {
+            let sent = ready!(socket.send(cx, frames));
+            // ØMQ took the frames handed over before a failure, so a message that failed is
+            // discarded rather than failing every send after it.
+            self.queue.pop_front();
+            sent?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Flush the queue and close `socket`, all within `linger`.
+    ///
+    /// Returns the number of messages that had to be discarded because the deadline passed first.
+    async fn close(mut self, socket: ZmqSocket, linger: Duration) -> Result<usize, Error> {
+        let start = Instant::now();
+        let mut deadline = Delay::new(linger);
+
+        while let Some(frames) = self.queue.front_mut() {
+            if !socket.flush_until(frames, &mut deadline).await? {
+                break;
+            }
+            self.queue.pop_front();
+        }
+
+        socket.close(linger.saturating_sub(start.elapsed())).await?;
+        Ok(self.queue.len())
+    }
+}
+
+pub(crate) struct Sender {
     pub(crate) socket: ZmqSocket,
-    pub(crate) buffer: Option<MultipartIter<I, T>>,
+    pub(crate) outgoing: Outgoing,
+}
+
+impl Sender {
+    pub(crate) fn new(socket: ZmqSocket) -> Self {
+        Self {
+            outgoing: Outgoing::new(socket.send_queue()),
+            socket,
+        }
+    }
+
+    pub(crate) async fn close(self, linger: Duration) -> Result<usize, CloseError> {
+        let context = self.socket.error_context(Operation::Close);
+        self.outgoing
+            .close(self.socket, linger)
+            .await
+            .map_err(|error| CloseError::from(error).with_context(context))
+    }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Sender<I, T> {
-    type Error = Error;
+impl Sink<Multipart> for Sender {
+    type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_flush(self, cx)
+        let Self { socket, outgoing } = self.get_mut();
+        outgoing
+            .poll_ready(socket, cx)
+            .map_err(|error| socket.send_error(error))
     }
 
-    fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        self.get_mut().buffer = Some(item);
+    fn start_send(self: Pin<&mut Self>, mut item: Multipart) -> Result<(), Self::Error> {
+        let Self { socket, outgoing } = self.get_mut();
+        socket.inject_trace_context(&mut item);
+        outgoing.push(item);
         Ok(())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let Self { socket, buffer } = self.get_mut();
-        if let Some(buffer) = buffer.as_mut() {
-            socket.send(cx, buffer)
-        } else {
-            Poll::Ready(Ok(()))
-        }
+        let Self { socket, outgoing } = self.get_mut();
+        outgoing
+            .poll_flush(socket, cx)
+            .map_err(|error| socket.send_error(error))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -128,42 +595,174 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>>
     }
 }
 
+/// Stream of batches of messages received from a socket.
+///
+/// Created by the `recv_batch` method of the receiving socket types. Every item holds at least
+/// one and at most `max` messages: after waiting for the first one, the stream takes whatever
+/// else ØMQ already has queued without going back through the reactor.
+pub struct RecvBatch<'a> {
+    socket: &'a ZmqSocket,
+    max: usize,
+}
+
+impl<'a> RecvBatch<'a> {
+    pub(crate) fn new(socket: &'a ZmqSocket, max: usize) -> Self {
+        assert!(max > 0, "a batch must hold at least one message");
+        Self { socket, max }
+    }
+
+    /// Pair every message of a batch with the trace context it carried, see
+    /// [`TracedRecvBatch`].
+    ///
+    /// [`TracedRecvBatch`]: struct.TracedRecvBatch.html
+    pub fn traced(self) -> TracedRecvBatch<'a> {
+        TracedRecvBatch(self)
+    }
+
+    fn poll_batch(&self, cx: &mut Context<'_>) -> Poll<Option<Result<Batch, RecvError>>> {
+        self.socket
+            .poll_next_batch(cx, self.max)
+            .map(|poll| poll.map(|result| result.map_err(|error| self.socket.recv_error(error))))
+    }
+}
+
+impl Stream for RecvBatch<'_> {
+    type Item = Result<Vec<Multipart>, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_batch(cx)
+            .map(|poll| poll.map(|result| result.map(|(batch, _)| batch)))
+    }
+}
+
+impl FusedStream for RecvBatch<'_> {
+    fn is_terminated(&self) -> bool {
+        self.socket.is_terminated()
+    }
+}
+
+/// Stream of batches of messages paired with the W3C trace context each of them carried.
+///
+/// Created by [`RecvBatch::traced`]. A message has a context only if the socket was built with
+/// [`trace_context`] and the message carried one.
+///
+/// [`RecvBatch::traced`]: struct.RecvBatch.html#method.traced
+/// [`trace_context`]: struct.SocketBuilder.html#method.trace_context
+pub struct TracedRecvBatch<'a>(RecvBatch<'a>);
+
+impl Stream for TracedRecvBatch<'_> {
+    type Item = Result<Vec<(Multipart, Option<TraceParent>)>, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_batch(cx).map(|poll| {
+            poll.map(|result| {
+                result.map(|(batch, contexts)| {
+                    let contexts = contexts.into_iter().chain(std::iter::repeat(None));
+                    batch.into_iter().zip(contexts).collect()
+                })
+            })
+        })
+    }
+}
+
+impl FusedStream for TracedRecvBatch<'_> {
+    fn is_terminated(&self) -> bool {
+        self.0.is_terminated()
+    }
+}
+
 pub(crate) struct Receiver {
     pub(crate) socket: ZmqSocket,
 }
 
+impl Receiver {
+    pub(crate) async fn recv_into(&self, buffer: &mut Multipart) -> Result<(), RecvError> {
+        poll_fn(|cx| self.socket.recv_into(cx, buffer))
+            .await
+            .map_err(|error| self.socket.recv_error(error))
+    }
+
+    pub(crate) async fn recv_traced(&self) -> Result<(Multipart, Option<TraceParent>), RecvError> {
+        poll_fn(|cx| self.socket.recv_traced(cx))
+            .await
+            .map_err(|error| self.socket.recv_error(error))
+    }
+
+    pub(crate) async fn close(self, linger: Duration) -> Result<(), CloseError> {
+        let context = self.socket.error_context(Operation::Close);
+        self.socket
+            .close(linger)
+            .await
+            .map_err(|error| CloseError::from(error).with_context(context))
+    }
+}
+
 impl Stream for Receiver {
-    type Item = Result<Multipart, Error>;
+    type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Poll::Ready(Some(Ok(ready!(self.socket.recv(cx))?)))
+        self.socket
+            .poll_next(cx)
+            .map(|poll| poll.map(|result| result.map_err(|error| self.socket.recv_error(error))))
     }
 }
 
-pub(crate) struct Broker<I: Iterator<Item = T> + Unpin, T: Into<Message>> {
+pub(crate) struct Broker {
     pub(crate) socket: ZmqSocket,
-    pub(crate) buffer: Option<MultipartIter<I, T>>,
+    pub(crate) outgoing: Outgoing,
+}
+
+impl Broker {
+    pub(crate) fn new(socket: ZmqSocket) -> Self {
+        Self {
+            outgoing: Outgoing::new(socket.send_queue()),
+            socket,
+        }
+    }
+
+    pub(crate) async fn recv_into(&self, buffer: &mut Multipart) -> Result<(), RecvError> {
+        poll_fn(|cx| self.socket.recv_into(cx, buffer))
+            .await
+            .map_err(|error| self.socket.recv_error(error))
+    }
+
+    pub(crate) async fn recv_traced(&self) -> Result<(Multipart, Option<TraceParent>), RecvError> {
+        poll_fn(|cx| self.socket.recv_traced(cx))
+            .await
+            .map_err(|error| self.socket.recv_error(error))
+    }
+
+    pub(crate) async fn close(self, linger: Duration) -> Result<usize, CloseError> {
+        let context = self.socket.error_context(Operation::Close);
+        self.outgoing
+            .close(self.socket, linger)
+            .await
+            .map_err(|error| CloseError::from(error).with_context(context))
+    }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for Broker<I, T> {
-    type Error = Error;
+impl Sink<Multipart> for Broker {
+    type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Sink::poll_flush(self, cx)
+        let Self { socket, outgoing } = self.get_mut();
+        outgoing
+            .poll_ready(socket, cx)
+            .map_err(|error| socket.send_error(error))
     }
 
-    fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        self.get_mut().buffer = Some(item);
+    fn start_send(self: Pin<&mut Self>, mut item: Multipart) -> Result<(), Self::Error> {
+        let Self { socket, outgoing } = self.get_mut();
+        socket.inject_trace_context(&mut item);
+        outgoing.push(item);
         Ok(())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let Self { socket, buffer } = self.get_mut();
-        if let Some(buffer) = buffer.as_mut() {
-            socket.send(cx, buffer)
-        } else {
-            Poll::Ready(Ok(()))
-        }
+        let Self { socket, outgoing } = self.get_mut();
+        outgoing
+            .poll_flush(socket, cx)
+            .map_err(|error| socket.send_error(error))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -171,10 +770,105 @@ impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>>
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for Broker<I, T> {
-    type Item = Result<Multipart, Error>;
+impl FusedStream for Receiver {
+    fn is_terminated(&self) -> bool {
+        self.socket.is_terminated()
+    }
+}
+
+impl Stream for Broker {
+    type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Poll::Ready(Some(Ok(ready!(self.socket.recv(cx))?)))
+        self.socket
+            .poll_next(cx)
+            .map(|poll| poll.map(|result| result.map_err(|error| self.socket.recv_error(error))))
     }
 }
+
+impl FusedStream for Broker {
+    fn is_terminated(&self) -> bool {
+        self.socket.is_terminated()
+    }
+}
+
+/// The inherent methods every socket type shares, so they can be called without importing
+/// [`ZmqSocketExt`](../traits/trait.ZmqSocketExt.html).
+///
+/// `send` adds the ones of socket types wrapping a `Sender` or `Broker` in field `$field`,
+/// `recv` the ones of socket types wrapping a `Receiver`, and neither just the ones forwarded to
+/// `ZmqSocketExt`.
+macro_rules! socket_methods {
+    () => {
+        /// A snapshot of the traffic that went through the socket, see [`SocketStats`].
+        ///
+        /// [`SocketStats`]: ../stats/struct.SocketStats.html
+        pub fn stats(&self) -> $crate::SocketStats {
+            $crate::ZmqSocketExt::stats(self)
+        }
+
+        /// Stream the lifecycle events selected in `events`, see [`Monitor`].
+        ///
+        /// [`Monitor`]: ../monitor/struct.Monitor.html
+        pub fn monitor(
+            &self,
+            events: &[$crate::zmq::SocketEvent],
+        ) -> ::std::result::Result<$crate::Monitor, $crate::SocketError> {
+            $crate::ZmqSocketExt::monitor(self, events)
+        }
+
+        /// Stream peers becoming alive or dead, see [`PeerLiveness`].
+        ///
+        /// [`PeerLiveness`]: ../monitor/struct.PeerLiveness.html
+        pub fn peer_liveness(
+            &self,
+        ) -> ::std::result::Result<$crate::PeerLiveness, $crate::SocketError> {
+            $crate::ZmqSocketExt::peer_liveness(self)
+        }
+    };
+    (send $field:tt) => {
+        socket_methods!();
+
+        /// Hand every message the `Sink` accepted over to ØMQ.
+        ///
+        /// Same as `SinkExt::flush`, which cannot tell the item type on its own since the `Sink`
+        /// accepts several.
+        pub async fn flush(&mut self) -> ::std::result::Result<(), $crate::SendError> {
+            $crate::SinkExt::<$crate::Multipart>::flush(self).await
+        }
+
+        /// The number of messages the `Sink` accepted but did not hand over to ØMQ yet.
+        ///
+        /// It never exceeds the capacity set with [`SocketBuilder::send_queue`].
+        ///
+        /// [`SocketBuilder::send_queue`]: ../struct.SocketBuilder.html#method.send_queue
+        pub fn queued(&self) -> usize {
+            self.$field.outgoing.len()
+        }
+
+        /// Flush the pending outgoing messages and close the socket, all within `linger`.
+        ///
+        /// Waits for the socket to become writable until the messages are handed over to ØMQ or
+        /// the deadline passes, then gives ØMQ whatever is left of `linger` to deliver its own
+        /// queue. Returns how many messages had to be discarded.
+        pub async fn close(
+            self,
+            linger: ::std::time::Duration,
+        ) -> ::std::result::Result<usize, $crate::CloseError> {
+            self.$field.close(linger).await
+        }
+    };
+    (recv $field:tt) => {
+        socket_methods!();
+
+        /// Close the socket, giving ØMQ at most `linger` to deliver anything still queued on it.
+        pub async fn close(
+            self,
+            linger: ::std::time::Duration,
+        ) -> ::std::result::Result<(), $crate::CloseError> {
+            self.$field.close(linger).await
+        }
+    };
+}
+
+pub(crate) use socket_methods;
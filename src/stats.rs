@@ -0,0 +1,326 @@
+//! Per-socket traffic statistics
+//!
+//! Every socket counts the messages, frames and bytes it sends and receives, how often ØMQ was
+//! not ready for an operation, how long operations were left waiting and how often the reactor
+//! woke the socket up. The `stats` method of a socket takes a [`SocketStats`] snapshot of them.
+//!
+//! With the `metrics` feature enabled, sockets built through a [`SocketBuilder`] also publish
+//! their statistics through the [`metrics`] facade, labelled with `socket_type` and
+//! `endpoint`:
+//!
+//! | Name                             | Type      | Snapshot field      |
+//! |----------------------------------|-----------|---------------------|
+//! | `async_zmq_messages_sent`        | counter   | `messages_sent`     |
+//! | `async_zmq_messages_received`    | counter   | `messages_received` |
+//! | `async_zmq_frames_sent`          | counter   | `frames_sent`       |
+//! | `async_zmq_frames_received`      | counter   | `frames_received`   |
+//! | `async_zmq_bytes_sent`           | counter   | `bytes_sent`        |
+//! | `async_zmq_bytes_received`       | counter   | `bytes_received`    |
+//! | `async_zmq_would_block`          | counter   | `would_block`       |
+//! | `async_zmq_pending_seconds`      | histogram | `pending`           |
+//! | `async_zmq_wakeups`              | counter   | `wakeups`           |
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{Result, StreamExt};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let mut zmq = async_zmq::pull("tcp://127.0.0.1:5555")?.connect()?;
+//!
+//!     while let Some(msg) = zmq.next().await {
+//!         msg?;
+//!         let stats = zmq.stats();
+//!         println!("{} messages, {} bytes", stats.messages_received, stats.bytes_received);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`SocketStats`]: struct.SocketStats.html
+//! [`SocketBuilder`]: ../struct.SocketBuilder.html
+//! [`metrics`]: https://crates.io/crates/metrics
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+/// A snapshot of the statistics of a socket, see the [module documentation].
+///
+/// [module documentation]: index.html
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SocketStats {
+    /// Messages handed over to ØMQ.
+    pub messages_sent: u64,
+    /// Messages received from ØMQ.
+    pub messages_received: u64,
+    /// Frames handed over to ØMQ.
+    pub frames_sent: u64,
+    /// Frames received from ØMQ.
+    pub frames_received: u64,
+    /// Payload bytes handed over to ØMQ.
+    pub bytes_sent: u64,
+    /// Payload bytes received from ØMQ.
+    pub bytes_received: u64,
+    /// How often ØMQ reported `EAGAIN`, i.e. was not ready to send or receive yet.
+    pub would_block: u64,
+    /// Total time sends and receives spent waiting for ØMQ to get ready.
+    pub pending: Duration,
+    /// How often the reactor woke up the tasks waiting on the socket.
+    pub wakeups: u64,
+}
+
+/// The direction of an operation.
+#[derive(Clone, Copy)]
+pub(crate) enum Direction {
+    Send,
+    Recv,
+}
+
+/// The statistics a socket keeps, see [`SocketStats`](struct.SocketStats.html).
+#[derive(Default)]
+pub(crate) struct Stats {
+    messages_sent: Cell<u64>,
+    messages_received: Cell<u64>,
+    frames_sent: Cell<u64>,
+    frames_received: Cell<u64>,
+    bytes_sent: Cell<u64>,
+    bytes_received: Cell<u64>,
+    would_block: Cell<u64>,
+    pending: Cell<Duration>,
+    /// Since when the current send or receive is waiting, if it is.
+    send_pending_since: Cell<Option<Instant>>,
+    recv_pending_since: Cell<Option<Instant>>,
+    /// Frames and bytes of the messages being sent and received, for tracing.
+    #[cfg(feature = "tracing")]
+    sending: Cell<(u64, u64)>,
+    #[cfg(feature = "tracing")]
+    receiving: Cell<(u64, u64)>,
+    /// The reactor token and type of the socket, to tell sockets apart in events.
+    #[cfg(feature = "tracing")]
+    identity: Cell<Option<(usize, zmq::SocketType)>>,
+    /// The wakeups already published to the recorder.
+    #[cfg(feature = "metrics")]
+    wakeups_published: Cell<u64>,
+    #[cfg(feature = "metrics")]
+    recorder: once_cell::unsync::OnceCell<Recorder>,
+}
+
+impl Stats {
+    pub(crate) fn snapshot(&self, wakeups: u64) -> SocketStats {
+        SocketStats {
+            messages_sent: self.messages_sent.get(),
+            messages_received: self.messages_received.get(),
+            frames_sent: self.frames_sent.get(),
+            frames_received: self.frames_received.get(),
+            bytes_sent: self.bytes_sent.get(),
+            bytes_received: self.bytes_received.get(),
+            would_block: self.would_block.get(),
+            pending: self.pending.get(),
+            wakeups,
+        }
+    }
+
+    /// Count a frame that was handed over to ØMQ.
+    pub(crate) fn frame_sent(&self, len: usize) {
+        add(&self.frames_sent, 1);
+        add(&self.bytes_sent, len as u64);
+        #[cfg(feature = "tracing")]
+        {
+            let (frames, bytes) = self.sending.get();
+            self.sending.set((frames + 1, bytes + len as u64));
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(recorder) = self.recorder.get() {
+            recorder.frames_sent.increment(1);
+            recorder.bytes_sent.increment(len as u64);
+        }
+    }
+
+    /// Count a frame that was received from ØMQ.
+    pub(crate) fn frame_received(&self, len: usize) {
+        add(&self.frames_received, 1);
+        add(&self.bytes_received, len as u64);
+        #[cfg(feature = "tracing")]
+        {
+            let (frames, bytes) = self.receiving.get();
+            self.receiving.set((frames + 1, bytes + len as u64));
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(recorder) = self.recorder.get() {
+            recorder.frames_received.increment(1);
+            recorder.bytes_received.increment(len as u64);
+        }
+    }
+
+    /// Count a message that was handed over to ØMQ completely.
+    pub(crate) fn message_sent(&self) {
+        add(&self.messages_sent, 1);
+        #[cfg(feature = "tracing")]
+        {
+            let (frames, bytes) = self.sending.take();
+            let (token, socket_type) = self.identity();
+            tracing::trace!(
+                token,
+                socket_type = socket_type.map(tracing::field::debug),
+                frames,
+                bytes,
+                "sent message"
+            );
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(recorder) = self.recorder.get() {
+            recorder.messages_sent.increment(1);
+        }
+    }
+
+    /// Note that the rest of the message being sent was dropped, see `ZmqSocket::send`.
+    pub(crate) fn message_dropped(&self) {
+        #[cfg(feature = "tracing")]
+        {
+            let (frames, bytes) = self.sending.take();
+            let (token, socket_type) = self.identity();
+            tracing::debug!(
+                token,
+                socket_type = socket_type.map(tracing::field::debug),
+                frames,
+                bytes,
+                "dropped message without peer"
+            );
+        }
+    }
+
+    /// Count a message that was received from ØMQ completely.
+    pub(crate) fn message_received(&self) {
+        add(&self.messages_received, 1);
+        #[cfg(feature = "tracing")]
+        {
+            let (frames, bytes) = self.receiving.take();
+            let (token, socket_type) = self.identity();
+            tracing::trace!(
+                token,
+                socket_type = socket_type.map(tracing::field::debug),
+                frames,
+                bytes,
+                "received message"
+            );
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(recorder) = self.recorder.get() {
+            recorder.messages_received.increment(1);
+        }
+    }
+
+    /// Count an `EAGAIN` from ØMQ.
+    pub(crate) fn would_block(&self) {
+        add(&self.would_block, 1);
+        #[cfg(feature = "metrics")]
+        if let Some(recorder) = self.recorder.get() {
+            recorder.would_block.increment(1);
+        }
+    }
+
+    /// Note that an operation is left waiting, unless it already was.
+    pub(crate) fn pending(&self, direction: Direction) {
+        let since = self.pending_since(direction);
+        if since.get().is_none() {
+            since.set(Some(Instant::now()));
+        }
+    }
+
+    /// Note that an operation is done waiting, adding the time it waited if it did.
+    pub(crate) fn ready(&self, direction: Direction) {
+        if let Some(since) = self.pending_since(direction).take() {
+            let waited = since.elapsed();
+            self.pending.set(self.pending.get() + waited);
+            #[cfg(feature = "tracing")]
+            {
+                let (token, socket_type) = self.identity();
+                tracing::trace!(
+                    token,
+                    socket_type = socket_type.map(tracing::field::debug),
+                    ?waited,
+                    "socket became ready"
+                );
+            }
+            #[cfg(feature = "metrics")]
+            if let Some(recorder) = self.recorder.get() {
+                recorder.pending.record(waited.as_secs_f64());
+            }
+        }
+    }
+
+    fn pending_since(&self, direction: Direction) -> &Cell<Option<Instant>> {
+        match direction {
+            Direction::Send => &self.send_pending_since,
+            Direction::Recv => &self.recv_pending_since,
+        }
+    }
+
+    /// Name the socket in its events from now on.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn identify(&self, token: usize, socket_type: zmq::SocketType) {
+        self.identity.set(Some((token, socket_type)));
+    }
+
+    #[cfg(feature = "tracing")]
+    fn identity(&self) -> (Option<usize>, Option<zmq::SocketType>) {
+        self.identity.get().unzip()
+    }
+
+    /// Wakeups are counted by the reactor thread, publish the ones it counted since last time.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn publish_wakeups(&self, wakeups: u64) {
+        let published = self.wakeups_published.replace(wakeups);
+        if let Some(recorder) = self.recorder.get() {
+            recorder
+                .wakeups
+                .increment(wakeups.saturating_sub(published));
+        }
+    }
+
+    /// Publish the statistics from now on, labelled with the type and endpoint of the socket.
+    #[cfg(feature = "metrics")]
+    pub(crate) fn record(&self, socket_type: zmq::SocketType, endpoint: &str) {
+        let _ = self.recorder.set(Recorder::new(socket_type, endpoint));
+    }
+}
+
+fn add(counter: &Cell<u64>, n: u64) {
+    counter.set(counter.get().wrapping_add(n));
+}
+
+/// The handles of the metrics a socket publishes.
+#[cfg(feature = "metrics")]
+struct Recorder {
+    messages_sent: metrics::Counter,
+    messages_received: metrics::Counter,
+    frames_sent: metrics::Counter,
+    frames_received: metrics::Counter,
+    bytes_sent: metrics::Counter,
+    bytes_received: metrics::Counter,
+    would_block: metrics::Counter,
+    pending: metrics::Histogram,
+    wakeups: metrics::Counter,
+}
+
+#[cfg(feature = "metrics")]
+impl Recorder {
+    fn new(socket_type: zmq::SocketType, endpoint: &str) -> Self {
+        let labels = [
+            ("socket_type", format!("{:?}", socket_type)),
+            ("endpoint", endpoint.to_owned()),
+        ];
+        Self {
+            messages_sent: metrics::counter!("async_zmq_messages_sent", &labels),
+            messages_received: metrics::counter!("async_zmq_messages_received", &labels),
+            frames_sent: metrics::counter!("async_zmq_frames_sent", &labels),
+            frames_received: metrics::counter!("async_zmq_frames_received", &labels),
+            bytes_sent: metrics::counter!("async_zmq_bytes_sent", &labels),
+            bytes_received: metrics::counter!("async_zmq_bytes_received", &labels),
+            would_block: metrics::counter!("async_zmq_would_block", &labels),
+            pending: metrics::histogram!("async_zmq_pending_seconds", &labels),
+            wakeups: metrics::counter!("async_zmq_wakeups", &labels),
+        }
+    }
+}
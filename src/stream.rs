@@ -1,25 +1,37 @@
 //! STREAM socket module of Pub-Sub pattern in ZMQ
 //!
 //! Use the [`stream`] function to instantiate a stream socket and use methods
-//! from the [`Stream`]/[`StreamExt`] traits.
+//! from the [`Stream`]/[`StreamExt`] and [`Sink`]/[`SinkExt`] traits.
 //!
 //! A stream socket is for interacting with TCP data from sources other than
 //! ZMQ. It is not compatible with any of the other ZMQ socket types.
 //!
+//! Every message carries the routing id of a TCP connection in its first frame. Instead of
+//! routing by hand, [`ZmqStream::incoming`] turns the socket into a stream of
+//! [`StreamConnection`]s, each of which reads and writes the bytes of one connection through
+//! `AsyncRead` and `AsyncWrite`. Every connection buffers a bounded number of received frames
+//! and is closed once its peer sends more than that ahead of the reader, see
+//! [`Incoming::max_buffered`].
+//!
 //! # Example
 //!
 //! ```no_run
-//! use async_zmq::{Result, StreamExt};
+//! use async_zmq::StreamExt;
+//! use futures::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 //!
 //! #[async_std::main]
-//! async fn main() -> Result<()> {
-//!     let mut zmq = async_zmq::stream("tcp://127.0.0.1:5555")?.bind()?;
-//!
-//!     while let Some(msg) = zmq.next().await {
-//!         // Received message is a type of Result<MessageBuf>
-//!         let msg = msg?;
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let zmq = async_zmq::stream("tcp://127.0.0.1:5555")?.bind()?;
+//!     let mut incoming = zmq.incoming();
 //!
-//!         println!("{:?}", msg.iter());
+//!     while let Some(connection) = incoming.next().await {
+//!         // Echo every line back to the peer
+//!         let mut connection = BufReader::new(connection?);
+//!         let mut line = String::new();
+//!         while connection.read_line(&mut line).await? > 0 {
+//!             connection.get_mut().write_all(line.as_bytes()).await?;
+//!             line.clear();
+//!         }
 //!     }
 //!     Ok(())
 //! }
@@ -28,31 +40,102 @@
 //! [`stream`]: fn.stream.html
 //! [`Stream`]: ../trait.Stream.html
 //! [`StreamExt`]: ../trait.StreamExt.html
+//! [`Sink`]: ../trait.Sink.html
+//! [`SinkExt`]: ../trait.SinkExt.html
+//! [`ZmqStream::incoming`]: struct.ZmqStream.html#method.incoming
+//! [`StreamConnection`]: struct.StreamConnection.html
+//! [`Incoming::max_buffered`]: struct.Incoming.html#method.max_buffered
 
+use std::collections::{HashMap, VecDeque};
+use std::io;
 use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
 
-use zmq::SocketType;
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::ready;
+use futures::stream::FusedStream;
+use zmq::{Message, SocketType};
 
 use crate::{
     reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, Receiver, SocketBuilder},
-    RecvError, SocketError, Stream,
+    socket::{socket_methods, Broker, IntoMultipart, Multipart, RecvBatch, SocketBuilder},
+    traits::{self, sealed::Sealed, ZmqRecv, ZmqSend, ZmqSocketExt},
+    RecvError, SendError, Sink, SocketError, Stream,
 };
 
 /// Create a ZMQ socket with STREAM type
 pub fn stream(endpoint: &str) -> Result<SocketBuilder<'_, ZmqStream>, SocketError> {
-    Ok(SocketBuilder::new(SocketType::STREAM, endpoint))
+    Ok(SocketBuilder::typed(SocketType::STREAM, endpoint))
 }
 
 /// The async wrapper of ZMQ socket with STREAM type
-pub struct ZmqStream(Receiver);
+pub struct ZmqStream(Broker);
+
+impl ZmqSend for ZmqStream {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        traits::poll_send_sink(self, cx, msg)
+    }
+}
+
+impl ZmqRecv for ZmqStream {
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        traits::poll_recv_stream(self, cx)
+    }
+}
+
+impl ZmqSocketExt for ZmqStream {
+    fn socket_type(&self) -> SocketType {
+        SocketType::STREAM
+    }
+}
+
+impl Sealed for ZmqStream {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        &self.0.socket
+    }
+}
+
+impl From<ZmqSocket> for ZmqStream {
+    fn from(socket: ZmqSocket) -> Self {
+        Self(Broker::new(socket))
+    }
+}
 
 impl From<zmq::Socket> for ZmqStream {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
     fn from(socket: zmq::Socket) -> Self {
-        Self(Receiver {
-            socket: ZmqSocket::from(socket),
-        })
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
+    }
+}
+
+impl<M: IntoMultipart> Sink<M> for ZmqStream {
+    type Error = SendError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_ready(Pin::new(&mut self.get_mut().0), cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: M) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().0).start_send(item.into_multipart())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_flush(Pin::new(&mut self.get_mut().0), cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::poll_close(Pin::new(&mut self.get_mut().0), cx)
     }
 }
 
@@ -60,9 +143,13 @@ impl Stream for ZmqStream {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().0)
-            .poll_next(cx)
-            .map(|poll| poll.map(|result| result.map_err(Into::into)))
+        Pin::new(&mut self.get_mut().0).poll_next(cx)
+    }
+}
+
+impl FusedStream for ZmqStream {
+    fn is_terminated(&self) -> bool {
+        self.0.socket.is_terminated()
     }
 }
 
@@ -71,4 +158,342 @@ impl ZmqStream {
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    socket_methods!(send 0);
+
+    /// Receive the next message into `buffer`, reusing the frames it already holds instead of
+    /// allocating new ones.
+    ///
+    /// `buffer` is resized to the number of frames received. Together with a
+    /// [`MultipartBuf`] this keeps a receive loop free of per-message allocations.
+    ///
+    /// [`MultipartBuf`]: ../buf/struct.MultipartBuf.html
+    pub async fn recv_into(&mut self, buffer: &mut Multipart) -> Result<(), RecvError> {
+        self.0.recv_into(buffer).await
+    }
+
+    /// Stream received messages in batches of at most `max`, see [`RecvBatch`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    ///
+    /// [`RecvBatch`]: ../struct.RecvBatch.html
+    pub fn recv_batch(&mut self, max: usize) -> RecvBatch<'_> {
+        RecvBatch::new(&self.0.socket, max)
+    }
+
+    /// Turn the socket into a stream of the TCP connections it accepts or establishes, see
+    /// [`Incoming`].
+    ///
+    /// Messages the `Sink` accepted but did not hand over to ØMQ yet are discarded, call
+    /// [`flush`] first to keep them.
+    ///
+    /// [`Incoming`]: struct.Incoming.html
+    /// [`flush`]: #method.flush
+    pub fn incoming(self) -> Incoming {
+        Incoming(Arc::new(Mutex::new(Connections {
+            socket: self.0.socket,
+            peers: HashMap::new(),
+            accepted: VecDeque::new(),
+            waker: None,
+            listening: true,
+            max_buffered: 64,
+        })))
+    }
+}
+
+/// State shared between [`Incoming`] and the connections it handed out.
+///
+/// Whoever polls first receives from the socket and files each message under the connection
+/// it belongs to, waking the task waiting on it.
+struct Connections {
+    socket: ZmqSocket,
+    /// Connections by routing id, including the ones not accepted yet.
+    peers: HashMap<Vec<u8>, Peer>,
+    /// Routing ids of new connections, in the order ØMQ announced them.
+    accepted: VecDeque<Vec<u8>>,
+    /// The task waiting on `Incoming`.
+    waker: Option<Waker>,
+    /// Whether `Incoming` is still around to accept new connections.
+    listening: bool,
+    /// How many received frames a connection buffers at most.
+    max_buffered: usize,
+}
+
+#[derive(Default)]
+struct Peer {
+    /// Received frames not read yet.
+    received: VecDeque<Message>,
+    /// How much of the first received frame was read already.
+    offset: usize,
+    /// Whether the TCP connection is gone.
+    disconnected: bool,
+    /// Whether our side closed the connection.
+    closed: bool,
+    /// Whether our side closed the connection because its buffer overflowed.
+    overflowed: bool,
+    /// The task waiting to read from the connection.
+    waker: Option<Waker>,
+}
+
+impl Connections {
+    /// Receive one message and file it under its connection, `None` once the context was
+    /// terminated.
+    ///
+    /// A connection that receives more than `max_buffered` frames ahead of its reader is
+    /// closed, so that it cannot hold up the other connections sharing ØMQ's queue.
+    fn poll_dispatch(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<(), zmq::Error>>> {
+        let mut msg = match ready!(self.socket.poll_next(cx)) {
+            Some(Ok(msg)) => msg,
+            Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+            None => return Poll::Ready(None),
+        };
+        // A STREAM socket always receives the routing id followed by a single data frame.
+        let (data, id) = match (msg.pop(), msg.pop()) {
+            (Some(data), Some(id)) => (data, id.to_vec()),
+            _ => return Poll::Ready(Some(Ok(()))),
+        };
+
+        // A frame without data announces a connection the first time its routing id shows
+        // up, and its disconnection every time after.
+        match self.peers.get_mut(&id) {
+            Some(peer) if data.is_empty() => {
+                peer.disconnected = true;
+                wake(&mut peer.waker);
+            }
+            // Left over from a connection that overflowed already.
+            Some(peer) if peer.overflowed => {}
+            Some(peer) if peer.received.len() < self.max_buffered => {
+                peer.received.push_back(data);
+                wake(&mut peer.waker);
+            }
+            Some(peer) => {
+                peer.overflowed = true;
+                peer.closed = true;
+                wake(&mut peer.waker);
+                self.disconnect(&id);
+            }
+            None if data.is_empty() && self.listening => {
+                self.peers.insert(id.clone(), Peer::default());
+                self.accepted.push_back(id);
+                wake(&mut self.waker);
+            }
+            None if data.is_empty() => self.disconnect(&id),
+            // Left over from a connection that was dropped already.
+            None => {}
+        }
+
+        Poll::Ready(Some(Ok(())))
+    }
+
+    /// Ask ØMQ to close the connection `id` refers to, without waiting for the socket.
+    fn disconnect(&self, id: &[u8]) {
+        let socket = self.socket.as_socket();
+        let _ = socket
+            .send(id, zmq::DONTWAIT | zmq::SNDMORE)
+            .and_then(|_| socket.send(Message::new(), zmq::DONTWAIT));
+    }
+}
+
+fn wake(waker: &mut Option<Waker>) {
+    if let Some(waker) = waker.take() {
+        waker.wake();
+    }
+}
+
+/// A stream of the TCP connections of a STREAM socket, see [`ZmqStream::incoming`].
+///
+/// Connections show up as soon as ØMQ announces them, both the ones accepted on a bound
+/// endpoint and the ones established to a connected endpoint. Once `Incoming` is dropped, new
+/// connections are closed right away while the ones handed out before keep working.
+///
+/// [`ZmqStream::incoming`]: struct.ZmqStream.html#method.incoming
+pub struct Incoming(Arc<Mutex<Connections>>);
+
+impl Incoming {
+    /// Buffer at most `max` received frames per connection, 64 by default. Zero is treated as
+    /// one.
+    ///
+    /// All connections share the queue of the socket, so a connection that is not read cannot
+    /// hold back the data of its peer without holding back every other connection too. Once a
+    /// frame arrives for a connection that buffers `max` frames already, whether it was
+    /// accepted or not, the connection is closed instead. Reading it returns the frames it
+    /// buffered, then fails with `ConnectionAborted`. A peer sending faster than it is read
+    /// thus cannot grow memory without bound nor stall the others.
+    pub fn max_buffered(self, max: usize) -> Self {
+        self.0.lock().unwrap().max_buffered = max.max(1);
+        self
+    }
+}
+
+impl Stream for Incoming {
+    type Item = Result<StreamConnection, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut connections = self.0.lock().unwrap();
+        loop {
+            if let Some(id) = connections.accepted.pop_front() {
+                return Poll::Ready(Some(Ok(StreamConnection {
+                    id,
+                    connections: self.0.clone(),
+                })));
+            }
+            match connections.poll_dispatch(cx) {
+                Poll::Ready(Some(Ok(()))) => {}
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Some(Err(connections.socket.recv_error(e))))
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => {
+                    connections.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl FusedStream for Incoming {
+    fn is_terminated(&self) -> bool {
+        let connections = self.0.lock().unwrap();
+        connections.socket.is_terminated() && connections.accepted.is_empty()
+    }
+}
+
+impl Drop for Incoming {
+    fn drop(&mut self) {
+        let mut connections = self.0.lock().unwrap();
+        connections.listening = false;
+        for id in std::mem::take(&mut connections.accepted) {
+            connections.peers.remove(&id);
+            connections.disconnect(&id);
+        }
+    }
+}
+
+/// A single TCP connection of a STREAM socket.
+///
+/// Bytes written are sent to the peer as they are and reads return the bytes it sent, until
+/// it disconnects. Closing or dropping the connection disconnects the peer. ØMQ finishes
+/// disconnecting while the socket is polled, so keep polling [`Incoming`] or another
+/// connection for that to happen.
+///
+/// [`Incoming`]: struct.Incoming.html
+pub struct StreamConnection {
+    id: Vec<u8>,
+    connections: Arc<Mutex<Connections>>,
+}
+
+impl StreamConnection {
+    /// The routing id ØMQ assigned to the connection.
+    pub fn routing_id(&self) -> &[u8] {
+        &self.id
+    }
+
+    /// Send `data` through the socket, addressed to this connection.
+    fn poll_send(&self, cx: &mut Context<'_>, data: Message) -> Poll<io::Result<()>> {
+        let connections = self.connections.lock().unwrap();
+        let peer = &connections.peers[&self.id];
+        if peer.disconnected || peer.closed {
+            return Poll::Ready(Err(io::ErrorKind::NotConnected.into()));
+        }
+
+        let mut frames = VecDeque::from([Message::from(&self.id[..]), data]);
+        ready!(connections.socket.send(cx, &mut frames)).map_err(io::Error::other)?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for StreamConnection {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut connections = self.connections.lock().unwrap();
+        loop {
+            let peer = connections.peers.get_mut(&self.id).unwrap();
+            if let Some(frame) = peer.received.front() {
+                let available = &frame[peer.offset..];
+                let n = available.len().min(buf.len());
+                buf[..n].copy_from_slice(&available[..n]);
+                peer.offset += n;
+                if peer.offset == frame.len() {
+                    peer.received.pop_front();
+                    peer.offset = 0;
+                }
+                return Poll::Ready(Ok(n));
+            }
+            if peer.overflowed {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "the connection received more than `max_buffered` frames ahead of the reader",
+                )));
+            }
+            if peer.disconnected || peer.closed {
+                return Poll::Ready(Ok(0));
+            }
+
+            match connections.poll_dispatch(cx) {
+                Poll::Ready(Some(Ok(()))) => {}
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(io::Error::other(e))),
+                // The context was terminated, nothing more will arrive.
+                Poll::Ready(None) => return Poll::Ready(Ok(0)),
+                Poll::Pending => {
+                    let peer = connections.peers.get_mut(&self.id).unwrap();
+                    peer.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+impl AsyncWrite for StreamConnection {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            // An empty frame would close the connection instead.
+            return Poll::Ready(Ok(0));
+        }
+        ready!(self.poll_send(cx, Message::from(buf)))?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        // Everything written was handed over to ØMQ already.
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        {
+            let connections = self.connections.lock().unwrap();
+            let peer = &connections.peers[&self.id];
+            if peer.disconnected || peer.closed {
+                return Poll::Ready(Ok(()));
+            }
+        }
+        ready!(self.poll_send(cx, Message::new()))?;
+
+        let mut connections = self.connections.lock().unwrap();
+        let peer = connections.peers.get_mut(&self.id).unwrap();
+        peer.closed = true;
+        wake(&mut peer.waker);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for StreamConnection {
+    fn drop(&mut self) {
+        let mut connections = self.connections.lock().unwrap();
+        if let Some(peer) = connections.peers.remove(&self.id) {
+            if !peer.disconnected && !peer.closed {
+                connections.disconnect(&self.id);
+            }
+        }
+    }
 }
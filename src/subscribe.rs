@@ -37,27 +37,58 @@
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use futures::stream::FusedStream;
 use zmq::SocketType;
 
 use crate::{
     reactor::{AsRawSocket, ZmqSocket},
-    socket::{Multipart, Receiver, SocketBuilder},
-    RecvError, SocketError, Stream, SubscribeError,
+    socket::{socket_methods, Multipart, Receiver, RecvBatch, SocketBuilder},
+    traits::{self, sealed::Sealed, ZmqRecv, ZmqSocketExt},
+    RecvError, SocketError, Stream, SubscribeError, TraceParent,
 };
 
 /// Create a ZMQ socket with SUB type
 pub fn subscribe(endpoint: &str) -> Result<SocketBuilder<'_, Subscribe>, SocketError> {
-    Ok(SocketBuilder::new(SocketType::SUB, endpoint))
+    Ok(SocketBuilder::typed(SocketType::SUB, endpoint))
 }
 
 /// The async wrapper of ZMQ socket with SUB type
 pub struct Subscribe(Receiver);
 
+impl ZmqRecv for Subscribe {
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        traits::poll_recv_stream(self, cx)
+    }
+}
+
+impl ZmqSocketExt for Subscribe {
+    fn socket_type(&self) -> SocketType {
+        SocketType::SUB
+    }
+}
+
+impl Sealed for Subscribe {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        &self.0.socket
+    }
+}
+
+impl From<ZmqSocket> for Subscribe {
+    fn from(socket: ZmqSocket) -> Self {
+        Self(Receiver { socket })
+    }
+}
+
 impl From<zmq::Socket> for Subscribe {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
     fn from(socket: zmq::Socket) -> Self {
-        Self(Receiver {
-            socket: ZmqSocket::from(socket),
-        })
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
     }
 }
 
@@ -65,9 +96,13 @@ impl Stream for Subscribe {
     type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().0)
-            .poll_next(cx)
-            .map(|poll| poll.map(|result| result.map_err(Into::into)))
+        Pin::new(&mut self.get_mut().0).poll_next(cx)
+    }
+}
+
+impl FusedStream for Subscribe {
+    fn is_terminated(&self) -> bool {
+        self.0.socket.is_terminated()
     }
 }
 
@@ -86,4 +121,47 @@ impl Subscribe {
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    socket_methods!(recv 0);
+
+    /// The W3C trace context of the message received last, if the socket was built with
+    /// [`trace_context`] and the message carried one.
+    ///
+    /// Use [`recv_traced`](#method.recv_traced) to get the context along with its message,
+    /// this one is only accurate as long as no other message was received since.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub fn last_trace_context(&self) -> Option<TraceParent> {
+        self.0.socket.last_trace_context()
+    }
+
+    /// Receive the next message along with its W3C trace context, if the socket was built
+    /// with [`trace_context`] and the message carried one.
+    ///
+    /// [`trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+    pub async fn recv_traced(&mut self) -> Result<(Multipart, Option<TraceParent>), RecvError> {
+        self.0.recv_traced().await
+    }
+
+    /// Receive the next message into `buffer`, reusing the frames it already holds instead of
+    /// allocating new ones.
+    ///
+    /// `buffer` is resized to the number of frames received. Together with a
+    /// [`MultipartBuf`] this keeps a receive loop free of per-message allocations.
+    ///
+    /// [`MultipartBuf`]: ../buf/struct.MultipartBuf.html
+    pub async fn recv_into(&mut self, buffer: &mut Multipart) -> Result<(), RecvError> {
+        self.0.recv_into(buffer).await
+    }
+
+    /// Stream received messages in batches of at most `max`, see [`RecvBatch`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    ///
+    /// [`RecvBatch`]: ../struct.RecvBatch.html
+    pub fn recv_batch(&mut self, max: usize) -> RecvBatch<'_> {
+        RecvBatch::new(&self.0.socket, max)
+    }
 }
@@ -0,0 +1,653 @@
+//! In-memory sockets for unit tests
+//!
+//! Available with the `testing` feature. Every socket type has a mock counterpart, e.g.
+//! [`MockDealer`] for [`Dealer`], implementing the same `Sink` and `Stream` traits with the
+//! same item and error types, so code written against those traits runs on either. Mocks never
+//! touch ØMQ: each comes with a [`MockHandle`] through which a test feeds in received messages,
+//! takes out sent ones and simulates a socket that cannot send, lost peers or a terminated
+//! context. Nothing happens in the background, which keeps tests deterministic and free of
+//! endpoint collisions. Mocks implement [`ZmqSend`] and [`ZmqRecv`] like the sockets they stand
+//! in for, but not [`ZmqSocketExt`], which needs a real socket.
+//!
+//! # Example
+//!
+//! ```
+//! use async_zmq::{testing::MockDealer, Multipart, SinkExt, StreamExt};
+//!
+//! // The code under test only relies on the traits.
+//! async fn echo_once<S>(socket: &mut S)
+//! where
+//!     S: futures::Stream<Item = Result<Multipart, async_zmq::RecvError>>
+//!         + futures::Sink<Multipart, Error = async_zmq::SendError>
+//!         + Unpin,
+//! {
+//!     if let Some(Ok(msg)) = socket.next().await {
+//!         socket.send(msg).await.unwrap();
+//!     }
+//! }
+//!
+//! # async_std::task::block_on(async {
+//! let (mut dealer, handle) = MockDealer::new();
+//! handle.inject(vec!["hello"]);
+//! echo_once(&mut dealer).await;
+//! assert_eq!(handle.take_sent(), vec![vec!["hello".into()]]);
+//! # });
+//! ```
+//!
+//! [`MockDealer`]: struct.MockDealer.html
+//! [`Dealer`]: ../dealer/struct.Dealer.html
+//! [`MockHandle`]: struct.MockHandle.html
+//! [`ZmqSend`]: ../traits/trait.ZmqSend.html
+//! [`ZmqRecv`]: ../traits/trait.ZmqRecv.html
+//! [`ZmqSocketExt`]: ../traits/trait.ZmqSocketExt.html
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use futures::{future::poll_fn, stream::FusedStream};
+use zmq::SocketType;
+
+use crate::{
+    IntoMultipart, Multipart, RecvError, RecvErrorKind, RequestReplyError, SendError,
+    SendErrorKind, Sink, Stream, SubscribeError, ZmqRecv, ZmqSend,
+};
+
+/// The state shared between a mock socket and its handle.
+struct State {
+    socket_type: SocketType,
+    incoming: VecDeque<Multipart>,
+    sent: VecDeque<Multipart>,
+    /// The topics subscribed to, for SUB and XSUB sockets.
+    subscriptions: Option<Vec<Vec<u8>>>,
+    high_water_mark: Option<usize>,
+    blocked: bool,
+    connected: bool,
+    terminated: bool,
+    /// Whether it is the turn of a REQ or REP socket to send rather than to receive.
+    send_turn: Option<bool>,
+    /// Whether the socket was dropped, which ends `MockHandle::next_sent`.
+    dropped: bool,
+    dropped_messages: usize,
+    send_error: Option<SendError>,
+    recv_error: Option<RecvError>,
+    send_waker: Option<Waker>,
+    recv_waker: Option<Waker>,
+    sent_waker: Option<Waker>,
+}
+
+impl State {
+    /// Whether messages sent while disconnected are dropped rather than waiting for a peer.
+    fn drops_without_peers(&self) -> bool {
+        matches!(self.socket_type, SocketType::PUB | SocketType::XPUB)
+    }
+
+    fn wake_all(&mut self) {
+        for waker in [
+            self.send_waker.take(),
+            self.recv_waker.take(),
+            self.sent_waker.take(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            waker.wake();
+        }
+    }
+
+    /// Whether a SUB or XSUB socket would let `msg` through.
+    fn is_subscribed(&self, msg: &Multipart) -> bool {
+        match &self.subscriptions {
+            Some(topics) => {
+                let first: &[u8] = msg.first().map_or(&[], |frame| frame);
+                topics.iter().any(|topic| first.starts_with(topic))
+            }
+            None => true,
+        }
+    }
+}
+
+/// The socket side of a mock.
+struct Mock(Arc<Mutex<State>>);
+
+impl Mock {
+    fn new(socket_type: SocketType) -> (Self, MockHandle) {
+        let subscriptions = match socket_type {
+            SocketType::SUB | SocketType::XSUB => Some(Vec::new()),
+            _ => None,
+        };
+        let send_turn = match socket_type {
+            SocketType::REQ => Some(true),
+            SocketType::REP => Some(false),
+            _ => None,
+        };
+        let state = Arc::new(Mutex::new(State {
+            socket_type,
+            incoming: VecDeque::new(),
+            sent: VecDeque::new(),
+            subscriptions,
+            high_water_mark: None,
+            blocked: false,
+            connected: true,
+            terminated: false,
+            send_turn,
+            dropped: false,
+            dropped_messages: 0,
+            send_error: None,
+            recv_error: None,
+            send_waker: None,
+            recv_waker: None,
+            sent_waker: None,
+        }));
+        (Mock(state.clone()), MockHandle(state))
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap()
+    }
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+        let mut state = self.state();
+        if state.terminated {
+            return Poll::Ready(Err(SendErrorKind::ContextTerminated.into()));
+        }
+        if let Some(error) = state.send_error.take() {
+            return Poll::Ready(Err(error));
+        }
+        if state.send_turn == Some(false) {
+            return Poll::Ready(Err(zmq::Error::EFSM.into()));
+        }
+        let full = state
+            .high_water_mark
+            .is_some_and(|hwm| state.sent.len() >= hwm);
+        let waiting = !state.connected && !state.drops_without_peers();
+        if state.blocked || full || waiting {
+            state.send_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(&self, msg: Multipart) -> Result<(), SendError> {
+        let mut state = self.state();
+        if state.terminated {
+            return Err(SendErrorKind::ContextTerminated.into());
+        }
+        if !state.connected && state.drops_without_peers() {
+            state.dropped_messages += 1;
+            return Ok(());
+        }
+        state.sent.push_back(msg);
+        if state.send_turn.is_some() {
+            state.send_turn = Some(false);
+        }
+        if let Some(waker) = state.sent_waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    fn poll_send<M: IntoMultipart>(
+        &self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<M>,
+    ) -> Poll<Result<(), SendError>> {
+        futures::ready!(self.poll_ready(cx))?;
+        match msg.take() {
+            Some(msg) => Poll::Ready(self.start_send(msg.into_multipart())),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_next(&self, cx: &mut Context<'_>) -> Poll<Option<Result<Multipart, RecvError>>> {
+        let mut state = self.state();
+        if state.terminated {
+            return Poll::Ready(None);
+        }
+        if let Some(error) = state.recv_error.take() {
+            return Poll::Ready(Some(Err(error)));
+        }
+        if state.send_turn == Some(true) {
+            return Poll::Ready(Some(Err(zmq::Error::EFSM.into())));
+        }
+        while let Some(msg) = state.incoming.pop_front() {
+            if state.is_subscribed(&msg) {
+                if state.send_turn.is_some() {
+                    state.send_turn = Some(true);
+                }
+                return Poll::Ready(Some(Ok(msg)));
+            }
+        }
+        state.recv_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        let item = futures::ready!(self.poll_next(cx));
+        Poll::Ready(item.unwrap_or(Err(RecvErrorKind::ContextTerminated.into())))
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.state().terminated
+    }
+
+    fn subscribe(&self, topic: &str, subscribe: bool) -> Result<(), SubscribeError> {
+        let mut state = self.state();
+        if state.terminated {
+            return Err(SubscribeError::ContextTerminated);
+        }
+        if let Some(topics) = &mut state.subscriptions {
+            if subscribe {
+                topics.push(topic.as_bytes().to_vec());
+            } else if let Some(index) = topics.iter().position(|t| t == topic.as_bytes()) {
+                topics.remove(index);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Mock {
+    fn drop(&mut self) {
+        let mut state = self.state();
+        state.dropped = true;
+        if let Some(waker) = state.sent_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Controls a mock socket from the test, see the [module documentation](index.html).
+///
+/// Handles are cheap to clone and every clone controls the same socket.
+#[derive(Clone)]
+pub struct MockHandle(Arc<Mutex<State>>);
+
+impl MockHandle {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap()
+    }
+
+    /// Queue a message for the socket to receive.
+    ///
+    /// SUB and XSUB mocks skip messages whose first frame matches none of their
+    /// subscriptions, like ØMQ does.
+    pub fn inject<M: IntoMultipart>(&self, msg: M) {
+        let mut state = self.state();
+        state.incoming.push_back(msg.into_multipart());
+        if let Some(waker) = state.recv_waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// The number of messages sent and not taken out yet.
+    pub fn sent_len(&self) -> usize {
+        self.state().sent.len()
+    }
+
+    /// Take out every message sent so far, oldest first.
+    pub fn take_sent(&self) -> Vec<Multipart> {
+        let mut state = self.state();
+        let sent = state.sent.drain(..).collect();
+        if let Some(waker) = state.send_waker.take() {
+            waker.wake();
+        }
+        sent
+    }
+
+    /// Wait for the next message sent and take it out, or `None` once the socket was dropped
+    /// without sending any more.
+    pub async fn next_sent(&self) -> Option<Multipart> {
+        poll_fn(|cx| {
+            let mut state = self.state();
+            if let Some(msg) = state.sent.pop_front() {
+                if let Some(waker) = state.send_waker.take() {
+                    waker.wake();
+                }
+                return Poll::Ready(Some(msg));
+            }
+            if state.dropped {
+                return Poll::Ready(None);
+            }
+            state.sent_waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// The topics a SUB or XSUB mock is subscribed to, in the order they were subscribed.
+    pub fn subscriptions(&self) -> Vec<Vec<u8>> {
+        self.state().subscriptions.clone().unwrap_or_default()
+    }
+
+    /// Let sending wait, as if ØMQ kept reporting `EAGAIN`, until this is called with `false`.
+    pub fn set_blocked(&self, blocked: bool) {
+        let mut state = self.state();
+        state.blocked = blocked;
+        if !blocked {
+            state.wake_all();
+        }
+    }
+
+    /// Let sending wait while `hwm` sent messages were not taken out yet, like a socket that
+    /// reached its high water mark. `None` lifts the limit, which is the default.
+    pub fn set_high_water_mark(&self, hwm: Option<usize>) {
+        let mut state = self.state();
+        state.high_water_mark = hwm;
+        state.wake_all();
+    }
+
+    /// Simulate the last peer disconnecting.
+    ///
+    /// From now on PUB and XPUB mocks drop what they send, see
+    /// [`dropped`](#method.dropped), and every other mock waits to send until
+    /// [`reconnect`](#method.reconnect) is called, like ØMQ does for sockets without peers.
+    pub fn disconnect(&self) {
+        self.state().connected = false;
+    }
+
+    /// Simulate a peer connecting again after [`disconnect`](#method.disconnect).
+    pub fn reconnect(&self) {
+        let mut state = self.state();
+        state.connected = true;
+        state.wake_all();
+    }
+
+    /// The number of messages PUB and XPUB mocks dropped while disconnected.
+    pub fn dropped(&self) -> usize {
+        self.state().dropped_messages
+    }
+
+    /// Fail the next send with `error`, which can also be a bare `SendErrorKind`.
+    pub fn fail_next_send(&self, error: impl Into<SendError>) {
+        let mut state = self.state();
+        state.send_error = Some(error.into());
+        state.wake_all();
+    }
+
+    /// Fail the next receive with `error`, which can also be a bare `RecvErrorKind`.
+    pub fn fail_next_recv(&self, error: impl Into<RecvError>) {
+        let mut state = self.state();
+        state.recv_error = Some(error.into());
+        state.wake_all();
+    }
+
+    /// Simulate the context being terminated: streams end and sending fails with
+    /// `ContextTerminated`, even for messages still queued with [`inject`](#method.inject).
+    pub fn terminate(&self) {
+        let mut state = self.state();
+        state.terminated = true;
+        state.wake_all();
+    }
+}
+
+/// Implement `Sink` and `ZmqSend` for a mock with a `0: Mock` field.
+macro_rules! mock_sink {
+    ($mock:ident) => {
+        impl<M: IntoMultipart> Sink<M> for $mock {
+            type Error = SendError;
+
+            fn poll_ready(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Result<(), Self::Error>> {
+                self.0.poll_ready(cx)
+            }
+
+            fn start_send(self: Pin<&mut Self>, item: M) -> Result<(), Self::Error> {
+                self.0.start_send(item.into_multipart())
+            }
+
+            fn poll_flush(
+                self: Pin<&mut Self>,
+                _: &mut Context<'_>,
+            ) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn poll_close(
+                self: Pin<&mut Self>,
+                _: &mut Context<'_>,
+            ) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+        }
+
+        impl ZmqSend for $mock {
+            fn poll_send(
+                &mut self,
+                cx: &mut Context<'_>,
+                msg: &mut Option<Multipart>,
+            ) -> Poll<Result<(), SendError>> {
+                self.0.poll_send(cx, msg)
+            }
+        }
+    };
+}
+
+/// Implement `Stream`, `FusedStream` and `ZmqRecv` for a mock with a `0: Mock` field.
+macro_rules! mock_stream {
+    ($mock:ident) => {
+        impl Stream for $mock {
+            type Item = Result<Multipart, RecvError>;
+
+            fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                self.0.poll_next(cx)
+            }
+        }
+
+        impl FusedStream for $mock {
+            fn is_terminated(&self) -> bool {
+                self.0.is_terminated()
+            }
+        }
+
+        impl ZmqRecv for $mock {
+            fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+                self.0.poll_recv(cx)
+            }
+        }
+    };
+}
+
+/// Define a mock with its constructor.
+macro_rules! mock {
+    ($(#[$doc:meta])* $mock:ident, $socket_type:ident) => {
+        $(#[$doc])*
+        pub struct $mock(Mock);
+
+        impl $mock {
+            /// Create the mock and the handle controlling it.
+            pub fn new() -> (Self, MockHandle) {
+                let (mock, handle) = Mock::new(SocketType::$socket_type);
+                (Self(mock), handle)
+            }
+        }
+    };
+}
+
+mock!(
+    /// A mock [`Dealer`](../dealer/struct.Dealer.html).
+    MockDealer,
+    DEALER
+);
+mock_sink!(MockDealer);
+mock_stream!(MockDealer);
+
+mock!(
+    /// A mock [`Pair`](../pair/struct.Pair.html).
+    MockPair,
+    PAIR
+);
+mock_sink!(MockPair);
+mock_stream!(MockPair);
+
+mock!(
+    /// A mock [`Publish`](../publish/struct.Publish.html).
+    MockPublish,
+    PUB
+);
+mock_sink!(MockPublish);
+
+mock!(
+    /// A mock [`Pull`](../pull/struct.Pull.html).
+    MockPull,
+    PULL
+);
+mock_stream!(MockPull);
+
+mock!(
+    /// A mock [`Push`](../push/struct.Push.html).
+    MockPush,
+    PUSH
+);
+mock_sink!(MockPush);
+
+mock!(
+    /// A mock [`Router`](../router/struct.Router.html).
+    ///
+    /// Messages are neither given nor stripped of routing ids, inject them with the id of the
+    /// peer in front.
+    MockRouter,
+    ROUTER
+);
+mock_sink!(MockRouter);
+mock_stream!(MockRouter);
+
+mock!(
+    /// A mock [`ZmqStream`](../stream/struct.ZmqStream.html).
+    ///
+    /// Messages carry the routing id of a TCP connection in front of the data like on a real
+    /// STREAM socket, e.g. inject `vec![id, b""]` to announce a new connection. There is no
+    /// `incoming`, test the code that handles the raw messages instead.
+    MockStream,
+    STREAM
+);
+mock_sink!(MockStream);
+mock_stream!(MockStream);
+
+mock!(
+    /// A mock [`Subscribe`](../subscribe/struct.Subscribe.html).
+    MockSubscribe,
+    SUB
+);
+mock_stream!(MockSubscribe);
+
+mock!(
+    /// A mock [`XPublish`](../xpublish/struct.XPublish.html).
+    ///
+    /// Subscriptions are received as injected, e.g. `vec![b"\x01topic"]` for subscribing to
+    /// `topic`.
+    MockXPublish,
+    XPUB
+);
+mock_sink!(MockXPublish);
+mock_stream!(MockXPublish);
+
+mock!(
+    /// A mock [`XSubscribe`](../xsubscribe/struct.XSubscribe.html).
+    MockXSubscribe,
+    XSUB
+);
+mock_stream!(MockXSubscribe);
+
+mock!(
+    /// A mock [`Reply`](../reply/struct.Reply.html).
+    ///
+    /// Like a real REP socket, it only sends after receiving a request.
+    MockReply,
+    REP
+);
+mock_stream!(MockReply);
+
+mock!(
+    /// A mock [`Request`](../request/struct.Request.html).
+    ///
+    /// Like a real REQ socket, it only sends again after receiving a reply.
+    MockRequest,
+    REQ
+);
+
+macro_rules! mock_subscribe {
+    ($mock:ident) => {
+        impl $mock {
+            /// Subscribe to `topic`, see [`MockHandle::subscriptions`].
+            ///
+            /// [`MockHandle::subscriptions`]: struct.MockHandle.html#method.subscriptions
+            pub fn set_subscribe(&self, topic: &str) -> Result<(), SubscribeError> {
+                self.0.subscribe(topic, true)
+            }
+
+            /// Unsubscribe from `topic`.
+            pub fn set_unsubscribe(&self, topic: &str) -> Result<(), SubscribeError> {
+                self.0.subscribe(topic, false)
+            }
+        }
+    };
+}
+
+mock_subscribe!(MockSubscribe);
+mock_subscribe!(MockXSubscribe);
+
+impl MockRequest {
+    /// Send a request, see [`Request::send`](../request/struct.Request.html#method.send).
+    pub async fn send<M: IntoMultipart>(&self, msg: M) -> Result<(), RequestReplyError> {
+        let mut msg = Some(msg);
+        poll_fn(|cx| self.0.poll_send(cx, &mut msg))
+            .await
+            .map_err(|e| zmq::Error::from(e).into())
+    }
+
+    /// Receive a reply, see [`Request::recv`](../request/struct.Request.html#method.recv).
+    pub async fn recv(&self) -> Result<Multipart, RequestReplyError> {
+        recv(&self.0).await
+    }
+}
+
+impl MockReply {
+    /// Receive a request, see [`Reply::recv`](../reply/struct.Reply.html#method.recv).
+    pub async fn recv(&self) -> Result<Multipart, RequestReplyError> {
+        recv(&self.0).await
+    }
+
+    /// Send a reply, see [`Reply::send`](../reply/struct.Reply.html#method.send).
+    pub async fn send<M: IntoMultipart>(&self, msg: M) -> Result<(), RequestReplyError> {
+        let mut msg = Some(msg);
+        poll_fn(|cx| self.0.poll_send(cx, &mut msg))
+            .await
+            .map_err(|e| zmq::Error::from(e).into())
+    }
+}
+
+async fn recv(mock: &Mock) -> Result<Multipart, RequestReplyError> {
+    poll_fn(|cx| mock.poll_recv(cx))
+        .await
+        .map_err(|e| zmq::Error::from(e).into())
+}
+
+impl ZmqSend for MockRequest {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        self.0.poll_send(cx, msg)
+    }
+}
+
+impl ZmqRecv for MockRequest {
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        self.0.poll_recv(cx)
+    }
+}
+
+impl ZmqSend for MockReply {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        self.0.poll_send(cx, msg)
+    }
+}
@@ -0,0 +1,225 @@
+//! W3C trace context propagation
+//!
+//! Distributed tracing loses track of a request whenever it crosses a ØMQ hop, since messages
+//! carry no headers. Sockets built with [`SocketBuilder::trace_context_with`] put a
+//! [`TraceParent`] into a frame of its own in front of every message they send, and sockets
+//! built with [`SocketBuilder::trace_context`] take that frame out again when receiving, so the
+//! rest of the code keeps seeing the same [`Multipart`] as before. The `recv_traced` method of
+//! the socket returns each message along with its context, and so does a batch turned
+//! [`traced`]. The context of the message received last is also available from the
+//! `last_trace_context` method of the socket.
+//!
+//! The header frame holds `traceparent: ` followed by the header value as specified by
+//! [W3C Trace Context], e.g. `traceparent: 00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+//! It goes first, except behind the routing id on ROUTER sockets and behind the topic on
+//! PUB/SUB and XPUB/XSUB sockets, so routing and subscriptions keep working. The envelope of
+//! REQ sockets talking to a ROUTER is not accounted for, and STREAM sockets never carry the
+//! header. Messages without a header frame are received unchanged, so peers that do not use
+//! the convention can still talk to sockets that do.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{Result, SinkExt};
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     // Typically the span context of the current task, e.g. from OpenTelemetry.
+//!     let current = || "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01".parse().ok();
+//!     let mut push = async_zmq::push("tcp://127.0.0.1:5555")?
+//!         .trace_context_with(current)
+//!         .connect()?;
+//!     push.send(vec!["hello"]).await?;
+//!
+//!     let mut pull = async_zmq::pull("tcp://127.0.0.1:5555")?
+//!         .trace_context()
+//!         .bind()?;
+//!     let (msg, context) = pull.recv_traced().await?;
+//!     assert_eq!(msg.len(), 1);
+//!     println!("{:?}", context);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`SocketBuilder::trace_context_with`]: ../struct.SocketBuilder.html#method.trace_context_with
+//! [`SocketBuilder::trace_context`]: ../struct.SocketBuilder.html#method.trace_context
+//! [`TraceParent`]: struct.TraceParent.html
+//! [`Multipart`]: ../type.Multipart.html
+//! [`traced`]: ../struct.RecvBatch.html#method.traced
+//! [W3C Trace Context]: https://www.w3.org/TR/trace-context/#traceparent-header
+
+use std::cell::Cell;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use zmq::{Message, SocketType};
+
+use crate::{Multipart, ParseTraceParentError};
+
+/// What a header frame starts with.
+const PREFIX: &[u8] = b"traceparent: ";
+
+/// The length of a version 00 header value.
+const LEN: usize = 55;
+
+/// A W3C `traceparent` header: the trace a message belongs to and the span it was sent from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceParent {
+    trace_id: u128,
+    parent_id: u64,
+    flags: u8,
+}
+
+impl TraceParent {
+    /// Create a header for the span `parent_id` in the trace `trace_id`.
+    ///
+    /// Returns `None` if either id is zero, which the specification reserves as invalid.
+    pub fn new(trace_id: u128, parent_id: u64, flags: u8) -> Option<Self> {
+        if trace_id == 0 || parent_id == 0 {
+            return None;
+        }
+        Some(Self {
+            trace_id,
+            parent_id,
+            flags,
+        })
+    }
+
+    /// The id of the trace.
+    pub fn trace_id(&self) -> u128 {
+        self.trace_id
+    }
+
+    /// The id of the span the message was sent from.
+    pub fn parent_id(&self) -> u64 {
+        self.parent_id
+    }
+
+    /// The trace flags.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+
+    /// Whether the sender recorded the trace.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & 1 == 1
+    }
+}
+
+impl fmt::Display for TraceParent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id, self.parent_id, self.flags
+        )
+    }
+}
+
+impl FromStr for TraceParent {
+    type Err = ParseTraceParentError;
+
+    /// Parse a header value. Versions above 00 are parsed as far as version 00 goes.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.as_bytes();
+        if value.len() < LEN || (value.len() > LEN && value[LEN] != b'-') {
+            return Err(ParseTraceParentError);
+        }
+        let version = hex(&value[0..2])? as u8;
+        if version == 0xff || (version == 0 && value.len() != LEN) {
+            return Err(ParseTraceParentError);
+        }
+        if value[2] != b'-' || value[35] != b'-' || value[52] != b'-' {
+            return Err(ParseTraceParentError);
+        }
+        Self::new(
+            hex(&value[3..35])?,
+            hex(&value[36..52])? as u64,
+            hex(&value[53..55])? as u8,
+        )
+        .ok_or(ParseTraceParentError)
+    }
+}
+
+/// Parse lowercase hex digits, which is all the specification allows.
+fn hex(digits: &[u8]) -> Result<u128, ParseTraceParentError> {
+    digits.iter().try_fold(0, |value, digit| {
+        let digit = match digit {
+            b'0'..=b'9' => digit - b'0',
+            b'a'..=b'f' => digit - b'a' + 10,
+            _ => return Err(ParseTraceParentError),
+        };
+        Ok(value << 4 | u128::from(digit))
+    })
+}
+
+/// Returns the context to send with the next message, see
+/// [`SocketBuilder::trace_context_with`](../struct.SocketBuilder.html#method.trace_context_with).
+pub(crate) type CurrentTraceParent = Arc<dyn Fn() -> Option<TraceParent> + Send + Sync>;
+
+/// The header frame handling of a socket.
+pub(crate) struct Envelope {
+    /// The index of the header frame.
+    position: usize,
+    current: Option<CurrentTraceParent>,
+    /// The context of the message received last.
+    last: Cell<Option<TraceParent>>,
+}
+
+impl Envelope {
+    /// Create the header frame handling for a socket of `socket_type`, unless that type cannot
+    /// carry a header frame.
+    pub(crate) fn new(
+        socket_type: SocketType,
+        current: Option<CurrentTraceParent>,
+    ) -> Option<Self> {
+        let position = match socket_type {
+            SocketType::STREAM => return None,
+            SocketType::ROUTER
+            | SocketType::PUB
+            | SocketType::SUB
+            | SocketType::XPUB
+            | SocketType::XSUB => 1,
+            _ => 0,
+        };
+        Some(Self {
+            position,
+            current,
+            last: Cell::new(None),
+        })
+    }
+
+    /// Add a header frame with the current context to `msg`, if there is one.
+    pub(crate) fn inject(&self, msg: &mut Multipart) {
+        let current = match &self.current {
+            Some(current) => current,
+            None => return,
+        };
+        if let Some(context) = current() {
+            if msg.len() >= self.position {
+                let header = format!("traceparent: {}", context);
+                msg.insert(self.position, Message::from(header.as_bytes()));
+            }
+        }
+    }
+
+    /// Take the header frame out of `msg` and return its context, if it has one. The context
+    /// is also remembered as the one of the message received last.
+    pub(crate) fn extract(&self, msg: &mut Multipart) -> Option<TraceParent> {
+        let context = msg.get(self.position).and_then(|frame| {
+            let value = frame.strip_prefix(PREFIX)?;
+            std::str::from_utf8(value).ok()?.parse().ok()
+        });
+        if context.is_some() {
+            msg.remove(self.position);
+        }
+        self.last.set(context);
+        context
+    }
+
+    /// The context of the message received last, if it had one.
+    pub(crate) fn last(&self) -> Option<TraceParent> {
+        self.last.get()
+    }
+}
@@ -0,0 +1,307 @@
+//! Traits shared by the socket types
+//!
+//! Every socket type that can send implements [`ZmqSend`], every one that can receive
+//! implements [`ZmqRecv`], with the same error types throughout: [`SendError`] and
+//! [`RecvError`]. Together with [`ZmqSocketExt`], which every socket type implements for
+//! managing endpoints, setting common options and monitoring, they let code be generic over
+//! sockets of any type, e.g. any socket to receive from.
+//!
+//! The methods are named `send_msg` and `recv_msg` so that they do not clash with
+//! [`SinkExt::send`] on sockets that are also a `Sink`. The mocks of the [`testing`] module
+//! implement [`ZmqSend`] and [`ZmqRecv`] as well.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use async_zmq::{Multipart, RecvErrorKind, Result, ZmqRecv, ZmqSocketExt};
+//!
+//! async fn drain<S: ZmqRecv + ZmqSocketExt>(socket: &mut S) -> Result<Vec<Multipart>> {
+//!     let mut messages = Vec::new();
+//!     loop {
+//!         match socket.recv_msg().await {
+//!             Ok(msg) => messages.push(msg),
+//!             Err(e) if matches!(e.kind(), RecvErrorKind::ContextTerminated) => return Ok(messages),
+//!             Err(e) => return Err(e.into()),
+//!         }
+//!     }
+//! }
+//!
+//! #[async_std::main]
+//! async fn main() -> Result<()> {
+//!     let mut pull = async_zmq::pull("tcp://127.0.0.1:5555")?.bind()?;
+//!     pull.bind("ipc:///tmp/pull")?;
+//!     let mut reply = async_zmq::reply("tcp://127.0.0.1:5556")?.bind()?;
+//!
+//!     drain(&mut pull).await?;
+//!     drain(&mut reply).await?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`ZmqSend`]: trait.ZmqSend.html
+//! [`ZmqRecv`]: trait.ZmqRecv.html
+//! [`ZmqSocketExt`]: trait.ZmqSocketExt.html
+//! [`SendError`]: ../errors/struct.SendError.html
+//! [`RecvError`]: ../errors/struct.RecvError.html
+//! [`SinkExt::send`]: ../trait.SinkExt.html#method.send
+//! [`testing`]: ../testing/index.html
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::ready;
+use zmq::{Mechanism, SocketEvent, SocketType};
+
+use crate::{
+    monitor::{Monitor, PeerLiveness},
+    reactor::AsRawSocket,
+    socket::millis,
+    stats::SocketStats,
+    ErrorContext, IntoMultipart, Multipart, Operation, RecvError, RecvErrorKind, SendError, Sink,
+    SocketError, Stream,
+};
+
+/// A socket that can send messages.
+pub trait ZmqSend {
+    /// Attempt to send `msg`, which is taken once the socket accepted it.
+    ///
+    /// Returns `Pending` with `msg` left in place while the socket is not ready. Called again
+    /// once `msg` was taken, it waits for the message to be handed over to ØMQ, for sockets
+    /// that queue messages first.
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>>;
+
+    /// Send `msg`, waiting for the socket to become ready.
+    fn send_msg<M: IntoMultipart>(&mut self, msg: M) -> SendMsg<'_, Self>
+    where
+        Self: Sized,
+    {
+        SendMsg {
+            socket: self,
+            msg: Some(msg.into_multipart()),
+        }
+    }
+}
+
+/// A socket that can receive messages.
+pub trait ZmqRecv {
+    /// Attempt to receive the next message.
+    ///
+    /// Once the context was terminated, every call fails with
+    /// [`RecvErrorKind::ContextTerminated`](../errors/enum.RecvErrorKind.html#variant.ContextTerminated).
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>>;
+
+    /// Receive the next message, waiting for one to arrive.
+    fn recv_msg(&mut self) -> RecvMsg<'_, Self>
+    where
+        Self: Sized,
+    {
+        RecvMsg { socket: self }
+    }
+}
+
+/// The operations every socket type supports, whatever it sends or receives.
+///
+/// This trait is sealed: it cannot be implemented outside of this crate.
+pub trait ZmqSocketExt: sealed::Sealed {
+    /// The type of the socket.
+    fn socket_type(&self) -> SocketType;
+
+    /// Represent as `Socket` from zmq crate in case you want to call its methods.
+    fn as_raw_socket(&self) -> &zmq::Socket {
+        self.zmq_socket().as_socket()
+    }
+
+    /// A snapshot of the traffic that went through the socket, see [`SocketStats`].
+    ///
+    /// [`SocketStats`]: ../stats/struct.SocketStats.html
+    fn stats(&self) -> SocketStats {
+        self.zmq_socket().stats()
+    }
+
+    /// Bind to another endpoint, in addition to the ones the socket is bound or connected to.
+    fn bind(&self, endpoint: &str) -> crate::Result<()> {
+        attach(self, Operation::Bind, endpoint, zmq::Socket::bind)
+    }
+
+    /// Connect to another endpoint, in addition to the ones the socket is bound or connected
+    /// to.
+    fn connect(&self, endpoint: &str) -> crate::Result<()> {
+        attach(self, Operation::Connect, endpoint, zmq::Socket::connect)
+    }
+
+    /// Stop listening on an endpoint the socket was bound to.
+    fn unbind(&self, endpoint: &str) -> crate::Result<()> {
+        attach(self, Operation::Unbind, endpoint, zmq::Socket::unbind)
+    }
+
+    /// Disconnect from an endpoint the socket was connected to.
+    fn disconnect(&self, endpoint: &str) -> crate::Result<()> {
+        attach(
+            self,
+            Operation::Disconnect,
+            endpoint,
+            zmq::Socket::disconnect,
+        )
+    }
+
+    /// The security mechanism the socket authenticates its peers with, e.g.
+    /// `Mechanism::ZMQ_PLAIN` for sockets built with
+    /// [`plain_server`](../struct.SocketBuilder.html#method.plain_server).
+    fn mechanism(&self) -> crate::Result<Mechanism> {
+        Ok(self.as_raw_socket().get_mechanism()?)
+    }
+
+    /// The endpoint the socket was bound or connected to last, with the port ØMQ picked for
+    /// wildcard addresses like `tcp://127.0.0.1:*`, or `None` if there is none.
+    fn last_endpoint(&self) -> crate::Result<Option<String>> {
+        match self.as_raw_socket().get_last_endpoint()? {
+            Ok(endpoint) if !endpoint.is_empty() => Ok(Some(endpoint)),
+            _ => Ok(None),
+        }
+    }
+
+    /// How long ØMQ keeps trying to deliver queued messages once the socket is closed, `None`
+    /// for as long as it takes.
+    fn linger(&self) -> crate::Result<Option<Duration>> {
+        let linger = self.as_raw_socket().get_linger()?;
+        Ok(u64::try_from(linger).ok().map(Duration::from_millis))
+    }
+
+    /// Set how long ØMQ keeps trying to deliver queued messages once the socket is closed,
+    /// `None` for as long as it takes.
+    fn set_linger(&self, linger: Option<Duration>) -> crate::Result<()> {
+        Ok(self.as_raw_socket().set_linger(linger.map_or(-1, millis))?)
+    }
+
+    /// How many outgoing messages ØMQ queues per peer before sending waits or drops them, zero
+    /// for no limit.
+    fn send_hwm(&self) -> crate::Result<i32> {
+        Ok(self.as_raw_socket().get_sndhwm()?)
+    }
+
+    /// Set how many outgoing messages ØMQ queues per peer, see [`send_hwm`](#method.send_hwm).
+    ///
+    /// Only applies to peers bound or connected afterwards.
+    fn set_send_hwm(&self, hwm: i32) -> crate::Result<()> {
+        Ok(self.as_raw_socket().set_sndhwm(hwm)?)
+    }
+
+    /// How many incoming messages ØMQ queues per peer, zero for no limit.
+    fn recv_hwm(&self) -> crate::Result<i32> {
+        Ok(self.as_raw_socket().get_rcvhwm()?)
+    }
+
+    /// Set how many incoming messages ØMQ queues per peer, see [`recv_hwm`](#method.recv_hwm).
+    ///
+    /// Only applies to peers bound or connected afterwards.
+    fn set_recv_hwm(&self, hwm: i32) -> crate::Result<()> {
+        Ok(self.as_raw_socket().set_rcvhwm(hwm)?)
+    }
+
+    /// Stream the lifecycle events selected in `events`, see [`Monitor`].
+    ///
+    /// [`Monitor`]: ../monitor/struct.Monitor.html
+    fn monitor(&self, events: &[SocketEvent]) -> Result<Monitor, SocketError> {
+        Monitor::new(self.zmq_socket(), events)
+    }
+
+    /// Stream peers becoming alive or dead, see [`PeerLiveness`].
+    ///
+    /// [`PeerLiveness`]: ../monitor/struct.PeerLiveness.html
+    fn peer_liveness(&self) -> Result<PeerLiveness, SocketError> {
+        PeerLiveness::new(self.zmq_socket())
+    }
+}
+
+pub(crate) mod sealed {
+    use crate::reactor::ZmqSocket;
+
+    /// Gives [`ZmqSocketExt`](../trait.ZmqSocketExt.html) access to the socket.
+    pub trait Sealed {
+        fn zmq_socket(&self) -> &ZmqSocket;
+    }
+}
+
+/// Bind, connect, unbind or disconnect `socket`, naming the endpoint, the socket type and the
+/// operation in the error.
+fn attach<S: ZmqSocketExt + ?Sized>(
+    socket: &S,
+    operation: Operation,
+    endpoint: &str,
+    attach: impl FnOnce(&zmq::Socket, &str) -> Result<(), zmq::Error>,
+) -> crate::Result<()> {
+    attach(socket.as_raw_socket(), endpoint).map_err(|error| {
+        crate::Error::from(SocketError::from(error)).with_context(ErrorContext {
+            endpoint: Some(endpoint.to_owned()),
+            socket_type: Some(socket.socket_type()),
+            operation: Some(operation),
+        })
+    })
+}
+
+/// Future for [`ZmqSend::send_msg`](trait.ZmqSend.html#method.send_msg).
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct SendMsg<'a, S> {
+    socket: &'a mut S,
+    msg: Option<Multipart>,
+}
+
+impl<S: ZmqSend> Future for SendMsg<'_, S> {
+    type Output = Result<(), SendError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        this.socket.poll_send(cx, &mut this.msg)
+    }
+}
+
+/// Future for [`ZmqRecv::recv_msg`](trait.ZmqRecv.html#method.recv_msg).
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct RecvMsg<'a, S> {
+    socket: &'a mut S,
+}
+
+impl<S: ZmqRecv> Future for RecvMsg<'_, S> {
+    type Output = Result<Multipart, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut().socket.poll_recv(cx)
+    }
+}
+
+/// [`ZmqSend::poll_send`](trait.ZmqSend.html#method.poll_send) through the `Sink` of a socket.
+pub(crate) fn poll_send_sink<S>(
+    sink: &mut S,
+    cx: &mut Context<'_>,
+    msg: &mut Option<Multipart>,
+) -> Poll<Result<(), SendError>>
+where
+    S: Sink<Multipart, Error = SendError> + Unpin,
+{
+    if msg.is_some() {
+        ready!(Pin::new(&mut *sink).poll_ready(cx))?;
+        if let Some(msg) = msg.take() {
+            Pin::new(&mut *sink).start_send(msg)?;
+        }
+    }
+    Pin::new(sink).poll_flush(cx)
+}
+
+/// [`ZmqRecv::poll_recv`](trait.ZmqRecv.html#method.poll_recv) through the `Stream` of a
+/// socket.
+pub(crate) fn poll_recv_stream<S>(
+    stream: &mut S,
+    cx: &mut Context<'_>,
+) -> Poll<Result<Multipart, RecvError>>
+where
+    S: Stream<Item = Result<Multipart, RecvError>> + Unpin,
+{
+    let item = ready!(Pin::new(stream).poll_next(cx));
+    Poll::Ready(item.unwrap_or(Err(RecvErrorKind::ContextTerminated.into())))
+}
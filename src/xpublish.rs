@@ -5,6 +5,14 @@
 //!
 //! An xpublish socket must be paired with a [`subscribe`] or [`xsubscribe`] socket.
 //!
+//! Reading the socket as a `Stream` yields the subscription messages of its peers as they are
+//! on the wire, [`XPublish::subscriptions`] parses them into [`SubscriptionEvent`]s instead.
+//! ØMQ only reports the first subscription to and the last unsubscription from each topic,
+//! [`XPublish::set_verbose`] reports every subscription.
+//!
+//! Reporting every unsubscription (`ZMQ_XPUB_VERBOSER`) and approving subscriptions by hand
+//! (`ZMQ_XPUB_MANUAL`) are not supported, [`zmq`] has no way to turn them on.
+//!
 //! # Example
 //!
 //! ```no_run
@@ -14,7 +22,7 @@
 //! async fn main() -> Result<()> {
 //!     let mut zmq = async_zmq::xpublish("tcp://127.0.0.1:5555")?.bind()?;
 //!
-//!     zmq.send(vec!["topic", "broadcast message"].into()).await?;
+//!     zmq.send(vec!["topic", "broadcast message"]).await?;
 //!     Ok(())
 //! }
 //! ```
@@ -26,74 +34,205 @@
 //! [`SinkExt`]: ../trait.SinkExt.html
 //! [`Stream`]: ../trait.Stream.html
 //! [`StreamExt`]: ../trait.StreamExt.html
+//! [`zmq`]: https://crates.io/crates/zmq
+//! [`XPublish::subscriptions`]: struct.XPublish.html#method.subscriptions
+//! [`XPublish::set_verbose`]: struct.XPublish.html#method.set_verbose
+//! [`SubscriptionEvent`]: struct.SubscriptionEvent.html
 
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use crate::{
     reactor::{AsRawSocket, ZmqSocket},
-    socket::{Broker, Multipart, MultipartIter, SocketBuilder},
-    SendError, Sink, SocketError, Stream,
+    socket::{socket_methods, Broker, IntoMultipart, Multipart, SocketBuilder},
+    traits::{self, sealed::Sealed, ZmqRecv, ZmqSend, ZmqSocketExt},
+    RecvError, SendError, Sink, SocketError, Stream, SubscribeError,
 };
-use zmq::{Message, SocketType};
+use futures::stream::FusedStream;
+use zmq::SocketType;
 
 /// Create a ZMQ socket with XPUB type
-pub fn xpublish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(
-    endpoint: &str,
-) -> Result<SocketBuilder<'_, XPublish<I, T>>, SocketError> {
-    Ok(SocketBuilder::new(SocketType::XPUB, endpoint))
+pub fn xpublish(endpoint: &str) -> Result<SocketBuilder<'_, XPublish>, SocketError> {
+    Ok(SocketBuilder::typed(SocketType::XPUB, endpoint))
 }
 
 /// The async wrapper of ZMQ socket with XPUB type
-pub struct XPublish<I: Iterator<Item = T> + Unpin, T: Into<Message>>(Broker<I, T>);
+pub struct XPublish(Broker);
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> XPublish<I, T> {
+impl XPublish {
     /// Represent as `Socket` from zmq crate in case you want to call its methods.
     pub fn as_raw_socket(&self) -> &zmq::Socket {
         self.0.socket.as_socket()
     }
+
+    socket_methods!(send 0);
+
+    /// Report every subscription of the peers, instead of only the first one to each topic.
+    ///
+    /// Unsubscriptions are still only reported once the last peer unsubscribed from a topic.
+    pub fn set_verbose(&self, verbose: bool) -> Result<(), SubscribeError> {
+        Ok(self.as_raw_socket().set_xpub_verbose(verbose)?)
+    }
+
+    /// Send `msg` to every peer as soon as it connects, or stop doing so with `None`.
+    ///
+    /// Peers only receive it if they subscribed to a matching topic.
+    pub fn set_welcome_msg(&self, msg: Option<&str>) -> Result<(), SubscribeError> {
+        Ok(self.as_raw_socket().set_xpub_welcome_msg(msg)?)
+    }
+
+    /// Stream the subscriptions of the peers, see [`Subscriptions`].
+    ///
+    /// [`Subscriptions`]: struct.Subscriptions.html
+    pub fn subscriptions(&mut self) -> Subscriptions<'_> {
+        Subscriptions(&mut self.0.socket)
+    }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Sink<MultipartIter<I, T>> for XPublish<I, T> {
+impl<M: IntoMultipart> Sink<M> for XPublish {
     type Error = SendError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_ready(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 
-    fn start_send(self: Pin<&mut Self>, item: MultipartIter<I, T>) -> Result<(), Self::Error> {
-        Pin::new(&mut self.get_mut().0)
-            .start_send(item)
-            .map_err(Into::into)
+    fn start_send(self: Pin<&mut Self>, item: M) -> Result<(), Self::Error> {
+        Pin::new(&mut self.get_mut().0).start_send(item.into_multipart())
     }
 
     fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_flush(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         Sink::poll_close(Pin::new(&mut self.get_mut().0), cx)
-            .map(|result| result.map_err(Into::into))
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> Stream for XPublish<I, T> {
-    type Item = Result<Multipart, SendError>;
+impl Stream for XPublish {
+    type Item = Result<Multipart, RecvError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().0)
-            .poll_next(cx)
-            .map(|poll| poll.map(|result| result.map_err(Into::into)))
+        Pin::new(&mut self.get_mut().0).poll_next(cx)
+    }
+}
+
+impl FusedStream for XPublish {
+    fn is_terminated(&self) -> bool {
+        self.0.socket.is_terminated()
+    }
+}
+
+impl ZmqSend for XPublish {
+    fn poll_send(
+        &mut self,
+        cx: &mut Context<'_>,
+        msg: &mut Option<Multipart>,
+    ) -> Poll<Result<(), SendError>> {
+        traits::poll_send_sink(self, cx, msg)
     }
 }
 
-impl<I: Iterator<Item = T> + Unpin, T: Into<Message>> From<zmq::Socket> for XPublish<I, T> {
+impl ZmqRecv for XPublish {
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Result<Multipart, RecvError>> {
+        traits::poll_recv_stream(self, cx)
+    }
+}
+
+impl ZmqSocketExt for XPublish {
+    fn socket_type(&self) -> SocketType {
+        SocketType::XPUB
+    }
+}
+
+impl Sealed for XPublish {
+    fn zmq_socket(&self) -> &ZmqSocket {
+        &self.0.socket
+    }
+}
+
+impl From<ZmqSocket> for XPublish {
+    fn from(socket: ZmqSocket) -> Self {
+        Self(Broker::new(socket))
+    }
+}
+
+impl From<zmq::Socket> for XPublish {
+    /// Register a socket created with the zmq crate in the global reactor.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the global reactor cannot be started or was shut down, see
+    /// [`BuildSocket::from_raw`](../trait.BuildSocket.html#method.from_raw) for the fallible
+    /// version.
     fn from(socket: zmq::Socket) -> Self {
-        Self(Broker {
-            socket: ZmqSocket::from(socket),
-            buffer: None,
+        crate::BuildSocket::from_raw(socket).expect("cannot register the socket in the reactor")
+    }
+}
+
+/// Whether a peer subscribed to or unsubscribed from a topic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubscriptionKind {
+    /// The peer subscribed to the topic.
+    Subscribe,
+    /// The peer unsubscribed from the topic.
+    Unsubscribe,
+}
+
+/// A subscription message received from a peer of an XPUB socket.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubscriptionEvent {
+    /// Whether the peer subscribed or unsubscribed.
+    pub kind: SubscriptionKind,
+    /// The topic prefix, empty for all messages.
+    pub topic: Vec<u8>,
+}
+
+impl SubscriptionEvent {
+    /// Parse a message received on an XPUB socket, which is `None` for anything but a
+    /// subscription message.
+    fn parse(msg: &Multipart) -> Option<Self> {
+        let (flag, topic) = msg.first()?.split_first()?;
+        let kind = match flag {
+            1 => SubscriptionKind::Subscribe,
+            0 => SubscriptionKind::Unsubscribe,
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            topic: topic.to_vec(),
         })
     }
 }
+
+/// A stream of the subscriptions of the peers of an XPUB socket, see
+/// [`XPublish::subscriptions`].
+///
+/// Messages that are not subscription messages, which XSUB peers may send, are skipped.
+///
+/// [`XPublish::subscriptions`]: struct.XPublish.html#method.subscriptions
+pub struct Subscriptions<'a>(&'a mut ZmqSocket);
+
+impl Stream for Subscriptions<'_> {
+    type Item = Result<SubscriptionEvent, RecvError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match futures::ready!(self.0.poll_next(cx)) {
+                Some(Ok(msg)) => {
+                    if let Some(event) = SubscriptionEvent::parse(&msg) {
+                        return Poll::Ready(Some(Ok(event)));
+                    }
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(self.0.recv_error(e)))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl FusedStream for Subscriptions<'_> {
+    fn is_terminated(&self) -> bool {
+        self.0.is_terminated()
+    }
+}
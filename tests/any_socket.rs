@@ -0,0 +1,48 @@
+use async_zmq::{AnySocket, AnySocketError, Operation, Result, SocketBuilder, SocketType};
+
+#[async_std::test]
+async fn any_socket_sends_and_receives() -> Result<()> {
+    let uri = "inproc://any_socket";
+    let mut push: AnySocket = SocketBuilder::new(SocketType::PUSH, uri).bind()?;
+    let mut pull: AnySocket = SocketBuilder::new(SocketType::PULL, uri).connect()?;
+    assert!(matches!(push, AnySocket::Push(_)));
+    assert_eq!(pull.socket_type(), SocketType::PULL);
+
+    push.send(vec!["hello", "world"]).await?;
+    let msg = pull.recv().await?;
+    assert_eq!(msg[0].as_str(), Some("hello"));
+    assert_eq!(msg[1].as_str(), Some("world"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn any_socket_rejects_unsupported_operations() -> Result<()> {
+    let uri = "inproc://any_socket_unsupported";
+    let mut push: AnySocket = SocketBuilder::new(SocketType::PUSH, uri).bind()?;
+    let mut pull: AnySocket = SocketBuilder::new(SocketType::PULL, uri).connect()?;
+
+    let err = push.recv().await.unwrap_err();
+    assert!(matches!(
+        err,
+        AnySocketError::Unsupported {
+            socket_type: SocketType::PUSH,
+            operation: Operation::Recv,
+        }
+    ));
+    assert_eq!(
+        async_zmq::zmq::Error::from(err),
+        async_zmq::zmq::Error::ENOTSUP
+    );
+
+    let err = pull.send(vec!["hello"]).await.unwrap_err();
+    assert!(matches!(
+        err,
+        AnySocketError::Unsupported {
+            operation: Operation::Send,
+            ..
+        }
+    ));
+
+    Ok(())
+}
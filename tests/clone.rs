@@ -0,0 +1,84 @@
+use async_zmq::{
+    clone::{CloneClient, CloneServer},
+    dealer, publish, pull, push, router, subscribe, CloneError, Result, SinkExt, StreamExt,
+};
+
+async fn client(name: &str) -> Result<CloneClient> {
+    Ok(CloneClient::connect(
+        dealer(&format!("inproc://{}_snapshot", name))?.connect()?,
+        subscribe(&format!("inproc://{}_updates", name))?.connect()?,
+        push(&format!("inproc://{}_collector", name))?.connect()?,
+    )
+    .await?)
+}
+
+#[async_std::test]
+async fn clone_snapshot_and_updates() -> Result<()> {
+    let server = CloneServer::new(
+        router("inproc://clone_snapshot")?.bind()?,
+        publish("inproc://clone_updates")?.bind()?,
+        pull("inproc://clone_collector")?.bind()?,
+    );
+    async_std::task::spawn(server.run());
+
+    let mut first = client("clone").await?;
+    assert_eq!(first.sequence(), 0);
+    first.set("config/timeout", "30").await?;
+    first.set("config/retries", "3").await?;
+    let update = first.recv_update().await?;
+    assert_eq!(
+        (update.key.as_str(), update.sequence),
+        ("config/timeout", 1)
+    );
+    first.recv_update().await?;
+
+    // A client joining later starts out with the snapshot
+    let mut second = client("clone").await?;
+    assert_eq!(second.sequence(), 2);
+    assert_eq!(second.get("config/timeout"), Some(&b"30"[..]));
+    assert_eq!(second.iter().count(), 2);
+
+    second.set("config/timeout", "").await?;
+    for client in [&mut first, &mut second] {
+        let update = client.recv_update().await?;
+        assert_eq!(update.sequence, 3);
+        assert_eq!(client.get("config/timeout"), None);
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn clone_detects_gaps() -> Result<()> {
+    let mut snapshot = router("inproc://gap_snapshot")?.bind()?;
+    let mut updates = publish("inproc://gap_updates")?.bind()?;
+    let _collector = pull("inproc://gap_collector")?.bind()?;
+
+    let server = async_std::task::spawn(async move {
+        let request = snapshot.next().await.unwrap().unwrap();
+        let identity = request.into_iter().next().unwrap();
+        snapshot
+            .send(vec![
+                &identity[..],
+                b"KTHXBAI",
+                &0u64.to_be_bytes(),
+                b"config/",
+            ])
+            .await
+            .unwrap();
+        // Skip the update with sequence number 1
+        updates
+            .send(vec![&b"config/timeout"[..], &2u64.to_be_bytes(), b"30"])
+            .await
+            .unwrap();
+    });
+
+    let mut client = client("gap").await?;
+    server.await;
+    match client.recv_update().await {
+        Err(CloneError::Gap { expected, received }) => assert_eq!((expected, received), (1, 2)),
+        other => panic!("expected a gap, got {:?}", other),
+    }
+
+    Ok(())
+}
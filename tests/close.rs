@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+use async_zmq::{
+    pull, push, CloseErrorKind, ContextHandle, Error, ErrorKind, Operation, Result, SinkExt,
+    SocketType, StreamExt, ZmqSocketExt,
+};
+
+#[async_std::test]
+async fn close_flushes_pending_message() -> Result<()> {
+    let mut push = push("tcp://127.0.0.1:*")?.bind()?;
+    let endpoint = push.last_endpoint()?.expect("no endpoint");
+    let mut pull = pull(&endpoint)?.connect()?;
+
+    push.feed(vec!["Hello", "World"]).await?;
+    let discarded = push.close(Duration::from_secs(5)).await?;
+    assert_eq!(discarded, 0);
+
+    let recv = pull.next().await.unwrap()?;
+    assert_eq!(recv[1].as_str(), Some("World"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn close_discards_after_linger() -> Result<()> {
+    // Nobody ever connects, so the push socket never becomes writable.
+    let mut push = push("tcp://127.0.0.1:*")?.bind()?;
+
+    push.feed(vec!["Hello"]).await?;
+    let start = Instant::now();
+    let discarded = push.close(Duration::from_millis(200)).await?;
+    assert_eq!(discarded, 1);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn close_errors_name_the_operation() -> Result<()> {
+    let handle = ContextHandle::builder()
+        .shutdown_timeout(Duration::from_millis(50))
+        .build()?;
+    // Nobody ever connects, so the message is still queued when the context goes away.
+    let mut push = push("tcp://127.0.0.1:*")?.with_handle(&handle).bind()?;
+    push.feed(vec!["Hello"]).await?;
+    handle.shutdown().await;
+
+    let err = Error::from(
+        push.close(Duration::from_secs(5))
+            .await
+            .expect_err("flushed into a terminated context"),
+    );
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::Close(CloseErrorKind::ContextTerminated)
+    ));
+    assert_eq!(err.socket_type(), Some(SocketType::PUSH));
+    assert_eq!(err.operation(), Some(Operation::Close));
+    assert_eq!(
+        err.to_string(),
+        "closing PUSH socket at tcp://127.0.0.1:*: the context specified was terminated"
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn receivers_close_without_a_count() -> Result<()> {
+    let pull = pull("inproc://close_receiver")?.bind()?;
+    let () = pull.close(Duration::from_millis(100)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use async_std::future::timeout;
+use async_std::task::spawn;
+use async_zmq::{
+    pull, push, ContextHandle, ErrorKind, RecvError, Result, SinkExt, SocketError, StreamExt,
+};
+
+#[async_std::test]
+async fn default_context_is_shared() -> Result<()> {
+    let uri = "inproc://default_context";
+    let mut push = push(uri)?.bind()?;
+    let mut pull = pull(uri)?.connect()?;
+
+    push.send(vec!["Hello"]).await?;
+    let recv = pull.next().await.unwrap()?;
+    assert_eq!(recv[0].as_str(), Some("Hello"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn handle_limits_sockets() -> Result<()> {
+    let handle = ContextHandle::builder().max_sockets(1).build()?;
+    let first = pull("inproc://limit_first")?.with_handle(&handle).bind()?;
+    assert_eq!(handle.sockets(), 1);
+
+    let second = pull("inproc://limit_second")?.with_handle(&handle).bind();
+    assert!(matches!(
+        second.map_err(|e| e.kind()),
+        Err(ErrorKind::Socket(SocketError::SocketLimitReached))
+    ));
+
+    drop(first);
+    assert_eq!(handle.sockets(), 0);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn shutdown_interrupts_sockets() -> Result<()> {
+    let handle = ContextHandle::new();
+    let mut pull = pull("inproc://shutdown")?.with_handle(&handle).bind()?;
+
+    let receiver = spawn(async move {
+        while let Some(msg) = pull.next().await {
+            msg?;
+        }
+        Ok::<_, RecvError>(())
+    });
+
+    timeout(Duration::from_secs(5), handle.shutdown())
+        .await
+        .expect("shutdown never completed");
+    receiver.await?;
+
+    assert!(handle.is_shutdown());
+    assert_eq!(handle.sockets(), 0);
+    assert!(handle.context().is_none());
+    assert!(matches!(
+        async_zmq::pull("inproc://after")?
+            .with_handle(&handle)
+            .bind()
+            .map_err(|e| e.kind()),
+        Err(ErrorKind::Socket(SocketError::ContextTerminated))
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn shutdown_gives_up_on_sockets_never_dropped() -> Result<()> {
+    let handle = ContextHandle::builder()
+        .shutdown_timeout(Duration::from_millis(100))
+        .build()?;
+    let mut pull = pull("inproc://never_dropped")?
+        .with_handle(&handle)
+        .bind()?;
+
+    timeout(Duration::from_secs(5), handle.shutdown())
+        .await
+        .expect("shutdown waited for a socket that is never dropped");
+    assert!(handle.context().is_none());
+    assert_eq!(handle.sockets(), 1);
+
+    // The socket stays interrupted until it is dropped.
+    assert!(pull.next().await.is_none());
+    drop(pull);
+    assert_eq!(handle.sockets(), 0);
+
+    Ok(())
+}
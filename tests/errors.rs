@@ -0,0 +1,110 @@
+use futures::future::join;
+
+use async_zmq::{
+    dealer, pull, router, ContextHandle, Error, ErrorKind, Operation, RecvErrorKind, Result,
+    SendErrorKind, SinkExt, SocketError, SocketType, ZmqSocketExt,
+};
+
+#[async_std::test]
+async fn bind_errors_name_endpoint_and_socket() -> Result<()> {
+    let first = pull("tcp://127.0.0.1:*")?.bind()?;
+    let endpoint = first.last_endpoint()?.expect("no endpoint");
+    let err = match pull(&endpoint)?.bind() {
+        Ok(_) => panic!("the address was bound twice"),
+        Err(err) => err,
+    };
+
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::Socket(SocketError::AddressInUse)
+    ));
+    assert_eq!(err.endpoint(), Some(endpoint.as_str()));
+    assert_eq!(err.socket_type(), Some(SocketType::PULL));
+    assert_eq!(err.operation(), Some(Operation::Bind));
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "binding PULL socket to {}: the requested address is already in use",
+            endpoint
+        )
+    );
+    assert_eq!(
+        async_zmq::zmq::Error::from(err),
+        async_zmq::zmq::Error::EADDRINUSE
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn malformed_endpoint_is_an_invalid_argument() -> Result<()> {
+    let err = match pull("tcp://nohost")?.bind() {
+        Ok(_) => panic!("bound to an endpoint without a port"),
+        Err(err) => err,
+    };
+
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::Socket(SocketError::InvalidArgument)
+    ));
+    assert_eq!(err.endpoint(), Some("tcp://nohost"));
+    assert_eq!(err.operation(), Some(Operation::Bind));
+    assert_eq!(
+        err.to_string(),
+        "binding PULL socket to tcp://nohost: an invalid argument was given"
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn send_errors_name_endpoint_and_socket() -> Result<()> {
+    let mut router = router("inproc://errors_send")?.bind()?;
+    router.as_raw_socket().set_router_mandatory(true)?;
+    // A mandatory ROUTER only reports being writable once it has a peer.
+    let _dealer = dealer("inproc://errors_send")?.connect()?;
+
+    let err = Error::from(
+        router
+            .send(vec!["nobody", "Hello"])
+            .await
+            .expect_err("the message was routed to an unknown peer"),
+    );
+
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::Send(SendErrorKind::HostUnreachable)
+    ));
+    assert_eq!(err.endpoint(), Some("inproc://errors_send"));
+    assert_eq!(err.socket_type(), Some(SocketType::ROUTER));
+    assert_eq!(err.operation(), Some(Operation::Send));
+    assert_eq!(
+        err.to_string(),
+        "sending on ROUTER socket at inproc://errors_send: the message cannot be routed"
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn recv_errors_name_endpoint_and_socket() -> Result<()> {
+    let handle = ContextHandle::new();
+    let pull = pull("inproc://errors_recv")?.with_handle(&handle).bind()?;
+
+    let recv = async move {
+        let mut pull = pull;
+        pull.recv_into(&mut Vec::new()).await
+    };
+    let (_, received) = join(handle.shutdown(), recv).await;
+    let err = Error::from(received.expect_err("received after the shutdown"));
+
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::Recv(RecvErrorKind::ContextTerminated)
+    ));
+    assert_eq!(err.endpoint(), Some("inproc://errors_recv"));
+    assert_eq!(err.socket_type(), Some(SocketType::PULL));
+    assert_eq!(err.operation(), Some(Operation::Recv));
+
+    Ok(())
+}
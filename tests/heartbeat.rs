@@ -0,0 +1,98 @@
+use std::time::{Duration, Instant};
+
+use async_std::future::timeout;
+use async_std::net::TcpStream;
+use async_zmq::{dealer, router, PeerStatus, Result, StreamExt, ZmqSocketExt};
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+#[async_std::test]
+async fn peer_liveness_reports_connect_and_disconnect() -> Result<()> {
+    let router = router("tcp://127.0.0.1:*")?
+        .heartbeat_ivl(Duration::from_millis(100))
+        .heartbeat_timeout(Duration::from_millis(300))
+        .bind()?;
+    let mut liveness = router.peer_liveness()?;
+    let endpoint = router.last_endpoint()?.expect("no endpoint");
+
+    let dealer = dealer(&endpoint)?
+        .heartbeat_ivl(Duration::from_millis(100))
+        .heartbeat_ttl(Duration::from_millis(300))
+        .connect()?;
+
+    let status = timeout(Duration::from_secs(5), liveness.next())
+        .await
+        .expect("peer never came alive")
+        .unwrap()?;
+    assert!(matches!(status, PeerStatus::Alive(_)));
+
+    drop(dealer);
+
+    let status = timeout(Duration::from_secs(5), liveness.next())
+        .await
+        .expect("peer never died")
+        .unwrap()?;
+    assert!(matches!(status, PeerStatus::Dead(_)));
+
+    Ok(())
+}
+
+/// Complete the ZMTP 3.1 handshake of a DEALER with the NULL mechanism on `stream`.
+async fn handshake(stream: &mut TcpStream) {
+    let mut greeting = [0; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10] = 3;
+    greeting[11] = 1;
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting).await.unwrap();
+
+    let mut ready = vec![0x04, 0, 5];
+    ready.extend_from_slice(b"READY");
+    ready.push(11);
+    ready.extend_from_slice(b"Socket-Type");
+    ready.extend_from_slice(&6u32.to_be_bytes());
+    ready.extend_from_slice(b"DEALER");
+    ready[1] = (ready.len() - 2) as u8;
+    stream.write_all(&ready).await.unwrap();
+
+    // The greeting and the READY command of the peer.
+    stream.read_exact(&mut greeting).await.unwrap();
+    let mut header = [0; 2];
+    stream.read_exact(&mut header).await.unwrap();
+    let mut command = vec![0; header[1] as usize];
+    stream.read_exact(&mut command).await.unwrap();
+    assert!(command.starts_with(b"\x05READY"));
+}
+
+#[async_std::test]
+async fn peer_liveness_reports_silent_peer_dead() -> Result<()> {
+    let router = router("tcp://127.0.0.1:*")?
+        .heartbeat_ivl(Duration::from_millis(100))
+        .heartbeat_timeout(Duration::from_millis(300))
+        .bind()?;
+    let mut liveness = router.peer_liveness()?;
+    let endpoint = router.last_endpoint()?.expect("no endpoint");
+
+    // The peer answers no heartbeat but keeps the connection open.
+    let mut peer = TcpStream::connect(endpoint.trim_start_matches("tcp://"))
+        .await
+        .unwrap();
+    handshake(&mut peer).await;
+
+    let status = timeout(Duration::from_secs(5), liveness.next())
+        .await
+        .expect("peer never came alive")
+        .unwrap()?;
+    assert!(matches!(status, PeerStatus::Alive(_)));
+    let alive = Instant::now();
+
+    let status = timeout(Duration::from_secs(5), liveness.next())
+        .await
+        .expect("silent peer never died")
+        .unwrap()?;
+    assert!(matches!(status, PeerStatus::Dead(_)));
+    assert!(alive.elapsed() < Duration::from_millis(1500));
+    drop(peer);
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+
+use async_zmq::{
+    lvc::LastValueCache, publish, subscribe, xpublish, xsubscribe, Result, SinkExt, StreamExt,
+};
+use futures::future::{select, Either};
+use futures_timer::Delay;
+
+#[async_std::test]
+async fn late_subscriber_receives_last_value() -> Result<()> {
+    let mut publisher = publish("inproc://lvc_upstream")?.bind()?;
+    let frontend = xsubscribe("inproc://lvc_upstream")?.connect()?;
+    let backend = xpublish("inproc://lvc_downstream")?.bind()?;
+    let cache = LastValueCache::new(frontend, backend)?.max_topics(16);
+    async_std::task::spawn(cache.run());
+
+    let mut early = subscribe("inproc://lvc_downstream")?.connect()?;
+    early.set_subscribe("status")?;
+
+    // Publish until the subscription made its way through the cache to the publisher
+    let start = Instant::now();
+    let mut value = 0u32;
+    loop {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "the subscription never reached the publisher"
+        );
+        value += 1;
+        publisher
+            .send(vec![&b"status"[..], &value.to_be_bytes()[..]])
+            .await?;
+        match select(early.next(), Delay::new(Duration::from_millis(10))).await {
+            Either::Left((msg, _)) => {
+                assert_eq!(msg.unwrap()?[1][..], value.to_be_bytes());
+                break;
+            }
+            Either::Right(_) => {}
+        }
+    }
+
+    // Joining mid-stream replays the current value without waiting for the next update
+    let mut late = subscribe("inproc://lvc_downstream")?.connect()?;
+    late.set_subscribe("status")?;
+    let msg = async_std::future::timeout(Duration::from_secs(5), late.next())
+        .await
+        .expect("the last value was never replayed")
+        .unwrap()?;
+    assert_eq!(msg[0].as_str(), Some("status"));
+    assert_eq!(msg[1][..], value.to_be_bytes());
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "metrics")]
+
+use async_zmq::{pull, push, Result, SinkExt, StreamExt};
+use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+
+/// The counters of a snapshot, as name, `socket_type` label, `endpoint` label and value.
+///
+/// Taking a snapshot resets the counters, so a test takes a single one.
+fn counters(snapshotter: &Snapshotter) -> Vec<(String, String, String, u64)> {
+    snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .filter_map(|(key, _, _, value)| {
+            let key = key.key();
+            let label = |name: &str| {
+                key.labels()
+                    .find(|label| label.key() == name)
+                    .map(|label| label.value().to_owned())
+            };
+            match value {
+                DebugValue::Counter(value) => Some((
+                    key.name().to_owned(),
+                    label("socket_type")?,
+                    label("endpoint")?,
+                    value,
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[async_std::test]
+async fn counters_are_labelled_with_socket_type_and_endpoint() -> Result<()> {
+    let uri = "inproc://metrics";
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    // The handles of the counters are registered when the sockets are bound or connected.
+    let (mut push, mut pull) = metrics::with_local_recorder(&recorder, || -> Result<_> {
+        let pull = pull(uri)?.bind()?;
+        let push = push(uri)?.connect()?;
+        Ok((push, pull))
+    })?;
+
+    push.send(vec!["Hello", "World!"]).await?;
+    pull.next().await.unwrap()?;
+
+    let counters = counters(&snapshotter);
+    let counter = |name: &str, socket_type: &str| {
+        counters
+            .iter()
+            .find(|counter| counter.0 == name && counter.1 == socket_type && counter.2 == uri)
+            .map(|counter| counter.3)
+    };
+
+    let sent = |name| counter(name, "PUSH");
+    assert_eq!(sent("async_zmq_messages_sent"), Some(1));
+    assert_eq!(sent("async_zmq_frames_sent"), Some(2));
+    assert_eq!(sent("async_zmq_bytes_sent"), Some(11));
+    assert_eq!(sent("async_zmq_messages_received"), Some(0));
+
+    let received = |name| counter(name, "PULL");
+    assert_eq!(received("async_zmq_messages_received"), Some(1));
+    assert_eq!(received("async_zmq_frames_received"), Some(2));
+    assert_eq!(received("async_zmq_bytes_received"), Some(11));
+    assert_eq!(received("async_zmq_messages_sent"), Some(0));
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use async_std::sync::{Arc, Mutex};
-use async_std::task::spawn;
+use async_std::task::{spawn, yield_now};
 
 use async_zmq::{publish, subscribe, Multipart, Result, SinkExt, StreamExt};
 
@@ -17,7 +17,10 @@ async fn publish_subscribe_message() -> Result<()> {
 
     let send_handle = spawn(async move {
         while *running.lock().await {
-            let _ = publish.send(message.clone().into()).await;
+            let _ = publish.send(message.clone()).await;
+            // A PUB socket never blocks, so give the receiver a turn on
+            // single-threaded executors.
+            yield_now().await;
         }
     });
 
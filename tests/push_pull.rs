@@ -15,7 +15,7 @@ async fn push_pull_message() -> Result<()> {
 
     let send_handle = spawn(async move {
         while *running.lock().await {
-            let _ = push.send(message.clone().into()).await;
+            let _ = push.send(message.clone()).await;
         }
     });
 
@@ -30,9 +30,13 @@ async fn push_pull_message() -> Result<()> {
                 break;
             }
         }
+        // Keep the peer connected until the sender has stopped, otherwise the
+        // push socket has nowhere to route its last message and blocks forever.
+        pull
     });
 
+    let pull = receive_handle.await;
     send_handle.await;
-    receive_handle.await;
+    drop(pull);
     Ok(())
 }
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use async_std::future::timeout;
+use async_std::task::spawn;
+use async_zmq::{
+    pull, push, BuildSocket, ErrorKind, Pull, Reactor, Result, SinkExt, SocketError, StreamExt,
+};
+
+#[async_std::test]
+async fn sockets_run_on_own_reactor() -> Result<()> {
+    let reactor = Reactor::new().expect("cannot start reactor");
+    let uri = "inproc://own_reactor";
+    let mut push = push(uri)?.with_reactor(&reactor).bind()?;
+    let mut pull = pull(uri)?.with_reactor(&reactor).connect()?;
+
+    push.send(vec!["Hello"]).await?;
+    let recv = pull.next().await.unwrap()?;
+    assert_eq!(recv[0].as_str(), Some("Hello"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn shutdown_interrupts_registered_sockets() -> Result<()> {
+    let reactor = Reactor::new().expect("cannot start reactor");
+    let mut pull = pull("inproc://reactor_shutdown")?
+        .with_reactor(&reactor)
+        .bind()?;
+
+    let receiver = spawn(async move { pull.next().await });
+    async_std::task::sleep(Duration::from_millis(100)).await;
+
+    reactor.shutdown();
+    assert!(reactor.is_shutdown());
+    let recv = timeout(Duration::from_secs(5), receiver)
+        .await
+        .expect("receiver was never woken up");
+    assert!(recv.is_none());
+
+    let after = async_zmq::pull("inproc://reactor_after")?
+        .with_reactor(&reactor)
+        .bind();
+    assert!(matches!(
+        after.map_err(|e| e.kind()),
+        Err(ErrorKind::Socket(SocketError::ReactorUnavailable(_)))
+    ));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn raw_sockets_register_fallibly() -> Result<()> {
+    let context = async_zmq::Context::new();
+    let mut pull = Pull::from_raw(context.socket(async_zmq::zmq::PULL)?)?;
+    pull.as_raw_socket().bind("inproc://raw_socket")?;
+    let push = context.socket(async_zmq::zmq::PUSH)?;
+    push.connect("inproc://raw_socket")?;
+    push.send("Hello", 0)?;
+    let recv = pull.next().await.unwrap()?;
+    assert_eq!(recv[0].as_str(), Some("Hello"));
+
+    let reactor = Reactor::new().expect("cannot start reactor");
+    reactor.shutdown();
+    let raw = context.socket(async_zmq::zmq::PULL)?;
+    assert!(matches!(
+        Pull::from_raw_with_reactor(raw, &reactor),
+        Err(SocketError::ReactorUnavailable(_))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Kept apart from the other reactor tests, which start polling threads of their own.
+#![cfg(target_os = "linux")]
+
+use std::time::{Duration, Instant};
+
+use async_zmq::{pull, Reactor, Result};
+
+/// The number of reactor polling threads of this process, by their name as the kernel keeps it.
+fn reactor_threads() -> usize {
+    std::fs::read_dir("/proc/self/task")
+        .unwrap()
+        .filter_map(|task| std::fs::read_to_string(task.ok()?.path().join("comm")).ok())
+        .filter(|name| name.starts_with("async-zmq/react"))
+        .count()
+}
+
+/// Wait until `expected` reactor polling threads run, which name themselves once started.
+fn wait_for_reactor_threads(expected: usize) {
+    let start = Instant::now();
+    while reactor_threads() != expected {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "expected {} reactor threads, found {}",
+            expected,
+            reactor_threads()
+        );
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn dropping_last_handle_stops_reactor() -> Result<()> {
+    let reactor = Reactor::new().expect("cannot start reactor");
+    let pull = pull("inproc://dropped_reactor")?
+        .with_reactor(&reactor)
+        .bind()?;
+    wait_for_reactor_threads(1);
+
+    // The socket keeps the reactor running after the handle is gone.
+    drop(reactor);
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(reactor_threads(), 1);
+
+    drop(pull);
+    wait_for_reactor_threads(0);
+
+    Ok(())
+}
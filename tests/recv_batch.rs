@@ -0,0 +1,25 @@
+use async_zmq::{pull, push, Result, SinkExt, StreamExt};
+
+#[async_std::test]
+async fn recv_batch_drains_ready_messages() -> Result<()> {
+    let uri = "inproc://recv_batch";
+    let mut push = push(uri)?.bind()?;
+    let mut pull = pull(uri)?.connect()?;
+
+    for i in 0..10u8 {
+        push.send(vec![vec![i]]).await?;
+    }
+
+    let mut received = Vec::new();
+    let mut batches = pull.recv_batch(4);
+    while received.len() < 10 {
+        let batch = batches.next().await.unwrap()?;
+        assert!(!batch.is_empty() && batch.len() <= 4);
+        received.extend(batch);
+    }
+
+    let received: Vec<u8> = received.iter().map(|msg| msg[0][0]).collect();
+    assert_eq!(received, (0..10).collect::<Vec<_>>());
+
+    Ok(())
+}
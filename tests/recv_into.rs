@@ -0,0 +1,48 @@
+use async_zmq::{pull, push, MultipartPool, Result, SinkExt};
+
+#[async_std::test]
+async fn recv_into_reuses_pooled_buffers() -> Result<()> {
+    let uri = "inproc://recv_into";
+    let mut push = push(uri)?.bind()?;
+    let mut pull = pull(uri)?.connect()?;
+    let pool = MultipartPool::new(1);
+
+    push.send(vec!["a", "b", "c"]).await?;
+    let mut msg = pool.get();
+    pull.recv_into(&mut msg).await?;
+    assert_eq!(msg.len(), 3);
+    assert_eq!(msg[2].as_str(), Some("c"));
+
+    push.send(vec!["d"]).await?;
+    pull.recv_into(&mut msg).await?;
+    assert_eq!(msg.len(), 1);
+    assert_eq!(msg[0].as_str(), Some("d"));
+
+    drop(msg);
+    assert_eq!(pool.idle(), 1);
+    let msg = pool.get();
+    assert!(msg.is_empty());
+    assert!(msg.capacity() >= 3);
+    assert_eq!(pool.idle(), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "bytes")]
+#[async_std::test]
+async fn frames_convert_to_bytes() -> Result<()> {
+    use async_zmq::buf::MultipartBuf;
+
+    let uri = "inproc://recv_into_bytes";
+    let mut push = push(uri)?.bind()?;
+    let mut pull = pull(uri)?.connect()?;
+
+    push.send(vec!["Hello", "World"]).await?;
+    let mut msg = MultipartBuf::new();
+    pull.recv_into(&mut msg).await?;
+    let frames: Vec<_> = msg.drain_bytes().collect();
+    assert_eq!(frames, vec!["Hello".as_bytes(), "World".as_bytes()]);
+    assert!(msg.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_std::task::{sleep, spawn};
+use async_zmq::{pull, ErrorKind, Result, RetryPolicy, SocketError, ZmqSocketExt};
+
+#[async_std::test]
+async fn bind_retries_until_address_is_free() -> Result<()> {
+    let first = pull("tcp://127.0.0.1:*")?.bind()?;
+    let endpoint = first.last_endpoint()?.expect("no endpoint");
+    spawn(async move {
+        sleep(Duration::from_millis(200)).await;
+        drop(first);
+    });
+
+    let attempts = Arc::new(Mutex::new(Vec::new()));
+    let log = attempts.clone();
+    let policy = RetryPolicy::new()
+        .initial_delay(Duration::from_millis(20))
+        .max_delay(Duration::from_millis(50))
+        .max_attempts(100)
+        .on_attempt(move |attempt| {
+            log.lock()
+                .unwrap()
+                .push((attempt.number, attempt.error.map(|e| e.kind())));
+        });
+    pull(&endpoint)?.bind_with_retry(policy).await?;
+
+    let attempts = attempts.lock().unwrap();
+    assert!(attempts.len() > 1);
+    let (last, failures) = attempts.split_last().unwrap();
+    assert!(last.1.is_none());
+    assert!(failures
+        .iter()
+        .all(|(_, kind)| matches!(kind, Some(ErrorKind::Socket(SocketError::AddressInUse)))));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn bind_gives_up() -> Result<()> {
+    let first = pull("tcp://127.0.0.1:*")?.bind()?;
+    let endpoint = first.last_endpoint()?.expect("no endpoint");
+
+    let attempts = Arc::new(Mutex::new(0));
+    let count = attempts.clone();
+    let policy = RetryPolicy::new()
+        .initial_delay(Duration::from_millis(1))
+        .max_attempts(3)
+        .on_attempt(move |_| *count.lock().unwrap() += 1);
+    let err = pull(&endpoint)?
+        .bind_with_retry(policy)
+        .await
+        .err()
+        .expect("the address was bound twice");
+    assert!(matches!(
+        err.kind(),
+        ErrorKind::Socket(SocketError::AddressInUse)
+    ));
+    assert_eq!(*attempts.lock().unwrap(), 3);
+
+    let policy = RetryPolicy::new().retry_if(|_| false);
+    let err = pull(&endpoint)?
+        .bind_with_retry(policy)
+        .await
+        .err()
+        .expect("the address was bound twice");
+    assert_eq!(err.endpoint(), Some(endpoint.as_str()));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn huge_multiplier_saturates_at_max_delay() -> Result<()> {
+    let first = pull("tcp://127.0.0.1:*")?.bind()?;
+    let endpoint = first.last_endpoint()?.expect("no endpoint");
+
+    let delays = Arc::new(Mutex::new(Vec::new()));
+    let log = delays.clone();
+    let policy = RetryPolicy::new()
+        .initial_delay(Duration::from_millis(1))
+        .max_delay(Duration::from_millis(5))
+        .multiplier(f64::MAX)
+        .jitter(0.0)
+        .max_attempts(4)
+        .on_attempt(move |attempt| log.lock().unwrap().push(attempt.retry_in));
+    assert!(pull(&endpoint)?.bind_with_retry(policy).await.is_err());
+
+    assert_eq!(
+        *delays.lock().unwrap(),
+        [
+            Some(Duration::from_millis(1)),
+            Some(Duration::from_millis(5)),
+            Some(Duration::from_millis(5)),
+            None
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "retry multiplier must be finite")]
+fn infinite_multiplier_is_rejected() {
+    RetryPolicy::new().multiplier(f64::INFINITY);
+}
+
+#[test]
+#[should_panic(expected = "retry jitter must be finite")]
+fn nan_jitter_is_rejected() {
+    RetryPolicy::new().jitter(f64::NAN);
+}
@@ -0,0 +1,176 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_std::task::{sleep, spawn};
+use async_zmq::{
+    dealer, router,
+    rpc::{RpcClient, RpcServer},
+    Dealer, Multipart, Result, Router, RpcError, SinkExt, StreamExt,
+};
+
+/// Reply with the request after sleeping for as many milliseconds as its first frame says.
+async fn delayed_echo(request: Multipart) -> Multipart {
+    let millis = std::str::from_utf8(&request[0]).unwrap().parse().unwrap();
+    sleep(Duration::from_millis(millis)).await;
+    request
+}
+
+#[async_std::test]
+async fn rpc_calls_complete_out_of_order() -> Result<()> {
+    let server = RpcServer::new(router("inproc://rpc_concurrent")?.bind()?, delayed_echo);
+    spawn(server.run());
+    let (client, driver) = RpcClient::new(dealer("inproc://rpc_concurrent")?.connect()?);
+    spawn(driver.run());
+
+    let (slow, fast) = futures::join!(client.call(vec!["200"]), async {
+        let reply = client.call(vec!["10"]).await;
+        (reply, std::time::Instant::now())
+    });
+    let slow_done = std::time::Instant::now();
+    let (fast, fast_done) = fast;
+
+    assert_eq!(slow?, vec!["200".into()]);
+    assert_eq!(fast?, vec!["10".into()]);
+    assert!(fast_done < slow_done);
+    Ok(())
+}
+
+#[async_std::test]
+async fn rpc_calls_time_out() -> Result<()> {
+    let server = RpcServer::new(router("inproc://rpc_timeout")?.bind()?, delayed_echo);
+    spawn(server.run());
+    let (client, driver) = RpcClient::new(dealer("inproc://rpc_timeout")?.connect()?);
+    spawn(driver.run());
+
+    let client = client.timeout(Duration::from_millis(50));
+    assert!(matches!(
+        client.call(vec!["500"]).await,
+        Err(RpcError::TimedOut)
+    ));
+    // The late reply is discarded and does not get in the way of the next call.
+    assert_eq!(client.call(vec!["0"]).await?, vec!["0".into()]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn rpc_server_bounds_concurrency() -> Result<()> {
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let (running, peak) = (running.clone(), peak.clone());
+        move |request: Multipart| {
+            let (running, peak) = (running.clone(), peak.clone());
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                sleep(Duration::from_millis(20)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                request
+            }
+        }
+    };
+    let server =
+        RpcServer::new(router("inproc://rpc_bounded")?.bind()?, handler).max_concurrency(2);
+    spawn(server.run());
+    let (client, driver) = RpcClient::new(dealer("inproc://rpc_bounded")?.connect()?);
+    spawn(driver.run());
+
+    let calls = (0..6).map(|i| client.call(vec![i.to_string().into_bytes()]));
+    for reply in futures::future::join_all(calls).await {
+        reply?;
+    }
+    assert_eq!(peak.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
+#[async_std::test]
+async fn rpc_calls_fail_without_driver() -> Result<()> {
+    let (client, driver) = RpcClient::new(dealer("inproc://rpc_closed")?.connect()?);
+    drop(driver);
+    assert!(matches!(
+        client.call(vec!["hello"]).await,
+        Err(RpcError::Closed)
+    ));
+    Ok(())
+}
+
+#[async_std::test]
+async fn rpc_calls_fail_once_driver_future_is_dropped() -> Result<()> {
+    // Nobody answers, so the call waits until the driver goes away.
+    let _router = router("inproc://rpc_dropped_driver")?.bind()?;
+    let (client, driver) = RpcClient::new(dealer("inproc://rpc_dropped_driver")?.connect()?);
+    let running = spawn(driver.run());
+
+    let call = spawn(async move { client.call(vec!["hello"]).await });
+    sleep(Duration::from_millis(100)).await;
+    running.cancel().await;
+
+    let reply = async_std::future::timeout(Duration::from_secs(5), call)
+        .await
+        .expect("the call outlived its driver");
+    assert!(matches!(reply, Err(RpcError::Closed)));
+    Ok(())
+}
+
+#[async_std::test]
+async fn rpc_server_outlives_vanished_clients() -> Result<()> {
+    let router = router("inproc://rpc_vanished")?.bind()?;
+    // Replies to clients that are gone fail instead of being dropped silently.
+    router.as_raw_socket().set_router_mandatory(true)?;
+    spawn(RpcServer::new(router, delayed_echo).run());
+
+    let mut vanished = dealer("inproc://rpc_vanished")?.connect()?;
+    vanished
+        .send(vec![&b""[..], &0u64.to_be_bytes()[..], &b"100"[..]])
+        .await?;
+    drop(vanished);
+
+    let (client, driver) = RpcClient::new(dealer("inproc://rpc_vanished")?.connect()?);
+    spawn(driver.run());
+    // The reply to the vanished client fails to be sent while this call is being handled.
+    assert_eq!(client.call(vec!["200"]).await?, vec!["200".into()]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn rpc_driver_receives_while_sending() -> Result<()> {
+    let context = async_zmq::zmq::Context::new();
+    let socket = context.socket(async_zmq::zmq::ROUTER)?;
+    socket.set_sndhwm(1)?;
+    socket.set_rcvhwm(1)?;
+    // Replies wait for room instead of being dropped.
+    socket.set_router_mandatory(true)?;
+    socket.bind("inproc://rpc_full")?;
+    let mut peer = Router::from(socket);
+    let socket = context.socket(async_zmq::zmq::DEALER)?;
+    socket.set_sndhwm(1)?;
+    socket.set_rcvhwm(1)?;
+    socket.connect("inproc://rpc_full")?;
+    let (client, driver) = RpcClient::new(Dealer::from(socket));
+    spawn(driver.run());
+
+    let calls = futures::future::join_all((0..16).map(|_| client.call(vec!["request"])));
+    // The peer replies to every call before reading more than one request, so neither the
+    // requests nor the replies fit in the queues until the driver takes some replies while it
+    // waits to send.
+    let peer = async {
+        let request = peer.next().await.unwrap()?;
+        for id in 0..16u64 {
+            let id = id.to_be_bytes();
+            let reply = vec![&request[0][..], &b""[..], &id[..], &b"reply"[..]];
+            peer.send(reply).await?;
+        }
+        for _ in 1..16 {
+            peer.next().await.unwrap()?;
+        }
+        Ok::<_, async_zmq::Error>(peer)
+    };
+    let (replies, peer) = futures::join!(calls, peer);
+    let _peer = peer?;
+
+    for reply in replies {
+        assert_eq!(reply?, vec!["reply".into()]);
+    }
+    Ok(())
+}
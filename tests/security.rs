@@ -0,0 +1,64 @@
+use std::thread;
+
+use async_zmq::{pull, push, Context, Metadata, Result, SinkExt, StreamExt, ZmqSocketExt};
+
+/// Minimal ZAP handler accepting a single PLAIN user.
+fn zap_handler(context: &Context) {
+    let handler = context.socket(async_zmq::zmq::REP).unwrap();
+    handler.bind("inproc://zeromq.zap.01").unwrap();
+
+    thread::spawn(move || {
+        while let Ok(request) = handler.recv_multipart(0) {
+            // version, request id, domain, address, identity, mechanism, username, password
+            let accepted =
+                request[5] == b"PLAIN" && request[6] == b"admin" && request[7] == b"secret";
+            let (code, text, user) = if accepted {
+                ("200", "OK", "admin")
+            } else {
+                ("400", "Denied", "")
+            };
+            let reply: Vec<&[u8]> = vec![
+                b"1.0",
+                &request[1],
+                code.as_bytes(),
+                text.as_bytes(),
+                user.as_bytes(),
+                b"",
+            ];
+            if handler.send_multipart(reply, 0).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+#[async_std::test]
+async fn plain_mechanism_exposes_user_id() -> Result<()> {
+    let context = Context::new();
+    zap_handler(&context);
+
+    let mut pull = pull("tcp://127.0.0.1:*")?
+        .with_context(&context)
+        .plain_server()
+        .zap_domain("test")
+        .bind()?;
+    let endpoint = pull.last_endpoint()?.expect("no endpoint");
+    let mut push = push(&endpoint)?
+        .with_context(&context)
+        .plain_client("admin", "secret")
+        .connect()?;
+
+    assert_eq!(pull.mechanism()?, async_zmq::zmq::Mechanism::ZMQ_PLAIN);
+    assert_eq!(push.mechanism()?, async_zmq::zmq::Mechanism::ZMQ_PLAIN);
+
+    push.send(vec!["Hello"]).await?;
+    let mut recv = pull.next().await.unwrap()?;
+    assert_eq!(recv[0].as_str(), Some("Hello"));
+    assert_eq!(recv[0].user_id(), Some("admin"));
+    assert_eq!(
+        recv[0].peer_socket_type(),
+        Some(async_zmq::zmq::SocketType::PUSH)
+    );
+
+    Ok(())
+}
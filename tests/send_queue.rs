@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use async_std::future::timeout;
+use async_zmq::{pull, push, Result, SinkExt, StreamExt, ZmqSocketExt};
+
+#[async_std::test]
+async fn queue_accepts_messages_while_congested() -> Result<()> {
+    let mut push = push("tcp://127.0.0.1:*")?.send_queue(4).bind()?;
+    let endpoint = push.last_endpoint()?.expect("no endpoint");
+
+    // Without a peer PUSH cannot send, so everything stays queued.
+    for i in 0..4u8 {
+        timeout(Duration::from_millis(100), push.feed(vec![vec![i]]))
+            .await
+            .expect("queue was not full yet")?;
+    }
+    assert_eq!(push.queued(), 4);
+    assert!(
+        timeout(Duration::from_millis(100), push.feed(vec![vec![4]]))
+            .await
+            .is_err()
+    );
+
+    let mut pull = pull(&endpoint)?.connect()?;
+    timeout(Duration::from_secs(5), push.flush())
+        .await
+        .expect("queue was never flushed")?;
+    assert_eq!(push.queued(), 0);
+
+    for i in 0..4u8 {
+        let recv = pull.next().await.unwrap()?;
+        assert_eq!(recv[0][0], i);
+    }
+
+    Ok(())
+}
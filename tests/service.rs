@@ -0,0 +1,141 @@
+#![cfg(feature = "serde")]
+
+use std::time::Duration;
+
+use async_std::task::{sleep, spawn};
+use async_zmq::{
+    dealer, router,
+    rpc::RpcClient,
+    service::{CallContext, ServiceClient, ServiceServer},
+    Error, ErrorKind, Multipart, Result, ServiceError,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Divide {
+    dividend: i64,
+    divisor: i64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Quotient {
+    quotient: i64,
+    remainder: i64,
+}
+
+fn server(endpoint: &str) -> Result<ServiceServer> {
+    Ok(ServiceServer::new(router(endpoint)?.bind()?)
+        .method("divide", |request: Divide| async move {
+            if request.divisor == 0 {
+                return Err("division by zero");
+            }
+            Ok(Quotient {
+                quotient: request.dividend / request.divisor,
+                remainder: request.dividend % request.divisor,
+            })
+        })
+        .method("sleep", |millis: u64| async move {
+            sleep(Duration::from_millis(millis)).await;
+            Ok::<_, String>(millis)
+        })
+        .method_with_context("time_left", |(), call: CallContext| async move {
+            Ok::<_, String>(call.time_left().map(|left| left.as_millis() as u64))
+        }))
+}
+
+#[async_std::test]
+async fn service_routes_typed_calls() -> Result<()> {
+    spawn(server("inproc://service_routes")?.run());
+    let (client, driver) = ServiceClient::new(dealer("inproc://service_routes")?.connect()?);
+    spawn(driver.run());
+
+    let divide = client.method::<Divide, Quotient>("divide");
+    let reply = divide
+        .call(&Divide {
+            dividend: 7,
+            divisor: 2,
+        })
+        .await?;
+    assert_eq!(
+        reply,
+        Quotient {
+            quotient: 3,
+            remainder: 1
+        }
+    );
+    let slept: u64 = client.call("sleep", &0).await?;
+    assert_eq!(slept, 0);
+    Ok(())
+}
+
+#[async_std::test]
+async fn service_reports_standard_errors() -> Result<()> {
+    spawn(server("inproc://service_errors")?.run());
+    let (client, driver) = ServiceClient::new(dealer("inproc://service_errors")?.connect()?);
+    spawn(driver.run());
+
+    let unknown = client.call::<_, u64>("multiply", &(2, 3)).await;
+    assert!(matches!(unknown, Err(ServiceError::UnknownMethod(method)) if method == "multiply"));
+
+    let decode = client.call::<_, Quotient>("divide", "seven").await;
+    assert!(matches!(decode, Err(ServiceError::Decode(_))));
+
+    let handler = client
+        .call::<_, Quotient>(
+            "divide",
+            &Divide {
+                dividend: 1,
+                divisor: 0,
+            },
+        )
+        .await;
+    assert!(
+        matches!(&handler, Err(ServiceError::Handler(message)) if message == "division by zero")
+    );
+    let error = Error::from(handler.unwrap_err());
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::Service(ServiceError::Handler(message)) if message == "division by zero"
+    ));
+    assert_eq!(error.to_string(), "the handler failed: division by zero");
+    Ok(())
+}
+
+#[async_std::test]
+async fn service_propagates_deadlines() -> Result<()> {
+    spawn(
+        server("inproc://service_deadline")?
+            .max_concurrency(1)
+            .run(),
+    );
+    let (client, driver) = ServiceClient::new(dealer("inproc://service_deadline")?.connect()?);
+    let (rpc, rpc_driver) = RpcClient::new(dealer("inproc://service_deadline")?.connect()?);
+    let client = client.timeout(Duration::from_secs(5));
+
+    // The call waits for the driver, so less than the timeout is left once it goes out.
+    let late_driver = async {
+        sleep(Duration::from_millis(200)).await;
+        spawn(driver.run());
+    };
+    let (left, ()) = futures::join!(client.call::<_, Option<u64>>("time_left", &()), late_driver);
+    let left = left?.expect("no deadline");
+    assert!(left <= 4800, "{} ms left", left);
+
+    // A deadline shorter than the timeout of the client expires on the server.
+    spawn(rpc_driver.run());
+    let rpc = rpc.timeout(Duration::from_secs(5));
+    let reply = rpc.call(vec!["sleep", "deadline: 50", "500"]).await?;
+    assert_eq!(reply, call_error("deadline-exceeded", ""));
+
+    // The server gave up on the handler instead of keeping a slot busy until it completed.
+    let started = std::time::Instant::now();
+    let late = client.call::<_, u64>("sleep", &10).await?;
+    assert_eq!(late, 10);
+    assert!(started.elapsed() < Duration::from_millis(400));
+    Ok(())
+}
+
+/// The reply of the server to a call that failed with `code` and `message`.
+fn call_error(code: &str, message: &str) -> Multipart {
+    vec!["error".into(), code.into(), message.into()]
+}
@@ -0,0 +1,57 @@
+use std::pin::Pin;
+
+use async_zmq::{
+    compat, pull, push, Message, Multipart, MultipartIter, Result, SendError, Sink, SinkExt,
+    StreamExt,
+};
+
+#[async_std::test]
+async fn sink_accepts_every_item_type() -> Result<()> {
+    let uri = "inproc://sink_items";
+    let mut push = push(uri)?.bind()?;
+    let mut pull = pull(uri)?.connect()?;
+
+    push.send(vec!["vec"]).await?;
+    push.send(Message::from("message")).await?;
+    push.send(MultipartIter(vec!["iter"].into_iter())).await?;
+    push.send(Multipart::from([Message::from("multipart")]))
+        .await?;
+
+    for expected in ["vec", "message", "iter", "multipart"] {
+        let recv = pull.next().await.unwrap()?;
+        assert_eq!(recv[0].as_str(), Some(expected));
+    }
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn different_sockets_share_a_collection() -> Result<()> {
+    let mut senders: Vec<Pin<Box<dyn Sink<Multipart, Error = SendError>>>> = vec![
+        Box::pin(push("inproc://sink_collection_push")?.bind()?),
+        Box::pin(async_zmq::publish("inproc://sink_collection_pub")?.bind()?),
+    ];
+    let mut pull = pull("inproc://sink_collection_push")?.connect()?;
+
+    for sender in senders.iter_mut() {
+        sender.send(vec![Message::from("Hello")]).await?;
+    }
+    let recv = pull.next().await.unwrap()?;
+    assert_eq!(recv[0].as_str(), Some("Hello"));
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn compat_sockets_infer_item_type() -> Result<()> {
+    let uri = "inproc://sink_compat";
+    let mut push = compat::push::<std::vec::IntoIter<&str>, &str>(uri)?.bind()?;
+    let mut pull = pull(uri)?.connect()?;
+
+    push.send(vec!["Hello", "World"].into()).await?;
+    let recv = pull.next().await.unwrap()?;
+    assert_eq!(recv[1].as_str(), Some("World"));
+    assert_eq!(push.queued(), 0);
+
+    Ok(())
+}
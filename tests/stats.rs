@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+use async_std::task::{sleep, spawn};
+use async_zmq::{pull, push, Result, SinkExt, StreamExt};
+
+#[async_std::test]
+async fn stats_count_traffic() -> Result<()> {
+    let mut pull = pull("inproc://stats")?.bind()?;
+    let mut push = push("inproc://stats")?.connect()?;
+
+    let receiver = spawn(async move {
+        for _ in 0..3 {
+            pull.next().await.expect("stream ended")?;
+        }
+        Ok::<_, async_zmq::Error>(pull.stats())
+    });
+
+    sleep(Duration::from_millis(100)).await;
+    for _ in 0..3 {
+        push.send(vec!["topic", "hello"]).await?;
+    }
+    let received = receiver.await?;
+
+    let sent = push.stats();
+    assert_eq!(sent.messages_sent, 3);
+    assert_eq!(sent.frames_sent, 6);
+    assert_eq!(sent.bytes_sent, 30);
+    assert_eq!(sent.messages_received, 0);
+
+    assert_eq!(received.messages_received, 3);
+    assert_eq!(received.frames_received, 6);
+    assert_eq!(received.bytes_received, 30);
+    assert!(received.would_block > 0);
+    assert!(received.pending >= Duration::from_millis(50));
+    assert!(received.wakeups > 0);
+
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use async_std::future::timeout;
+use async_std::net::TcpStream;
+use async_std::task;
+use async_zmq::{stream, stream::Incoming, Result, StreamExt, ZmqSocketExt};
+use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+/// Bind a STREAM socket to an ephemeral port, returning its connections and its address.
+fn listen() -> Result<(Incoming, String)> {
+    let stream = stream("tcp://127.0.0.1:*")?.bind()?;
+    let endpoint = stream.last_endpoint()?.expect("no endpoint");
+    let address = endpoint.trim_start_matches("tcp://").to_owned();
+    Ok((stream.incoming(), address))
+}
+
+#[async_std::test]
+async fn stream_connection_reads_and_writes_tcp() -> Result<()> {
+    let (mut incoming, address) = listen()?;
+
+    let mut client = TcpStream::connect(address).await.unwrap();
+    let mut connection = incoming.next().await.unwrap()?;
+    assert!(!connection.routing_id().is_empty());
+
+    client.write_all(b"ping").await.unwrap();
+    let mut buf = [0; 4];
+    connection.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"ping");
+
+    connection.write_all(b"pong").await.unwrap();
+    client.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"pong");
+
+    // Dropping the client disconnects the connection, which then reads to the end
+    drop(client);
+    let mut rest = Vec::new();
+    connection.read_to_end(&mut rest).await.unwrap();
+    assert!(rest.is_empty());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn closing_stream_connection_disconnects_peer() -> Result<()> {
+    let (mut incoming, address) = listen()?;
+
+    let mut client = TcpStream::connect(address).await.unwrap();
+    let mut connection = incoming.next().await.unwrap()?;
+    // ØMQ only finishes closing the connection while the socket is being polled
+    async_std::task::spawn(async move { while incoming.next().await.is_some() {} });
+
+    connection.write_all(b"bye").await.unwrap();
+    connection.close().await.unwrap();
+
+    let mut received = Vec::new();
+    client.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, b"bye");
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn overflowing_connection_is_closed_without_stalling_others() -> Result<()> {
+    let (incoming, address) = listen()?;
+    let mut incoming = incoming.max_buffered(1);
+
+    let mut first = TcpStream::connect(&address).await.unwrap();
+    let mut reader = incoming.next().await.unwrap()?;
+    first.write_all(b"one").await.unwrap();
+    // Reading nothing waits until "one" fills the buffer, without taking it off.
+    assert_eq!(reader.read(&mut []).await.unwrap(), 0);
+
+    // The full buffer of the first connection holds up neither new nor other connections.
+    let mut second = TcpStream::connect(&address).await.unwrap();
+    let mut other = timeout(Duration::from_secs(5), incoming.next())
+        .await
+        .expect("the second connection was never accepted")
+        .unwrap()?;
+    second.write_all(b"hi").await.unwrap();
+    let mut buf = [0; 2];
+    timeout(Duration::from_secs(5), other.read_exact(&mut buf))
+        .await
+        .expect("the second connection was stalled by the first")
+        .unwrap();
+    assert_eq!(&buf, b"hi");
+
+    // Sending more than the first connection buffers closes it.
+    task::spawn(async move { while incoming.next().await.is_some() {} });
+    first.write_all(b"two").await.unwrap();
+    let mut rest = Vec::new();
+    timeout(Duration::from_secs(5), first.read_to_end(&mut rest))
+        .await
+        .expect("the overflowing connection was never closed")
+        .unwrap();
+    assert!(rest.is_empty());
+
+    let mut buf = [0; 3];
+    reader.read_exact(&mut buf).await.unwrap();
+    assert_eq!(&buf, b"one");
+    let err = reader.read(&mut buf).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionAborted);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn writing_to_slow_reader_waits_at_high_water_mark() -> Result<()> {
+    let stream = stream("tcp://127.0.0.1:*")?.bind()?;
+    let endpoint = stream.last_endpoint()?.expect("no endpoint");
+    // The pipe of a connection is created with the options at the time it is accepted.
+    stream.as_raw_socket().set_sndhwm(1)?;
+    stream.as_raw_socket().set_sndbuf(4096)?;
+    let mut incoming = stream.incoming();
+
+    let mut client = TcpStream::connect(endpoint.trim_start_matches("tcp://"))
+        .await
+        .unwrap();
+    let mut connection = incoming.next().await.unwrap()?;
+
+    // Write until the TCP buffers and the pipe of the connection are full.
+    let chunk = vec![7; 64 * 1024];
+    let mut written = 0;
+    let mut blocked = false;
+    for _ in 0..1024 {
+        match futures::poll!(connection.write(&chunk)) {
+            std::task::Poll::Ready(len) => written += len.unwrap(),
+            std::task::Poll::Pending => {
+                blocked = true;
+                break;
+            }
+        }
+    }
+    assert!(blocked, "the connection never reached its high-water mark");
+
+    // Everything written so far and the chunk that had to wait arrive once the client reads.
+    let mut received = vec![0; written + chunk.len()];
+    let (read, resumed) = timeout(
+        Duration::from_secs(10),
+        futures::future::join(
+            client.read_exact(&mut received),
+            connection.write_all(&chunk),
+        ),
+    )
+    .await
+    .expect("the waiting write never resumed");
+    read.unwrap();
+    resumed.unwrap();
+    assert!(received.iter().all(|&byte| byte == 7));
+
+    Ok(())
+}
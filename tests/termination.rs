@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use async_zmq::{pull, push, subscribe, ContextHandle, Result, SinkExt, StreamExt};
+use futures::stream::FusedStream;
+
+#[async_std::test]
+async fn streams_end_on_shutdown_async_std() -> Result<()> {
+    let handle = ContextHandle::new();
+    let mut subscribe = subscribe("inproc://terminate_async_std")?
+        .with_handle(&handle)
+        .bind()?;
+    subscribe.set_subscribe("")?;
+
+    let receiver = async_std::task::spawn(async move {
+        while let Some(msg) = subscribe.next().await {
+            msg?;
+        }
+        assert!(subscribe.is_terminated());
+        assert!(subscribe.next().await.is_none());
+        Ok::<_, async_zmq::RecvError>(())
+    });
+
+    async_std::future::timeout(Duration::from_secs(5), handle.shutdown())
+        .await
+        .expect("shutdown never completed");
+    receiver.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn streams_end_on_shutdown_tokio() -> Result<()> {
+    let handle = ContextHandle::new();
+    let mut pull = pull("inproc://terminate_tokio")?
+        .with_handle(&handle)
+        .bind()?;
+
+    let receiver = tokio::spawn(async move {
+        let mut received = 0;
+        while let Some(msg) = pull.next().await {
+            msg?;
+            received += 1;
+        }
+        Ok::<_, async_zmq::RecvError>(received)
+    });
+
+    let mut push = push("inproc://terminate_tokio")?
+        .with_handle(&handle)
+        .connect()?;
+    push.send(vec!["last"]).await?;
+    drop(push);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    tokio::time::timeout(Duration::from_secs(5), handle.shutdown())
+        .await
+        .expect("shutdown never completed");
+    assert_eq!(receiver.await.unwrap()?, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn shutdown_signal_ends_take_until() -> Result<()> {
+    let handle = ContextHandle::new();
+    let pull = pull("inproc://terminate_signal")?
+        .with_handle(&handle)
+        .bind()?;
+
+    // The consumer stops as soon as shutdown starts, before its socket is interrupted
+    let consumer = tokio::spawn(pull.take_until(handle.shutdown_signal()).count());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    tokio::time::timeout(Duration::from_secs(5), handle.shutdown())
+        .await
+        .expect("shutdown never completed");
+    assert_eq!(consumer.await.unwrap(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "testing")]
+
+use async_zmq::testing::{MockDealer, MockPublish, MockRequest, MockStream, MockSubscribe};
+use async_zmq::{RecvErrorKind, RequestReplyErrorKind, SendErrorKind, SinkExt, StreamExt};
+use futures::{poll, stream::FusedStream};
+
+#[async_std::test]
+async fn mock_records_sent_and_yields_injected() {
+    let (mut dealer, handle) = MockDealer::new();
+    handle.inject(vec!["first"]);
+    handle.inject(vec!["second", "frame"]);
+
+    assert_eq!(dealer.next().await.unwrap().unwrap(), vec!["first".into()]);
+    dealer.send(vec!["reply"]).await.unwrap();
+    assert_eq!(
+        dealer.next().await.unwrap().unwrap(),
+        vec!["second".into(), "frame".into()]
+    );
+
+    assert_eq!(handle.take_sent(), vec![vec!["reply".into()]]);
+    assert_eq!(handle.sent_len(), 0);
+}
+
+#[async_std::test]
+async fn mock_simulates_back_pressure_and_errors() {
+    let (mut dealer, handle) = MockDealer::new();
+    handle.set_high_water_mark(Some(1));
+    dealer.send(vec!["one"]).await.unwrap();
+    let mut send = dealer.send(vec!["two"]);
+    assert!(poll!(&mut send).is_pending());
+    handle.take_sent();
+    send.await.unwrap();
+
+    handle.set_high_water_mark(None);
+    handle.set_blocked(true);
+    let mut send = dealer.send(vec!["three"]);
+    assert!(poll!(&mut send).is_pending());
+    handle.set_blocked(false);
+    send.await.unwrap();
+
+    handle.fail_next_send(SendErrorKind::Interrupted);
+    assert!(matches!(
+        dealer.send(vec!["four"]).await.map_err(|e| e.kind()),
+        Err(SendErrorKind::Interrupted)
+    ));
+    handle.fail_next_recv(RecvErrorKind::Interrupted);
+    assert!(matches!(
+        dealer.next().await.map(|r| r.map_err(|e| e.kind())),
+        Some(Err(RecvErrorKind::Interrupted))
+    ));
+}
+
+#[async_std::test]
+async fn mock_simulates_disconnect_and_termination() {
+    let (mut publish, handle) = MockPublish::new();
+    handle.disconnect();
+    publish.send(vec!["lost"]).await.unwrap();
+    handle.reconnect();
+    publish.send(vec!["kept"]).await.unwrap();
+    assert_eq!(handle.dropped(), 1);
+    assert_eq!(handle.next_sent().await, Some(vec!["kept".into()]));
+
+    let (mut dealer, handle) = MockDealer::new();
+    handle.disconnect();
+    let mut send = dealer.send(vec!["waiting"]);
+    assert!(poll!(&mut send).is_pending());
+    assert_eq!(handle.sent_len(), 0);
+    handle.reconnect();
+    send.await.unwrap();
+    assert_eq!(handle.next_sent().await, Some(vec!["waiting".into()]));
+
+    handle.terminate();
+    assert!(dealer.next().await.is_none());
+    assert!(dealer.is_terminated());
+    assert!(matches!(
+        dealer.send(vec!["late"]).await.map_err(|e| e.kind()),
+        Err(SendErrorKind::ContextTerminated)
+    ));
+    drop(dealer);
+    assert_eq!(handle.next_sent().await, None);
+}
+
+#[async_std::test]
+async fn mock_subscribe_filters_by_topic() {
+    let (mut subscribe, handle) = MockSubscribe::new();
+    subscribe.set_subscribe("weather").unwrap();
+    handle.inject(vec!["sports", "goal"]);
+    handle.inject(vec!["weather", "rain"]);
+
+    assert_eq!(
+        subscribe.next().await.unwrap().unwrap(),
+        vec!["weather".into(), "rain".into()]
+    );
+    assert_eq!(handle.subscriptions(), vec![b"weather".to_vec()]);
+}
+
+#[async_std::test]
+async fn mock_request_alternates() {
+    let (request, handle) = MockRequest::new();
+    request.send(vec!["ping"]).await.unwrap();
+    assert!(matches!(
+        request.send(vec!["again"]).await.map_err(|e| e.kind()),
+        Err(RequestReplyErrorKind::AwaitingReply)
+    ));
+    handle.inject(vec!["pong"]);
+    assert_eq!(request.recv().await.unwrap(), vec!["pong".into()]);
+    assert_eq!(handle.take_sent(), vec![vec!["ping".into()]]);
+}
+
+#[async_std::test]
+async fn mock_stream_keeps_routing_ids() {
+    let (mut stream, handle) = MockStream::new();
+    handle.set_high_water_mark(Some(1));
+    handle.inject(vec!["peer", ""]);
+    handle.inject(vec!["peer", "GET / HTTP/1.0\r\n\r\n"]);
+
+    assert_eq!(
+        stream.next().await.unwrap().unwrap(),
+        vec!["peer".into(), "".into()]
+    );
+    assert_eq!(
+        stream.next().await.unwrap().unwrap(),
+        vec!["peer".into(), "GET / HTTP/1.0\r\n\r\n".into()]
+    );
+    assert!(poll!(stream.next()).is_pending());
+
+    stream.send(vec!["peer", "HTTP/1.0 200 OK"]).await.unwrap();
+    let mut send = stream.send(vec!["peer", ""]);
+    assert!(poll!(&mut send).is_pending());
+    assert_eq!(
+        handle.next_sent().await,
+        Some(vec!["peer".into(), "HTTP/1.0 200 OK".into()])
+    );
+    send.await.unwrap();
+    assert_eq!(handle.take_sent(), vec![vec!["peer".into(), "".into()]]);
+}
@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_std::task::sleep;
+use async_zmq::{publish, pull, push, subscribe, Message, Result, SinkExt, StreamExt, TraceParent};
+
+const HEADER: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+#[test]
+fn traceparent_parses_and_formats() {
+    let context: TraceParent = HEADER.parse().unwrap();
+    assert_eq!(context.trace_id(), 0x4bf92f3577b34da6a3ce929d0e0e4736);
+    assert_eq!(context.parent_id(), 0x00f067aa0ba902b7);
+    assert!(context.is_sampled());
+    assert_eq!(context.to_string(), HEADER);
+
+    for invalid in [
+        "",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+    ] {
+        assert!(invalid.parse::<TraceParent>().is_err(), "{}", invalid);
+    }
+    assert!(
+        "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra"
+            .parse::<TraceParent>()
+            .is_ok()
+    );
+}
+
+#[async_std::test]
+async fn trace_context_travels_in_header_frame() -> Result<()> {
+    let mut pull = pull("inproc://traceparent")?.trace_context().bind()?;
+    let mut traced = push("inproc://traceparent")?
+        .trace_context_with(|| HEADER.parse().ok())
+        .connect()?;
+    let mut plain = push("inproc://traceparent")?.connect()?;
+
+    traced.send(vec!["hello"]).await?;
+    let msg = pull.next().await.unwrap()?;
+    assert_eq!(msg, vec![Message::from("hello")]);
+    assert_eq!(pull.last_trace_context(), HEADER.parse().ok());
+
+    plain.send(vec!["world"]).await?;
+    let msg = pull.next().await.unwrap()?;
+    assert_eq!(msg, vec![Message::from("world")]);
+    assert_eq!(pull.last_trace_context(), None);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn trace_context_keeps_topic_first() -> Result<()> {
+    let mut publish = publish("inproc://traceparent_topic")?
+        .trace_context_with(|| HEADER.parse().ok())
+        .bind()?;
+    let mut subscribe = subscribe("inproc://traceparent_topic")?
+        .trace_context()
+        .connect()?;
+    subscribe.set_subscribe("topic")?;
+    sleep(Duration::from_millis(100)).await;
+
+    publish.send(vec!["other", "dropped"]).await?;
+    publish.send(vec!["topic", "kept"]).await?;
+    let msg = subscribe.next().await.unwrap()?;
+    assert_eq!(msg, vec![Message::from("topic"), Message::from("kept")]);
+    assert_eq!(subscribe.last_trace_context(), HEADER.parse().ok());
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn trace_context_is_returned_per_message() -> Result<()> {
+    let mut pull = pull("inproc://traceparent_per_message")?
+        .trace_context()
+        .bind()?;
+    // Every other message goes out without a context.
+    let traced = AtomicBool::new(false);
+    let mut push = push("inproc://traceparent_per_message")?
+        .trace_context_with(move || {
+            let traced = !traced.fetch_xor(true, Ordering::Relaxed);
+            traced.then(|| HEADER.parse().unwrap())
+        })
+        .connect()?;
+
+    for msg in ["one", "two", "three", "four", "five"] {
+        push.send(vec![msg]).await?;
+    }
+
+    assert_eq!(
+        pull.recv_traced().await?,
+        (vec![Message::from("one")], HEADER.parse().ok())
+    );
+
+    let mut received = Vec::new();
+    let mut batches = pull.recv_batch(4).traced();
+    while received.len() < 4 {
+        received.extend(batches.next().await.unwrap()?);
+    }
+    assert_eq!(
+        received,
+        vec![
+            (vec![Message::from("two")], None),
+            (vec![Message::from("three")], HEADER.parse().ok()),
+            (vec![Message::from("four")], None),
+            (vec![Message::from("five")], HEADER.parse().ok()),
+        ]
+    );
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn batches_without_trace_context_pair_messages_with_none() -> Result<()> {
+    let mut pull = pull("inproc://traceparent_untraced")?.bind()?;
+    let mut push = push("inproc://traceparent_untraced")?
+        .trace_context_with(|| HEADER.parse().ok())
+        .connect()?;
+
+    push.send(vec!["hello"]).await?;
+    let batch = pull.recv_batch(1).traced().next().await.unwrap()?;
+    // The socket does not handle the header frame, so it is received as part of the message.
+    assert_eq!(batch.len(), 1);
+    assert_eq!(batch[0].0.len(), 2);
+    assert_eq!(batch[0].1, None);
+
+    Ok(())
+}
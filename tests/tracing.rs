@@ -0,0 +1,86 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use async_zmq::{pull, push, Result, SinkExt, StreamExt};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Collects the fields of every event.
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<Fields>>>);
+
+/// The fields of an event, as their name and debug formatted value.
+#[derive(Debug, Default)]
+struct Fields(Vec<(&'static str, String)>);
+
+impl Fields {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push((field.name(), format!("{:?}", value)));
+    }
+}
+
+impl Subscriber for Events {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.0.lock().unwrap().push(fields);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[async_std::test]
+async fn sockets_emit_events() -> Result<()> {
+    let events = Events::default();
+    let _guard = tracing::subscriber::set_default(events.clone());
+
+    let mut pull = pull("inproc://tracing")?.bind()?;
+    let mut push = push("inproc://tracing")?.connect()?;
+    push.send(vec!["hello", "world"]).await?;
+    pull.next().await.expect("stream ended")?;
+
+    let events = events.0.lock().unwrap();
+    let event = |message: &str| {
+        events
+            .iter()
+            .find(|fields| fields.get("message") == Some(message))
+            .unwrap_or_else(|| panic!("no {:?} event in {:?}", message, events))
+    };
+    event("registered I/O handle");
+    event("attached");
+
+    // Traffic events name the socket they happened on.
+    for (message, socket_type) in [("sent message", "PUSH"), ("received message", "PULL")] {
+        let fields = event(message);
+        assert_eq!(fields.get("socket_type"), Some(socket_type));
+        assert!(fields.get("token").is_some(), "no token in {:?}", fields);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use async_zmq::{
+    pull, push, reply, request, subscribe, xpublish, Multipart, Operation, RecvError, Result,
+    SocketType, ZmqRecv, ZmqSend, ZmqSocketExt,
+};
+
+async fn round_trip<S, R>(sender: &mut S, receiver: &mut R, msg: &str) -> Result<Multipart>
+where
+    S: ZmqSend,
+    R: ZmqRecv,
+{
+    sender.send_msg(vec![msg]).await?;
+    Ok(receiver.recv_msg().await?)
+}
+
+#[async_std::test]
+async fn traits_send_and_receive_generically() -> Result<()> {
+    let mut pull = pull("inproc://traits_pipeline")?.bind()?;
+    let mut push = push("inproc://traits_pipeline")?.connect()?;
+    let msg = round_trip(&mut push, &mut pull, "hello").await?;
+    assert_eq!(msg, vec!["hello".into()]);
+
+    let mut rep = reply("inproc://traits_reqrep")?.bind()?;
+    let mut req = request("inproc://traits_reqrep")?.connect()?;
+    let msg = round_trip(&mut req, &mut rep, "ping").await?;
+    assert_eq!(msg, vec!["ping".into()]);
+    let msg = round_trip(&mut rep, &mut req, "pong").await?;
+    assert_eq!(msg, vec!["pong".into()]);
+
+    Ok(())
+}
+
+#[async_std::test]
+async fn xpublish_yields_recv_errors() -> Result<()> {
+    let mut xpub = xpublish("inproc://traits_xpub")?.bind()?;
+    let sub = subscribe("inproc://traits_xpub")?.connect()?;
+    sub.set_subscribe("topic")?;
+
+    let subscription: std::result::Result<_, RecvError> = xpub.recv_msg().await;
+    let subscription = subscription?;
+    assert_eq!(subscription, vec![b"\x01topic".to_vec().into()]);
+    Ok(())
+}
+
+#[async_std::test]
+async fn socket_ext_manages_endpoints_and_options() -> Result<()> {
+    let pull = pull("inproc://traits_endpoints")?.bind()?;
+    assert_eq!(ZmqSocketExt::socket_type(&pull), SocketType::PULL);
+
+    pull.bind("tcp://127.0.0.1:*")?;
+    let endpoint = pull.last_endpoint()?.expect("no endpoint");
+    assert!(endpoint.starts_with("tcp://127.0.0.1:"));
+    pull.unbind(&endpoint)?;
+
+    let err = pull.disconnect("tcp://127.0.0.1:1").unwrap_err();
+    assert_eq!(err.operation(), Some(Operation::Disconnect));
+    assert_eq!(err.socket_type(), Some(SocketType::PULL));
+    assert!(err
+        .to_string()
+        .starts_with("disconnecting PULL socket from tcp://127.0.0.1:1: "));
+
+    pull.set_linger(None)?;
+    assert_eq!(pull.linger()?, None);
+    pull.set_recv_hwm(10)?;
+    assert_eq!(pull.recv_hwm()?, 10);
+    Ok(())
+}
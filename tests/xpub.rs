@@ -1,13 +1,14 @@
-use async_zmq::{subscribe, xpublish, Context, Message, Result, StreamExt};
-use std::vec::IntoIter;
+use async_zmq::{
+    subscribe, xpublish,
+    xpublish::{SubscriptionEvent, SubscriptionKind},
+    Context, Result, StreamExt,
+};
 
 #[async_std::test]
 async fn xpublish_subscribe() -> Result<()> {
     let uri = "inproc://xpub_xsub";
     let context = Context::new();
-    let mut xpublish = xpublish::<IntoIter<Message>, Message>(uri)?
-        .with_context(&context)
-        .bind()?;
+    let mut xpublish = xpublish(uri)?.with_context(&context).bind()?;
     let subscribe = subscribe(uri)?.with_context(&context).connect()?;
 
     let topic = "Topic";
@@ -23,3 +24,40 @@ async fn xpublish_subscribe() -> Result<()> {
 
     Ok(())
 }
+
+#[async_std::test]
+async fn xpublish_subscription_events() -> Result<()> {
+    let uri = "inproc://xpub_subscriptions";
+    let mut xpublish = xpublish(uri)?.bind()?;
+    xpublish.set_verbose(true)?;
+    xpublish.set_welcome_msg(Some("welcome"))?;
+
+    let mut first = subscribe(uri)?.connect()?;
+    let second = subscribe(uri)?.connect()?;
+    first.set_subscribe("")?;
+    second.set_subscribe("")?;
+
+    // Verbose reports the second subscription to the same topic as well
+    let mut subscriptions = xpublish.subscriptions();
+    for _ in 0..2 {
+        let event = subscriptions.next().await.unwrap()?;
+        assert_eq!(
+            event,
+            SubscriptionEvent {
+                kind: SubscriptionKind::Subscribe,
+                topic: Vec::new(),
+            }
+        );
+    }
+
+    // The welcome message went out once the socket took the peers in
+    let welcome = first.next().await.unwrap()?;
+    assert_eq!(welcome[0].as_str(), Some("welcome"));
+
+    first.set_unsubscribe("")?;
+    second.set_unsubscribe("")?;
+    let event = subscriptions.next().await.unwrap()?;
+    assert_eq!(event.kind, SubscriptionKind::Unsubscribe);
+
+    Ok(())
+}